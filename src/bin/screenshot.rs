@@ -0,0 +1,115 @@
+// Plays a game without a window and saves its last frame, e.g.
+// cargo run --bin screenshot -- --seed 7 --ticks 600 --output frame.png
+use rust_sand_tetris::{
+    constants::{animation_constants::HEADLESS_FRAME_TIME, TetrisConstants},
+    controllers::game_controller::GameController,
+    objects::{piece_set::PieceSet, puzzle::Puzzle},
+    renderers::framebuffer_renderer::FramebufferRenderer,
+    utils::{
+        block_on::block_on,
+        clock::FakeClock,
+        launch_args::{parse_launch_args, GameSetup, LaunchArgs},
+        tetris_rng::{ScriptedTetrisRng, SeededTetrisRng, TetrisRng, ThreadTetrisRng},
+    },
+};
+
+const DEFAULT_TICKS: u32 = 300;
+const DEFAULT_OUTPUT: &str = "screenshot.png";
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let launch_args: LaunchArgs =
+        parse_launch_args(&get_game_args(&args)).unwrap_or_else(|message| exit_with(&message));
+    let ticks: u32 = match get_flag_value(&args, "--ticks") {
+        Some(value) => value
+            .parse()
+            .unwrap_or_else(|_| exit_with(&format!("Invalid tick count: {}", value))),
+        None => DEFAULT_TICKS,
+    };
+    let output = get_flag_value(&args, "--output").unwrap_or(DEFAULT_OUTPUT);
+    let GameSetup {
+        constants: tetris_constants,
+        piece_set,
+        puzzle,
+        script,
+        ..
+    } = launch_args
+        .load_game_setup()
+        .unwrap_or_else(|message| exit_with(&message));
+
+    let renderer = match launch_args.seed {
+        Some(seed) => render_game(
+            &mut ScriptedTetrisRng::new(SeededTetrisRng::new(seed), script),
+            &tetris_constants,
+            piece_set,
+            puzzle,
+            ticks,
+        ),
+        None => render_game(
+            &mut ScriptedTetrisRng::new(ThreadTetrisRng::new(), script),
+            &tetris_constants,
+            piece_set,
+            puzzle,
+            ticks,
+        ),
+    };
+    renderer
+        .save_png(output)
+        .unwrap_or_else(|message| exit_with(&message));
+}
+
+fn render_game<R: TetrisRng>(
+    rng: &mut R,
+    tetris_constants: &TetrisConstants,
+    piece_set: PieceSet,
+    puzzle: Option<Puzzle>,
+    ticks: u32,
+) -> FramebufferRenderer {
+    let mut renderer = FramebufferRenderer::new(
+        tetris_constants.map_width * tetris_constants.grain_side_size,
+        tetris_constants.map_height * tetris_constants.grain_side_size,
+    );
+    let clock = FakeClock::new(); // Effects look the same whatever the machine's speed
+    let mut gc: GameController<R, FramebufferRenderer, FakeClock> =
+        GameController::new(rng, &mut renderer, &clock, tetris_constants);
+    gc.set_piece_set(piece_set);
+    if let Some(puzzle) = puzzle {
+        gc.set_puzzle(puzzle);
+    }
+    gc.reset_game();
+
+    for _ in 0..ticks {
+        block_on(gc.tick());
+        clock.advance(HEADLESS_FRAME_TIME);
+    }
+
+    renderer
+}
+
+fn get_flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|arg| arg == flag)
+        .and_then(|index| args.get(index + 1))
+        .map(|value| value.as_str())
+}
+
+// Everything but the screenshot's own flags, which the game would reject
+fn get_game_args(args: &[String]) -> Vec<String> {
+    let mut game_args = Vec::new();
+    let mut args_iter = args.iter();
+    while let Some(arg) = args_iter.next() {
+        match arg.as_str() {
+            "--ticks" | "--output" => {
+                args_iter.next(); // Its value
+            }
+            _ => game_args.push(arg.clone()),
+        }
+    }
+
+    game_args
+}
+
+fn exit_with(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1);
+}
@@ -0,0 +1,170 @@
+// Plays the game inside a terminal with 24-bit colors, e.g. over SSH
+// cargo run --bin terminal -- --seed 7
+use std::{
+    io::{stdin, stdout, Read, Stdout, Write},
+    process::Command,
+    sync::mpsc::{channel, Receiver},
+    thread,
+};
+
+use rust_sand_tetris::{
+    constants::{mode_constants::HIGH_SCORES_PATH, TetrisConstants},
+    controllers::game_controller::GameController,
+    objects::{high_scores::HighScores, piece_set::PieceSet, puzzle::Puzzle},
+    renderers::terminal_renderer::TerminalRenderer,
+    utils::{
+        block_on::block_on,
+        clock::SystemClock,
+        launch_args::{parse_launch_args, GameSetup, LaunchArgs},
+        terminal_input::{parse_terminal_input, TerminalCommand},
+        tetris_rng::{ScriptedTetrisRng, SeededTetrisRng, TetrisRng, ThreadTetrisRng},
+    },
+};
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let launch_args: LaunchArgs =
+        parse_launch_args(&args).unwrap_or_else(|message| exit_with(&message));
+    let GameSetup {
+        constants: tetris_constants,
+        piece_set,
+        puzzle,
+        script,
+        high_scores,
+    } = launch_args
+        .load_game_setup()
+        .unwrap_or_else(|message| exit_with(&message));
+
+    let raw_mode = enter_raw_mode().unwrap_or_else(|message| exit_with(&message));
+    let input = spawn_input_reader();
+    let save_error = match launch_args.seed {
+        Some(seed) => run_game(
+            &mut ScriptedTetrisRng::new(SeededTetrisRng::new(seed), script),
+            &tetris_constants,
+            piece_set,
+            puzzle,
+            high_scores,
+            &input,
+        ),
+        None => run_game(
+            &mut ScriptedTetrisRng::new(ThreadTetrisRng::new(), script),
+            &tetris_constants,
+            piece_set,
+            puzzle,
+            high_scores,
+            &input,
+        ),
+    };
+    drop(raw_mode);
+    if let Some(message) = save_error {
+        eprintln!("{}", message);
+    }
+}
+
+// Gives the terminal back its saved state when dropped, also when the game panics
+struct RawMode {
+    saved_state: String,
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        leave_raw_mode(&self.saved_state);
+    }
+}
+
+fn run_game<R: TetrisRng>(
+    rng: &mut R,
+    tetris_constants: &TetrisConstants,
+    piece_set: PieceSet,
+    puzzle: Option<Puzzle>,
+    mut high_scores: HighScores,
+    input: &Receiver<Vec<u8>>,
+) -> Option<String> {
+    let mut renderer: TerminalRenderer<Stdout> = TerminalRenderer::new(
+        stdout(),
+        tetris_constants.map_width,
+        tetris_constants.map_height,
+    );
+    let clock = SystemClock::new();
+    let mut gc: GameController<R, TerminalRenderer<Stdout>, SystemClock> =
+        GameController::new(rng, &mut renderer, &clock, tetris_constants);
+    gc.set_piece_set(piece_set);
+    if let Some(puzzle) = puzzle {
+        gc.set_puzzle(puzzle);
+    }
+    gc.set_high_scores(high_scores.clone());
+    gc.reset_game();
+    let mut save_error = None; // Printing would garble the raw terminal, so only the last is kept
+
+    loop {
+        block_on(gc.tick());
+        if *gc.get_high_scores() != high_scores {
+            high_scores = gc.get_high_scores().clone();
+            if let Err(message) = high_scores.save(HIGH_SCORES_PATH) {
+                save_error = Some(message);
+            }
+        }
+        for bytes in input.try_iter() {
+            for command in parse_terminal_input(&bytes) {
+                match command {
+                    TerminalCommand::Move(key) => gc.do_move(key),
+                    TerminalCommand::ToggleCursor => gc.toggle_cursor(),
+                    TerminalCommand::Restart => gc.reset_game(),
+                    TerminalCommand::Quit => return save_error,
+                }
+            }
+        }
+    }
+}
+
+fn spawn_input_reader() -> Receiver<Vec<u8>> {
+    let (sender, receiver) = channel();
+    thread::spawn(move || {
+        let mut buffer = [0; 16];
+        while let Ok(read) = stdin().read(&mut buffer) {
+            if read == 0 || sender.send(buffer[..read].to_vec()).is_err() {
+                break;
+            }
+        }
+    });
+
+    receiver
+}
+
+fn enter_raw_mode() -> Result<RawMode, String> {
+    let raw_mode = RawMode {
+        saved_state: run_stty(&["-g"])?.trim().to_string(),
+    };
+    run_stty(&["raw", "-echo"])?;
+    print!("\x1b[2J\x1b[?25l"); // Clear the screen and hide the cursor
+    let _ = stdout().flush();
+
+    Ok(raw_mode)
+}
+
+fn leave_raw_mode(saved_state: &str) {
+    let _ = run_stty(&[saved_state]);
+    print!("\x1b[0m\x1b[?25h\r\n");
+    let _ = stdout().flush();
+}
+
+fn run_stty(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(std::process::Stdio::inherit())
+        .output()
+        .map_err(|error| format!("stty: {}", error))?;
+    if !output.status.success() {
+        return Err(format!(
+            "stty: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+fn exit_with(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1);
+}
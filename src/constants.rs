@@ -1,83 +1,175 @@
-pub(crate) mod window_constants {
-    pub(crate) const WINDOW_TITLE: &str = "Sand Tetris";
-    pub(crate) const WINDOW_WIDTH: i32 = 500;
-    pub(crate) const WINDOW_HEIGHT: i32 = 800;
-}
+use crate::objects::{
+    color_family::ColorWeights,
+    game_mode::GameMode,
+    garbage::Garbage,
+    matching_rules::MatchingRules,
+    piece_set::PieceSet,
+    puzzle::Puzzle,
+    settle_rules::{ImpactMode, LockDelay, SettlePolicy},
+    theme::Theme,
+};
 
-pub(crate) mod map_constants {
-    use super::window_constants;
+pub mod window_constants {
+    use super::map_constants::{GRAIN_SIDE_SIZE, MAP_HEIGHT, MAP_WIDTH};
 
-    pub(crate) const GRAIN_SIDE_SIZE: i32 = 5;
-    pub(crate) const MAP_WIDTH: i32 = {
-        assert!(
-            window_constants::WINDOW_WIDTH % GRAIN_SIDE_SIZE == 0,
-            "WINDOW_WIDTH is not divisible by GRAIN_SIDE_SIZE"
-        );
-        window_constants::WINDOW_WIDTH / GRAIN_SIDE_SIZE
-    };
-    pub(crate) const MAP_HEIGHT: i32 = {
-        assert!(
-            window_constants::WINDOW_HEIGHT % GRAIN_SIDE_SIZE == 0,
-            "WINDOW_HEIGHT is not divisible by GRAIN_SIDE_SIZE"
-        );
-        window_constants::WINDOW_HEIGHT / GRAIN_SIDE_SIZE
-    };
+    pub const WINDOW_TITLE: &str = "Sand Tetris";
+    // Starting size only, the window can be resized and the map is scaled to fit it
+    pub const WINDOW_WIDTH: i32 = MAP_WIDTH * GRAIN_SIDE_SIZE;
+    pub const WINDOW_HEIGHT: i32 = MAP_HEIGHT * GRAIN_SIDE_SIZE;
+}
+
+pub mod map_constants {
+    pub const GRAIN_SIDE_SIZE: i32 = 5; // In pixels at the starting window size
+    pub const MAP_WIDTH: i32 = 100;
+    pub const MAP_HEIGHT: i32 = 160;
 }
 
-pub(crate) mod colors {
+pub mod colors {
     use macroquad::color::Color;
 
-    pub(crate) const BACKGROUND_COLOR: Color = Color::new(0.10196, 0.0941, 0.1058, 1.0);
-    pub(crate) const BLACK: Color = Color::new(0.0, 0.0, 0.0, 1.0);
+    pub const BACKGROUND_COLOR: Color = Color::new(0.10196, 0.0941, 0.1058, 1.0);
+    pub const BLACK: Color = Color::new(0.0, 0.0, 0.0, 1.0);
+    pub const LETTERBOX_COLOR: Color = Color::new(0.0549, 0.0509, 0.0588, 1.0);
+
+    pub const WHITE: Color = Color::new(0.9196, 0.8941, 0.9058, 1.0);
+
+    pub const RED: Color = Color::new(0.6392, 0.0862, 0.1294, 1.0);
+    pub const RED_LIGHT: Color = Color::new(0.6509, 0.2431, 0.2784, 1.0);
+    pub const RED_DARK: Color = Color::new(0.3960, 0.0588, 0.0901, 1.0);
+    pub const RED_VAR: [Color; 3] = [RED, RED_LIGHT, RED_DARK];
+
+    pub const BLUE: Color = Color::new(0.0039, 0.4352, 0.7254, 1.0);
+    pub const BLUE_LIGHT: Color = Color::new(0.2549, 0.6078, 0.8588, 1.0);
+    pub const BLUE_DARK: Color = Color::new(0.0000, 0.2588, 0.4862, 1.0);
+    pub const BLUE_VAR: [Color; 3] = [BLUE, BLUE_LIGHT, BLUE_DARK];
+
+    pub const GREEN: Color = Color::new(0.2980, 0.6862, 0.3137, 1.0);
+    pub const GREEN_LIGHT: Color = Color::new(0.4549, 0.7843, 0.4666, 1.0);
+    pub const GREEN_DARK: Color = Color::new(0.2078, 0.4862, 0.2196, 1.0);
+    pub const GREEN_VAR: [Color; 3] = [GREEN, GREEN_LIGHT, GREEN_DARK];
+
+    pub const YELLOW: Color = Color::new(0.8588, 0.6, 0.3529, 1.0);
+    pub const YELLOW_LIGHT: Color = Color::new(0.9607, 0.7490, 0.5294, 1.0);
+    pub const YELLOW_DARK: Color = Color::new(0.7372, 0.4862, 0.2588, 1.0);
+    pub const YELLOW_VAR: [Color; 3] = [YELLOW, YELLOW_LIGHT, YELLOW_DARK];
+
+    pub const PURPLE: Color = Color::new(0.4862, 0.2274, 0.6196, 1.0);
+    pub const PURPLE_LIGHT: Color = Color::new(0.6117, 0.3960, 0.7490, 1.0);
+    pub const PURPLE_DARK: Color = Color::new(0.3333, 0.1411, 0.4392, 1.0);
+    pub const PURPLE_VAR: [Color; 3] = [PURPLE, PURPLE_LIGHT, PURPLE_DARK];
 
-    pub(crate) const WHITE: Color = Color::new(0.9196, 0.8941, 0.9058, 1.0);
+    pub const ORANGE: Color = Color::new(0.9019, 0.4313, 0.1215, 1.0);
+    pub const ORANGE_LIGHT: Color = Color::new(0.9568, 0.5686, 0.2941, 1.0);
+    pub const ORANGE_DARK: Color = Color::new(0.6862, 0.3019, 0.0627, 1.0);
+    pub const ORANGE_VAR: [Color; 3] = [ORANGE, ORANGE_LIGHT, ORANGE_DARK];
 
-    pub(crate) const RED: Color = Color::new(0.6392, 0.0862, 0.1294, 1.0);
-    pub(crate) const RED_LIGHT: Color = Color::new(0.6509, 0.2431, 0.2784, 1.0);
-    pub(crate) const RED_DARK: Color = Color::new(0.3960, 0.0588, 0.0901, 1.0);
-    pub(crate) const RED_VAR: [Color; 3] = [RED, RED_LIGHT, RED_DARK];
+    pub const CYAN: Color = Color::new(0.1607, 0.7058, 0.7254, 1.0);
+    pub const CYAN_LIGHT: Color = Color::new(0.4117, 0.8156, 0.8313, 1.0);
+    pub const CYAN_DARK: Color = Color::new(0.0823, 0.4901, 0.5098, 1.0);
+    pub const CYAN_VAR: [Color; 3] = [CYAN, CYAN_LIGHT, CYAN_DARK];
 
-    pub(crate) const BLUE: Color = Color::new(0.0039, 0.4352, 0.7254, 1.0);
-    pub(crate) const BLUE_LIGHT: Color = Color::new(0.2549, 0.6078, 0.8588, 1.0);
-    pub(crate) const BLUE_DARK: Color = Color::new(0.0000, 0.2588, 0.4862, 1.0);
-    pub(crate) const BLUE_VAR: [Color; 3] = [BLUE, BLUE_LIGHT, BLUE_DARK];
+    pub const PINK: Color = Color::new(0.8862, 0.4470, 0.6470, 1.0);
+    pub const PINK_LIGHT: Color = Color::new(0.9411, 0.6196, 0.7725, 1.0);
+    pub const PINK_DARK: Color = Color::new(0.6745, 0.2862, 0.4705, 1.0);
+    pub const PINK_VAR: [Color; 3] = [PINK, PINK_LIGHT, PINK_DARK];
+}
+
+// Okabe-Ito hues, which stay apart for the common kinds of color blindness, and a pale grey.
+// The fourth shade of each family marks out its pattern.
+pub mod colorblind_colors {
+    use macroquad::color::Color;
+
+    pub const RED_VAR: [Color; 4] = [
+        Color::new(0.8352, 0.3686, 0.0, 1.0),
+        Color::new(0.8941, 0.4745, 0.1647, 1.0),
+        Color::new(0.6941, 0.2980, 0.0, 1.0),
+        Color::new(0.3921, 0.1647, 0.0, 1.0),
+    ];
+    pub const BLUE_VAR: [Color; 4] = [
+        Color::new(0.0, 0.4470, 0.6980, 1.0),
+        Color::new(0.2, 0.5725, 0.7882, 1.0),
+        Color::new(0.0, 0.3568, 0.5607, 1.0),
+        Color::new(0.0, 0.1960, 0.3137, 1.0),
+    ];
+    pub const GREEN_VAR: [Color; 4] = [
+        Color::new(0.0, 0.6196, 0.4509, 1.0),
+        Color::new(0.2, 0.7254, 0.5607, 1.0),
+        Color::new(0.0, 0.4980, 0.3647, 1.0),
+        Color::new(0.0, 0.2745, 0.2, 1.0),
+    ];
+    pub const YELLOW_VAR: [Color; 4] = [
+        Color::new(0.9411, 0.8941, 0.2588, 1.0),
+        Color::new(0.9725, 0.9333, 0.4980, 1.0),
+        Color::new(0.8235, 0.7764, 0.1764, 1.0),
+        Color::new(0.4705, 0.4431, 0.0941, 1.0),
+    ];
+    pub const PURPLE_VAR: [Color; 4] = [
+        Color::new(0.8, 0.4745, 0.6549, 1.0),
+        Color::new(0.8588, 0.5960, 0.7411, 1.0),
+        Color::new(0.6901, 0.3882, 0.5568, 1.0),
+        Color::new(0.4, 0.2117, 0.3176, 1.0),
+    ];
+    pub const ORANGE_VAR: [Color; 4] = [
+        Color::new(0.9019, 0.6235, 0.0, 1.0),
+        Color::new(0.9450, 0.7137, 0.2, 1.0),
+        Color::new(0.7803, 0.5333, 0.0, 1.0),
+        Color::new(0.4509, 0.3098, 0.0, 1.0),
+    ];
+    pub const CYAN_VAR: [Color; 4] = [
+        Color::new(0.3372, 0.7058, 0.9137, 1.0),
+        Color::new(0.4980, 0.7803, 0.9411, 1.0),
+        Color::new(0.2509, 0.6, 0.7960, 1.0),
+        Color::new(0.1176, 0.3529, 0.4980, 1.0),
+    ];
+    pub const PINK_VAR: [Color; 4] = [
+        Color::new(0.8666, 0.8, 0.8666, 1.0),
+        Color::new(0.9333, 0.8941, 0.9333, 1.0),
+        Color::new(0.7450, 0.6784, 0.7450, 1.0),
+        Color::new(0.4, 0.3529, 0.4, 1.0),
+    ];
+}
 
-    pub(crate) const GREEN: Color = Color::new(0.2980, 0.6862, 0.3137, 1.0);
-    pub(crate) const GREEN_LIGHT: Color = Color::new(0.4549, 0.7843, 0.4666, 1.0);
-    pub(crate) const GREEN_DARK: Color = Color::new(0.2078, 0.4862, 0.2196, 1.0);
-    pub(crate) const GREEN_VAR: [Color; 3] = [GREEN, GREEN_LIGHT, GREEN_DARK];
+pub mod matching_tables {
+    use crate::objects::color_family::ColorFamily;
 
-    pub(crate) const YELLOW: Color = Color::new(0.8588, 0.6, 0.3529, 1.0);
-    pub(crate) const YELLOW_LIGHT: Color = Color::new(0.9607, 0.7490, 0.5294, 1.0);
-    pub(crate) const YELLOW_DARK: Color = Color::new(0.7372, 0.4862, 0.2588, 1.0);
-    pub(crate) const YELLOW_VAR: [Color; 3] = [YELLOW, YELLOW_LIGHT, YELLOW_DARK];
+    // Every warm family matches the other warm ones, same for cool
+    pub const WARM_COOL: [(ColorFamily, ColorFamily); 12] = [
+        (ColorFamily::Red, ColorFamily::Yellow),
+        (ColorFamily::Red, ColorFamily::Orange),
+        (ColorFamily::Red, ColorFamily::Pink),
+        (ColorFamily::Yellow, ColorFamily::Orange),
+        (ColorFamily::Yellow, ColorFamily::Pink),
+        (ColorFamily::Orange, ColorFamily::Pink),
+        (ColorFamily::Blue, ColorFamily::Green),
+        (ColorFamily::Blue, ColorFamily::Cyan),
+        (ColorFamily::Blue, ColorFamily::Purple),
+        (ColorFamily::Green, ColorFamily::Cyan),
+        (ColorFamily::Green, ColorFamily::Purple),
+        (ColorFamily::Cyan, ColorFamily::Purple),
+    ];
 }
 
-pub(crate) mod block_constants {
+pub mod block_constants {
     use super::map_constants::MAP_WIDTH;
 
-    pub(crate) const BLOCK_CHUNK_SIDE: i32 = MAP_WIDTH / 12;
-    pub(crate) const PREVIEW_BLOCK_CHUNK_SIDE: i32 = 3;
-    pub(crate) const BLOCK_STARTING_POS: (i32, i32) = (
-        MAP_WIDTH / 2 - (BLOCK_CHUNK_SIDE / 2),
-        -BLOCK_CHUNK_SIDE * 2,
-    );
+    pub const BLOCK_CHUNK_SIDE: i32 = MAP_WIDTH / 12;
+    pub const PREVIEW_BLOCK_CHUNK_SIDE: i32 = 3;
 }
 
-pub(crate) mod block_schemas {
-    pub(crate) const L_BLOCK: [(i8, i8); 4] = [(0, 0), (0, -1), (0, 1), (1, 1)];
-    pub(crate) const REV_L_BLOCK: [(i8, i8); 4] = [(0, 0), (0, -1), (0, 1), (-1, 1)];
-    pub(crate) const SQUARE_BLOCK: [(i8, i8); 4] = [(0, 0), (0, 1), (1, 0), (1, 1)];
-    pub(crate) const Z_BLOCK: [(i8, i8); 4] = [(0, 0), (0, -1), (1, 0), (1, 1)];
-    pub(crate) const REV_Z_BLOCK: [(i8, i8); 4] = [(0, 0), (0, 1), (1, 0), (1, -1)];
-    pub(crate) const I_BLOCK: [(i8, i8); 4] = [(0, 0), (0, -1), (0, 1), (0, 2)];
-    pub(crate) const T_BLOCK: [(i8, i8); 4] = [(0, 0), (-1, 0), (1, 0), (0, -1)];
+pub mod block_schemas {
+    pub const L_BLOCK: [(i8, i8); 4] = [(0, 0), (0, -1), (0, 1), (1, 1)];
+    pub const REV_L_BLOCK: [(i8, i8); 4] = [(0, 0), (0, -1), (0, 1), (-1, 1)];
+    pub const SQUARE_BLOCK: [(i8, i8); 4] = [(0, 0), (0, 1), (1, 0), (1, 1)];
+    pub const Z_BLOCK: [(i8, i8); 4] = [(0, 0), (0, -1), (1, 0), (1, 1)];
+    pub const REV_Z_BLOCK: [(i8, i8); 4] = [(0, 0), (0, 1), (1, 0), (1, -1)];
+    pub const I_BLOCK: [(i8, i8); 4] = [(0, 0), (0, -1), (0, 1), (0, 2)];
+    pub const T_BLOCK: [(i8, i8); 4] = [(0, 0), (-1, 0), (1, 0), (0, -1)];
 }
 
-pub(crate) mod block_skins {
-    pub(crate) const SKIN_SIDE: usize = 6;
+pub mod block_skins {
+    pub const SKIN_SIDE: usize = 6;
     // 0 - normal, 1 - light, 2 - dark
-    pub(crate) const NATURAL: [[usize; SKIN_SIDE]; SKIN_SIDE] = [
+    pub const NATURAL: [[usize; SKIN_SIDE]; SKIN_SIDE] = [
         [2, 1, 0, 0, 0, 2],
         [2, 0, 0, 1, 1, 0],
         [0, 0, 0, 0, 0, 0],
@@ -85,25 +177,231 @@ pub(crate) mod block_skins {
         [1, 1, 0, 0, 0, 1],
         [0, 0, 2, 2, 0, 0],
     ];
+
+    // Patterns tell color families apart by shape, 3 - mark
+    pub const PATTERN_SIDE: usize = 4;
+    pub const DIAGONAL_STRIPES: [[usize; PATTERN_SIDE]; PATTERN_SIDE] =
+        [[3, 0, 1, 0], [0, 3, 0, 2], [1, 0, 3, 0], [0, 2, 0, 3]];
+    pub const DOTS: [[usize; PATTERN_SIDE]; PATTERN_SIDE] =
+        [[0, 1, 0, 0], [1, 3, 0, 0], [0, 0, 0, 2], [0, 0, 2, 0]];
+    pub const HORIZONTAL_STRIPES: [[usize; PATTERN_SIDE]; PATTERN_SIDE] =
+        [[0, 1, 0, 0], [3, 3, 3, 3], [0, 0, 2, 0], [1, 0, 0, 0]];
+    pub const VERTICAL_STRIPES: [[usize; PATTERN_SIDE]; PATTERN_SIDE] =
+        [[0, 3, 0, 1], [1, 3, 0, 0], [0, 3, 2, 0], [0, 3, 0, 0]];
+    pub const CHECKERS: [[usize; PATTERN_SIDE]; PATTERN_SIDE] =
+        [[3, 3, 0, 1], [3, 3, 0, 0], [0, 2, 3, 3], [0, 0, 3, 3]];
+    pub const ANTI_DIAGONAL_STRIPES: [[usize; PATTERN_SIDE]; PATTERN_SIDE] =
+        [[0, 1, 0, 3], [2, 0, 3, 0], [0, 3, 0, 1], [3, 0, 2, 0]];
+    pub const GRID: [[usize; PATTERN_SIDE]; PATTERN_SIDE] =
+        [[3, 3, 3, 3], [3, 0, 1, 0], [3, 2, 0, 0], [3, 0, 0, 1]];
+}
+
+pub mod interface_constants {
+    pub const GAME_OVER_TEXT: &str = "Game Over";
+    pub const GAME_OVER_FONT_SIZE: u16 = 60;
+    pub const GAME_OVER_BOTTOM_TEXT: &str = "press (R) to restart...";
+    pub const GAME_OVER_BOTTOM_FONT_SIZE: u16 = 40;
+    pub const GAME_OVER_OUTLINE_WIDTH: u16 = 3;
+
+    pub const SCORE_TEXT: &str = "Score";
+    pub const LEVEL_TEXT: &str = "Level";
+    pub const GRAINS_LEFT_TEXT: &str = "Left";
+    pub const TIME_TEXT: &str = "Time";
+    pub const BEST_TEXT: &str = "Best";
+    pub const CURSOR_TEXT: &str = "Free placement";
+    pub const HUD_LINE_SPACING: f32 = 50.0;
+    pub const SCORE_FONT_SIZE: u16 = 45;
+    pub const SCORE_OUTLINE_WIDTH: u16 = 3;
+    pub const SCORE_POPUP_FONT_SIZE: u16 = 30;
+    pub const SCORE_POPUP_LINE_SPACING: f32 = 36.0;
+    pub const COMBO_TEXT: &str = "Combo";
+    pub const CHAIN_TEXT: &str = "Chain";
+
+    pub const ALL_CLEAR_TEXT: &str = "ALL CLEAR";
+    pub const ALL_CLEAR_FONT_SIZE: u16 = 80;
+    pub const NEW_RECORD_TEXT: &str = "NEW RECORD";
+
+    pub const MARATHON_FINISHED_TEXT: &str = "Marathon Complete";
+    pub const SPRINT_FINISHED_TEXT: &str = "Sprint Complete";
+    pub const ULTRA_FINISHED_TEXT: &str = "Time's Up";
+    pub const PUZZLE_SOLVED_TEXT: &str = "Puzzle Solved";
+    pub const PUZZLE_FAILED_TEXT: &str = "Puzzle Failed";
+    pub const PIECES_LEFT_TEXT: &str = "Pieces";
+    pub const SURVIVE_TEXT: &str = "Survive";
+
+    pub const BRUSH_TEXT: &str = "Brush";
+    pub const FILL_TEXT: &str = "Fill";
+    pub const SHAPE_TEXT: &str = "Shape";
+    pub const ERASER_TEXT: &str = "eraser";
+    pub const SIZE_TEXT: &str = "Size";
+    pub const UNSAVED_TEXT: &str = "Unsaved";
+    pub const CURSOR_OPACITY: f32 = 0.4; // Of the brush drawn over the map
+
+    pub const SAND_TEXT: &str = "Sand";
+    pub const POUR_RATE_TEXT: &str = "Rate";
+
+    pub const H_BORDER_OFFSET: f32 = 20.0;
+    pub const V_BORDER_OFFSET: f32 = 20.0;
+}
+
+pub mod impact_constants {
+    // Fall heights are measured in block chunks
+    pub const SHATTER_MIN_FALL_HEIGHT: i32 = 6;
+    pub const SHATTER_FALL_HEIGHT_PER_MOMENTUM: i32 = 3;
+    pub const SHATTER_MAX_MOMENTUM: i32 = 8;
+}
+
+pub mod lock_delay_constants {
+    pub const LOCK_DELAY_TICKS: u32 = 30;
+    pub const FAST_LOCK_DELAY_TICKS: u32 = 20; // In the modes played against the clock or speeding up
+    pub const ZEN_LOCK_DELAY_TICKS: u32 = 60;
+    pub const LOCK_DELAY_MAX_RESETS: u32 = 15;
+}
+
+pub mod mode_constants {
+    use std::time::Duration;
+
+    pub const MARATHON_GRAINS_PER_LEVEL: u32 = 3000;
+    pub const MARATHON_MAX_LEVEL: u32 = 15;
+    pub const LEVELS_PER_FALL_STEP: u32 = 3; // Every few levels blocks fall one grain further per tick
+    pub const SPRINT_TARGET: u32 = 8000; // In grains
+    pub const ULTRA_TIME_LIMIT: Duration = Duration::from_secs(180);
+    pub const ZEN_ERASED_SHARE: f32 = 0.5; // How much of the map, from the top, is erased on topping out
+
+    pub const HIGH_SCORES_PATH: &str = "high_scores.txt";
+}
+
+pub mod garbage_constants {
+    use std::time::Duration;
+
+    pub const GARBAGE_INTERVAL: Duration = Duration::from_secs(20);
+    pub const GARBAGE_ROWS: i32 = 8; // In grains
+    pub const GARBAGE_WARNING_DURATION: Duration = Duration::from_secs(3); // Before the garbage is due
+    pub const GARBAGE_WARNING_OPACITY: f32 = 0.5;
+    pub const GARBAGE_WARNING_FLASHES: f32 = 2.0; // Per second
+}
+
+pub mod editor_constants {
+    pub const DEFAULT_BRUSH_RADIUS: i32 = 2; // In grains, 0 paints a single grain
+    pub const MAX_BRUSH_RADIUS: i32 = 16;
+    pub const UNDO_LIMIT: usize = 100;
+}
+
+pub mod sandbox_constants {
+    pub const DEFAULT_BRUSH_RADIUS: i32 = 4; // In grains, 0 pours into a single field
+    pub const MAX_BRUSH_RADIUS: i32 = 16;
+    pub const DEFAULT_POUR_RATE: usize = 8; // Grains poured every tick while the button is held
+    pub const MAX_POUR_RATE: usize = 64;
+}
+
+pub mod physics_constants {
+    // Velocities are measured in cells per tick
+    pub const GRAIN_START_VELOCITY: f32 = 1.0;
+    pub const GRAIN_GRAVITY: f32 = 0.25;
+    pub const GRAIN_TERMINAL_VELOCITY: f32 = 4.0;
 }
 
-pub(crate) mod interface_constants {
-    pub(crate) const GAME_OVER_TEXT: &str = "Game Over";
-    pub(crate) const GAME_OVER_FONT_SIZE: u16 = 60;
-    pub(crate) const GAME_OVER_BOTTOM_TEXT: &str = "press (R) to restart...";
-    pub(crate) const GAME_OVER_BOTTOM_FONT_SIZE: u16 = 40;
-    pub(crate) const GAME_OVER_OUTLINE_WIDTH: u16 = 3;
+pub mod animation_constants {
+    use std::time::Duration;
+
+    // Particles of cleared grains, velocities are measured in cells per tick
+    pub const PARTICLE_LIFETIME: u32 = 24;
+    pub const PARTICLE_MAX_SPEED: f32 = 1.5;
+    pub const PARTICLE_GRAVITY: f32 = 0.1;
 
-    pub(crate) const SCORE_TEXT: &str = "Score";
-    pub(crate) const SCORE_FONT_SIZE: u16 = 45;
-    pub(crate) const SCORE_OUTLINE_WIDTH: u16 = 3;
+    pub const CLEAR_VANISH_DURATION: Duration = Duration::from_millis(250);
+    pub const SCORE_POPUP_DURATION: Duration = Duration::from_millis(800);
+    pub const SCORE_POPUP_RISE: f32 = 12.0; // In grains
+    pub const SCREEN_SHAKE_DURATION: Duration = Duration::from_millis(300);
+    pub const SCREEN_SHAKE_AMPLITUDE: f32 = 1.5; // In grains
+    pub const SCREEN_SHAKE_WAVES: f32 = 4.0;
+    pub const BANNER_DURATION: Duration = Duration::from_millis(1500);
+    pub const GAME_OVER_FADE_DURATION: Duration = Duration::from_millis(600);
+    pub const GAME_OVER_FADE_OPACITY: f32 = 0.6;
+
+    // How far a fake clock moves per tick when frames are rendered without a window
+    pub const HEADLESS_FRAME_TIME: Duration = Duration::from_millis(16);
+}
+
+pub mod terminal_constants {
+    use std::time::Duration;
+
+    pub const TERMINAL_FRAME_TIME: Duration = Duration::from_millis(16);
+    pub const HALF_BLOCK: char = '\u{2580}'; // Upper half block, the lower half shows the background color
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GameRules {
+    pub matching_rules: MatchingRules,
+    pub impact_mode: ImpactMode,
+    pub settle_policy: SettlePolicy,
+    pub lock_delay: LockDelay,
+    pub color_weights: ColorWeights,
+    pub mode: GameMode,
+    pub garbage: Option<Garbage>, // None keeps the bottom of the map still
+}
+
+impl GameRules {
+    pub const CLASSIC: GameRules = GameRules {
+        matching_rules: MatchingRules::CLASSIC,
+        impact_mode: ImpactMode::Settle,
+        settle_policy: SettlePolicy::AnyContact,
+        lock_delay: LockDelay {
+            ticks: None,
+            max_resets: lock_delay_constants::LOCK_DELAY_MAX_RESETS,
+        },
+        color_weights: ColorWeights::CLASSIC,
+        mode: GameMode::Endless,
+        garbage: None,
+    };
+
+    pub fn get_lock_delay_ticks(&self) -> u32 {
+        self.lock_delay
+            .ticks
+            .unwrap_or_else(|| self.mode.get_lock_delay_ticks())
+    }
+}
 
-    pub(crate) const H_BORDER_OFFSET: f32 = 20.0;
-    pub(crate) const V_BORDER_OFFSET: f32 = 20.0;
+// Everything about the board that can be changed at launch
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoardSize {
+    pub width: i32,  // In grains
+    pub height: i32, // In grains
+    pub grain_side_size: i32,
+    pub block_chunk_side: i32,
 }
 
-pub(crate) mod animation_constants {
-    pub(crate) const DEMOLISHION_CHUNK_SIZE: usize = 20;
+impl BoardSize {
+    pub const CLASSIC: BoardSize = BoardSize {
+        width: map_constants::MAP_WIDTH,
+        height: map_constants::MAP_HEIGHT,
+        grain_side_size: map_constants::GRAIN_SIDE_SIZE,
+        block_chunk_side: block_constants::BLOCK_CHUNK_SIDE,
+    };
+
+    pub fn get_block_starting_pos(&self) -> (i32, i32) {
+        (
+            self.width / 2 - (self.block_chunk_side / 2),
+            -self.block_chunk_side * 2,
+        )
+    }
+
+    // The sides don't have to be a multiple of the chunk side, blocks move a grain at a time
+    // so the last chunk column is just narrower. The classic board is 100 grains wide with chunks of 8.
+    fn validate(&self) -> Result<(), String> {
+        for (name, value) in [
+            ("Board width", self.width),
+            ("Board height", self.height),
+            ("Grain size", self.grain_side_size),
+            ("Block chunk side", self.block_chunk_side),
+        ] {
+            if value <= 0 {
+                return Err(format!("{} must be positive, got {}", name, value));
+            }
+        }
+
+        Ok(())
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -114,4 +412,231 @@ pub struct TetrisConstants {
     pub grain_side_size: i32,
     pub preview_block_chunk_side: i32,
     pub block_starting_pos: (i32, i32),
+    pub rules: GameRules,
+    pub theme: &'static Theme,
+}
+
+impl TetrisConstants {
+    pub fn new(rules: GameRules) -> Self {
+        TetrisConstants::from_board(BoardSize::CLASSIC, rules)
+    }
+
+    pub fn with_board(board: BoardSize, rules: GameRules) -> Result<Self, String> {
+        board.validate()?;
+        if let Some(garbage) = rules.garbage {
+            if garbage.rows >= board.height {
+                return Err(format!(
+                    "{} rows of garbage don't fit on a board {} grains high",
+                    garbage.rows, board.height
+                ));
+            }
+        }
+
+        Ok(TetrisConstants::from_board(board, rules))
+    }
+
+    fn from_board(board: BoardSize, rules: GameRules) -> Self {
+        TetrisConstants {
+            map_width: board.width,
+            map_height: board.height,
+            block_chunk_side: board.block_chunk_side,
+            grain_side_size: board.grain_side_size,
+            preview_block_chunk_side: block_constants::PREVIEW_BLOCK_CHUNK_SIDE,
+            block_starting_pos: board.get_block_starting_pos(),
+            rules,
+            theme: &Theme::CLASSIC,
+        }
+    }
+
+    // Every piece has to spawn inside the side walls and fit between the top and the floor
+    pub fn check_piece_set(&self, piece_set: &PieceSet) -> Result<(), String> {
+        for piece in piece_set.get_pieces() {
+            let cells = piece.get_cells();
+            let left = cells.iter().map(|cell| cell.0 as i32).min().unwrap();
+            let right = cells.iter().map(|cell| cell.0 as i32).max().unwrap() + 1;
+            let top = cells.iter().map(|cell| cell.1 as i32).min().unwrap();
+            let bottom = cells.iter().map(|cell| cell.1 as i32).max().unwrap() + 1;
+
+            if self.block_starting_pos.0 + left * self.block_chunk_side < 0
+                || self.block_starting_pos.0 + right * self.block_chunk_side > self.map_width
+            {
+                return Err(format!(
+                    "Piece {} doesn't fit between the walls of a board {} grains wide",
+                    piece.get_name(),
+                    self.map_width
+                ));
+            }
+            if (bottom - top) * self.block_chunk_side > self.map_height {
+                return Err(format!(
+                    "Piece {} doesn't fit on a board {} grains high",
+                    piece.get_name(),
+                    self.map_height
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn check_puzzle(&self, puzzle: &Puzzle) -> Result<(), String> {
+        let (width, height) = puzzle.get_board_size();
+        if width > self.map_width || height > self.map_height {
+            return Err(format!(
+                "Puzzle board is {} by {} grains, it doesn't fit on a board {} by {} grains",
+                width, height, self.map_width, self.map_height
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::objects::piece_set::PieceDefinition;
+
+    use super::*;
+
+    #[test]
+    fn tetris_constants_new() {
+        let constants = TetrisConstants::new(GameRules::CLASSIC);
+
+        assert_eq!(constants.map_width, map_constants::MAP_WIDTH);
+        assert_eq!(constants.map_height, map_constants::MAP_HEIGHT);
+        assert_eq!(
+            constants.block_starting_pos,
+            BoardSize::CLASSIC.get_block_starting_pos()
+        );
+    }
+
+    #[test]
+    fn with_board_uneven_chunks() {
+        let board = BoardSize {
+            width: 205,
+            block_chunk_side: 10,
+            ..BoardSize::CLASSIC
+        };
+
+        assert!(TetrisConstants::with_board(board, GameRules::CLASSIC).is_ok());
+        assert_ne!(
+            BoardSize::CLASSIC.width % BoardSize::CLASSIC.block_chunk_side,
+            0
+        );
+    }
+
+    #[test]
+    fn with_board() {
+        let board = BoardSize {
+            width: 40,
+            height: 60,
+            grain_side_size: 10,
+            block_chunk_side: 4,
+        };
+
+        let constants = TetrisConstants::with_board(board, GameRules::CLASSIC).unwrap();
+
+        assert_eq!(constants.map_width, 40);
+        assert_eq!(constants.map_height, 60);
+        assert_eq!(constants.grain_side_size, 10);
+        assert_eq!(constants.block_chunk_side, 4);
+        assert_eq!(constants.block_starting_pos, (18, -8));
+    }
+
+    #[test]
+    fn with_board_invalid() {
+        for board in [
+            BoardSize {
+                width: 0,
+                ..BoardSize::CLASSIC
+            },
+            BoardSize {
+                height: -5,
+                ..BoardSize::CLASSIC
+            },
+            BoardSize {
+                grain_side_size: 0,
+                ..BoardSize::CLASSIC
+            },
+            BoardSize {
+                block_chunk_side: 0,
+                ..BoardSize::CLASSIC
+            },
+        ] {
+            assert!(TetrisConstants::with_board(board, GameRules::CLASSIC).is_err());
+        }
+    }
+
+    #[test]
+    fn check_piece_set() {
+        let constants = TetrisConstants::new(GameRules::CLASSIC);
+
+        assert_eq!(constants.check_piece_set(&PieceSet::classic()), Ok(()));
+    }
+
+    #[test]
+    fn check_piece_set_too_wide() {
+        let board = BoardSize {
+            width: 12,
+            block_chunk_side: 4,
+            ..BoardSize::CLASSIC
+        };
+        let constants = TetrisConstants::with_board(board, GameRules::CLASSIC).unwrap();
+        let wide_piece = PieceSet::new(Vec::from([PieceDefinition::new(
+            "wide",
+            Vec::from([(-1, 0), (0, 0), (1, 0), (2, 0)]),
+            2,
+            1,
+        )]))
+        .unwrap();
+
+        assert!(constants.check_piece_set(&wide_piece).is_err());
+    }
+
+    #[test]
+    fn check_piece_set_too_high() {
+        let board = BoardSize {
+            height: 8,
+            block_chunk_side: 4,
+            ..BoardSize::CLASSIC
+        };
+        let constants = TetrisConstants::with_board(board, GameRules::CLASSIC).unwrap();
+
+        assert!(constants.check_piece_set(&PieceSet::classic()).is_err()); // I block is 4 chunks high
+    }
+
+    #[test]
+    fn with_board_garbage_too_high() {
+        let rules = GameRules {
+            garbage: Some(Garbage {
+                rows: 60,
+                ..Garbage::CLASSIC
+            }),
+            ..GameRules::CLASSIC
+        };
+        let board = BoardSize {
+            height: 60,
+            ..BoardSize::CLASSIC
+        };
+
+        assert!(TetrisConstants::with_board(board, rules).is_err());
+        assert!(TetrisConstants::with_board(BoardSize::CLASSIC, rules).is_ok());
+    }
+
+    #[test]
+    fn check_puzzle() {
+        let board = BoardSize {
+            width: 3,
+            height: 2,
+            block_chunk_side: 1,
+            ..BoardSize::CLASSIC
+        };
+        let constants = TetrisConstants::with_board(board, GameRules::CLASSIC).unwrap();
+        let puzzle = |board: &str| {
+            Puzzle::parse(&format!("goal clear 5\npiece T red\nboard\n{}\nend", board)).unwrap()
+        };
+
+        assert_eq!(constants.check_puzzle(&puzzle("rrr\nbbb")), Ok(()));
+        assert!(constants.check_puzzle(&puzzle("rrrr")).is_err());
+        assert!(constants.check_puzzle(&puzzle("r\nr\nr")).is_err());
+    }
 }
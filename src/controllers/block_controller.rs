@@ -1,17 +1,31 @@
 use bounded_vec_deque::BoundedVecDeque;
-use macroquad::color::Color;
 
 use crate::{
-    constants::TetrisConstants,
+    constants::{
+        impact_constants::{
+            SHATTER_FALL_HEIGHT_PER_MOMENTUM, SHATTER_MAX_MOMENTUM, SHATTER_MIN_FALL_HEIGHT,
+        },
+        TetrisConstants,
+    },
     controllers::map_controller::{ColisionType, MapController},
-    objects::block::Block,
+    objects::{
+        block::Block,
+        color_family::{ColorFamily, ColorWeights},
+        piece_set::PieceSet,
+        settle_rules::{ImpactMode, SettlePolicy},
+    },
     utils::tetris_rng::TetrisRng,
 };
 
 pub struct BlockController<'a> {
     block_center_pos: (i32, i32),
+    fall_height: i32,
+    lock_timer: Option<u32>, // Ticks spent resting on sand, None while falling
+    lock_resets: u32,
+    settled_blocks: u32, // Since the game started
     block_queue: BoundedVecDeque<Block>,
-    color_queue: BoundedVecDeque<Color>,
+    color_queue: BoundedVecDeque<ColorFamily>,
+    piece_set: PieceSet,
     constants: &'a TetrisConstants,
 }
 
@@ -19,12 +33,21 @@ impl<'a> BlockController<'a> {
     pub fn new(constants: &'a TetrisConstants) -> Self {
         BlockController {
             block_center_pos: constants.block_starting_pos,
+            fall_height: 0,
+            lock_timer: None,
+            lock_resets: 0,
+            settled_blocks: 0,
             block_queue: BoundedVecDeque::new(2),
             color_queue: BoundedVecDeque::new(2),
+            piece_set: PieceSet::classic(),
             constants,
         }
     }
 
+    pub fn set_piece_set(&mut self, piece_set: PieceSet) {
+        self.piece_set = piece_set;
+    }
+
     pub fn init_block_queue(&mut self, rng: &mut impl TetrisRng) {
         self.block_queue.clear();
         self.color_queue.clear();
@@ -35,21 +58,30 @@ impl<'a> BlockController<'a> {
 
     fn get_new_block(&mut self, rng: &mut impl TetrisRng) {
         self.block_queue
-            .push_front(BlockController::generate_random_block(rng));
+            .push_front(BlockController::generate_random_block(rng, &self.piece_set));
         self.color_queue
-            .push_front(BlockController::generate_random_color(rng));
+            .push_front(BlockController::generate_random_color(
+                rng,
+                &self.constants.rules.color_weights,
+            ));
 
         self.block_center_pos = self.constants.block_starting_pos;
+        self.fall_height = 0;
+        self.lock_timer = None;
+        self.lock_resets = 0;
     }
 
-    fn generate_random_block(rng: &mut impl TetrisRng) -> Block {
-        let block_type = rng.generate_block_type();
+    fn generate_random_block(rng: &mut impl TetrisRng, piece_set: &PieceSet) -> Block {
+        let piece_index = rng.generate_piece_index(&piece_set.get_spawn_weights());
 
-        Block::new(block_type)
+        Block::new(piece_set.get_piece(piece_index).clone())
     }
 
-    fn generate_random_color(rng: &mut impl TetrisRng) -> Color {
-        let color = rng.generate_block_color();
+    fn generate_random_color(
+        rng: &mut impl TetrisRng,
+        color_weights: &ColorWeights,
+    ) -> ColorFamily {
+        let color = rng.generate_block_color(color_weights.get_weights());
 
         color
     }
@@ -62,7 +94,7 @@ impl<'a> BlockController<'a> {
     }
 
     fn settle_and_get_new_block(&mut self, mc: &mut MapController, rng: &mut impl TetrisRng) {
-        self.settle_block(mc);
+        self.settle_block(mc, rng);
         self.get_new_block(rng)
     }
 
@@ -72,12 +104,40 @@ impl<'a> BlockController<'a> {
         if !can_move {
             match self.check_game_over(mc) {
                 true => return true,
-                false => self.settle_and_get_new_block(mc, rng),
+                false if self.does_contact_settle(true) => self.settle_and_get_new_block(mc, rng),
+                false => self.lock_timer = Some(self.lock_timer.unwrap_or(0)),
             }
         }
         false
     }
 
+    fn does_contact_settle(&self, is_downward: bool) -> bool {
+        match self.constants.rules.settle_policy {
+            SettlePolicy::AnyContact => true,
+            SettlePolicy::DownwardContact => is_downward,
+            SettlePolicy::AfterLockDelay => false,
+        }
+    }
+
+    fn tick_lock_timer(&mut self, mc: &mut MapController, rng: &mut impl TetrisRng) {
+        if let Some(lock_timer) = self.lock_timer {
+            match lock_timer + 1 >= self.constants.rules.get_lock_delay_ticks() {
+                true => self.settle_and_get_new_block(mc, rng),
+                false => self.lock_timer = Some(lock_timer + 1),
+            }
+        }
+    }
+
+    fn reset_lock_timer(&mut self) {
+        // Moving a resting block buys it more time, but only so many times
+        if self.lock_timer.is_some()
+            && self.lock_resets < self.constants.rules.lock_delay.max_resets
+        {
+            self.lock_timer = Some(0);
+            self.lock_resets += 1;
+        }
+    }
+
     fn move_down(&mut self, mc: &mut MapController) -> (bool, ColisionType) {
         let (can_move, colision) = mc.can_block_move_down(
             &self.get_current_block().get_schema(),
@@ -85,6 +145,8 @@ impl<'a> BlockController<'a> {
         );
         if can_move {
             self.block_center_pos.1 += 1;
+            self.fall_height += 1;
+            self.lock_timer = None;
         }
 
         (can_move, colision)
@@ -99,7 +161,7 @@ impl<'a> BlockController<'a> {
             if self.check_game_over(mc) {
                 game_over = true;
             } else {
-                if colision == ColisionType::SandColision {
+                if colision == ColisionType::SandColision && self.does_contact_settle(false) {
                     self.settle_and_get_new_block(mc, rng);
                 }
             }
@@ -114,6 +176,7 @@ impl<'a> BlockController<'a> {
         );
         if can_move {
             self.block_center_pos.0 += 1;
+            self.reset_lock_timer();
         }
 
         (can_move, colision)
@@ -128,7 +191,7 @@ impl<'a> BlockController<'a> {
             if self.check_game_over(mc) {
                 game_over = true;
             } else {
-                if colision == ColisionType::SandColision {
+                if colision == ColisionType::SandColision && self.does_contact_settle(false) {
                     self.settle_and_get_new_block(mc, rng);
                 }
             }
@@ -143,6 +206,7 @@ impl<'a> BlockController<'a> {
         );
         if can_move {
             self.block_center_pos.0 -= 1;
+            self.reset_lock_timer();
         }
 
         (can_move, colision)
@@ -161,7 +225,7 @@ impl<'a> BlockController<'a> {
             if self.check_game_over(mc) {
                 game_over = true;
             } else {
-                if colision == ColisionType::SandColision {
+                if colision == ColisionType::SandColision && self.does_contact_settle(false) {
                     self.settle_and_get_new_block(mc, rng);
                 }
             }
@@ -176,6 +240,7 @@ impl<'a> BlockController<'a> {
         );
         if can_move {
             self.get_current_block_mut().rotate_clockwise();
+            self.reset_lock_timer();
         }
 
         (can_move, colision)
@@ -194,7 +259,7 @@ impl<'a> BlockController<'a> {
             if self.check_game_over(mc) {
                 game_over = true;
             } else {
-                if colision == ColisionType::SandColision {
+                if colision == ColisionType::SandColision && self.does_contact_settle(false) {
                     self.settle_and_get_new_block(mc, rng);
                 }
             }
@@ -209,16 +274,44 @@ impl<'a> BlockController<'a> {
         );
         if can_move {
             self.get_current_block_mut().rotate_counter_clockwise();
+            self.reset_lock_timer();
         }
 
         (can_move, colision)
     }
 
+    // Free placement, the block goes wherever it fits and only settles when placed
+    pub fn handle_cursor_move(&mut self, mc: &MapController, offset: (i32, i32)) {
+        let new_pos = (
+            self.block_center_pos.0 + offset.0,
+            self.block_center_pos.1 + offset.1,
+        );
+        if new_pos.1 >= self.constants.block_starting_pos.1
+            && mc.can_block_fit(&self.get_current_block().get_schema(), new_pos)
+        {
+            self.block_center_pos = new_pos;
+        }
+    }
+
+    pub fn handle_cursor_rotate(&mut self, mc: &MapController, is_clockwise: bool) {
+        match is_clockwise {
+            true => self.rotate_clockwise(mc),
+            false => self.rotate_counter_clockwise(mc),
+        };
+    }
+
+    // Only inside the map, a block sticking out above it stays in hand
+    pub fn handle_place(&mut self, mc: &mut MapController, rng: &mut impl TetrisRng) {
+        if !self.check_game_over(mc) {
+            self.settle_and_get_new_block(mc, rng);
+        }
+    }
+
     fn get_current_block(&self) -> &Block {
         self.block_queue.get(1).unwrap()
     }
 
-    fn get_current_color(&self) -> &Color {
+    fn get_current_color(&self) -> &ColorFamily {
         self.color_queue.get(1).unwrap()
     }
 
@@ -226,7 +319,7 @@ impl<'a> BlockController<'a> {
         self.block_queue.get(0).unwrap()
     }
 
-    fn get_next_color(&self) -> &Color {
+    fn get_next_color(&self) -> &ColorFamily {
         self.color_queue.get(0).unwrap()
     }
 
@@ -248,9 +341,9 @@ impl<'a> BlockController<'a> {
         return rotated_block;
     }
 
-    pub fn get_block_to_draw(&self) -> (Vec<(i32, i32)>, Color) {
+    pub fn get_block_to_draw(&self) -> (Vec<(i32, i32)>, ColorFamily) {
         let mut output: Vec<(i32, i32)> = Vec::new();
-        let color: Color = self.get_current_color().clone();
+        let color: ColorFamily = *self.get_current_color();
 
         for block_box in self.get_current_block().get_schema() {
             output.push((
@@ -261,9 +354,9 @@ impl<'a> BlockController<'a> {
         (output, color)
     }
 
-    pub fn get_next_block_miniature(&self) -> (Vec<(i32, i32)>, Color) {
+    pub fn get_next_block_miniature(&self) -> (Vec<(i32, i32)>, ColorFamily) {
         let mut output: Vec<(i32, i32)> = Vec::new();
-        let color: Color = self.get_next_color().clone();
+        let color: ColorFamily = *self.get_next_color();
 
         for block_box in self.get_next_block().get_schema() {
             output.push((
@@ -279,15 +372,49 @@ impl<'a> BlockController<'a> {
         mc: &mut MapController,
         rng: &mut impl TetrisRng,
     ) -> bool {
-        self.handle_move_down(mc, rng)
+        let game_over = self.handle_move_down(mc, rng);
+        if !game_over {
+            self.tick_lock_timer(mc, rng);
+        }
+
+        game_over
     }
 
-    pub fn settle_block(&mut self, mc: &mut MapController) {
-        let drawing_schema_color: (Vec<(i32, i32)>, Color) = self.get_block_to_draw();
-        mc.spawn_block(drawing_schema_color.0, drawing_schema_color.1);
+    pub fn settle_block(&mut self, mc: &mut MapController, rng: &mut impl TetrisRng) {
+        let drawing_schema_color: (Vec<(i32, i32)>, ColorFamily) = self.get_block_to_draw();
+        self.settled_blocks += 1;
+        match self.get_impact_momentum() {
+            0 => mc.spawn_block(drawing_schema_color.0, drawing_schema_color.1),
+            max_momentum => mc.shatter_block(
+                drawing_schema_color.0,
+                drawing_schema_color.1,
+                max_momentum,
+                rng,
+            ),
+        }
+    }
+
+    fn get_impact_momentum(&self) -> i32 {
+        if self.constants.rules.impact_mode != ImpactMode::Shatter {
+            return 0;
+        }
+
+        let fall_height = self.fall_height / self.constants.block_chunk_side;
+        if fall_height < SHATTER_MIN_FALL_HEIGHT {
+            return 0;
+        }
+        let momentum =
+            (fall_height - SHATTER_MIN_FALL_HEIGHT) / SHATTER_FALL_HEIGHT_PER_MOMENTUM + 1;
+
+        momentum.min(SHATTER_MAX_MOMENTUM)
+    }
+
+    pub fn get_settled_block_count(&self) -> u32 {
+        self.settled_blocks
     }
 
     pub fn clear(&mut self, rng: &mut impl TetrisRng) {
+        self.settled_blocks = 0;
         self.init_block_queue(rng);
     }
 }
@@ -297,14 +424,22 @@ mod test {
     use crate::{
         constants::{
             block_schemas::{L_BLOCK, SQUARE_BLOCK},
-            colors::{RED, WHITE, YELLOW},
+            block_skins::NATURAL,
+            GameRules,
+        },
+        objects::{
+            block::BlockType, color_family::GrainColor, field::Field, settle_rules::LockDelay,
+            theme::Theme,
         },
-        objects::{block::BlockType, field::Field},
         utils::tetris_rng::{MockTetrisRng, ThreadTetrisRng},
     };
 
     use super::*;
 
+    // Chunks are a single grain, so every grain gets the first shade of the skin
+    const SETTLED_COLOR: Option<GrainColor> =
+        Some(GrainColor::new(ColorFamily::Green, NATURAL[0][0]));
+
     const TEST_CONSTANTS: TetrisConstants = TetrisConstants {
         map_width: 10,
         map_height: 10,
@@ -312,8 +447,14 @@ mod test {
         grain_side_size: 1,
         preview_block_chunk_side: 1,
         block_starting_pos: (5, 0),
+        rules: GameRules::CLASSIC,
+        theme: &Theme::CLASSIC,
     };
 
+    fn get_test_constants() -> TetrisConstants {
+        TEST_CONSTANTS
+    }
+
     #[test]
     fn create_block_controller() {
         let bc: BlockController = BlockController::new(&TEST_CONSTANTS);
@@ -350,11 +491,11 @@ mod test {
     fn get_current_color() {
         let mut bc: BlockController = BlockController::new(&TEST_CONSTANTS);
         let mut rng: MockTetrisRng = MockTetrisRng::new();
-        rng.set_block_color(RED);
+        rng.set_block_color(ColorFamily::Red);
 
         bc.init_block_queue(&mut rng);
 
-        assert_eq!(*bc.get_current_color(), RED);
+        assert_eq!(*bc.get_current_color(), ColorFamily::Red);
     }
 
     #[test]
@@ -372,11 +513,11 @@ mod test {
     fn get_next_color() {
         let mut bc: BlockController = BlockController::new(&TEST_CONSTANTS);
         let mut rng: MockTetrisRng = MockTetrisRng::new();
-        rng.set_block_color(RED);
+        rng.set_block_color(ColorFamily::Red);
 
         bc.init_block_queue(&mut rng);
 
-        assert_eq!(*bc.get_next_color(), RED);
+        assert_eq!(*bc.get_next_color(), ColorFamily::Red);
     }
 
     #[test]
@@ -384,13 +525,13 @@ mod test {
         let mut bc: BlockController = BlockController::new(&TEST_CONSTANTS);
         let mut rng: MockTetrisRng = MockTetrisRng::new();
         rng.set_block_type(BlockType::SquareBlock);
-        rng.set_block_color(RED);
+        rng.set_block_color(ColorFamily::Red);
 
         bc.get_new_block(&mut rng);
         assert_eq!(bc.block_queue.len(), 1);
         assert_eq!(bc.block_queue.get(0).unwrap().get_schema(), SQUARE_BLOCK);
         assert_eq!(bc.color_queue.len(), 1);
-        assert_eq!(*bc.color_queue.get(0).unwrap(), RED);
+        assert_eq!(*bc.color_queue.get(0).unwrap(), ColorFamily::Red);
         assert_eq!(bc.block_center_pos, TEST_CONSTANTS.block_starting_pos);
 
         bc.get_new_block(&mut rng);
@@ -409,19 +550,49 @@ mod test {
         let mut rng: MockTetrisRng = MockTetrisRng::new();
         rng.set_block_type(BlockType::SquareBlock);
 
-        let new_block: Block = BlockController::generate_random_block(&mut rng);
+        let new_block: Block =
+            BlockController::generate_random_block(&mut rng, &PieceSet::classic());
 
         assert_eq!(new_block.get_schema(), SQUARE_BLOCK);
     }
 
+    #[test]
+    fn generate_random_block_from_loaded_set() {
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        let piece_set = PieceSet::parse("piece A\n@\nend\npiece B\n@##\nend").unwrap();
+        rng.set_piece_index(1);
+
+        let new_block: Block = BlockController::generate_random_block(&mut rng, &piece_set);
+
+        assert_eq!(new_block.get_schema(), Vec::from([(0, 0), (1, 0), (2, 0)]));
+    }
+
+    #[test]
+    fn set_piece_set() {
+        let mut bc: BlockController = BlockController::new(&TEST_CONSTANTS);
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        let piece_set = PieceSet::parse(include_str!("../../assets/pieces/pentomino.txt")).unwrap();
+        rng.set_piece_index(9); // X pentomino
+
+        bc.set_piece_set(piece_set);
+        bc.init_block_queue(&mut rng);
+
+        assert_eq!(bc.get_current_block().get_schema().len(), 5);
+        assert_eq!(
+            bc.get_block_to_draw().0,
+            Vec::from([(5, -1), (4, 0), (5, 0), (6, 0), (5, 1)])
+        );
+    }
+
     #[test]
     fn generate_random_color() {
         let mut rng: MockTetrisRng = MockTetrisRng::new();
-        rng.set_block_color(YELLOW);
+        rng.set_block_color(ColorFamily::Yellow);
 
-        let color: Color = BlockController::generate_random_color(&mut rng);
+        let color: ColorFamily =
+            BlockController::generate_random_color(&mut rng, &ColorWeights::CLASSIC);
 
-        assert_eq!(color, YELLOW);
+        assert_eq!(color, ColorFamily::Yellow);
     }
 
     #[test]
@@ -459,7 +630,7 @@ mod test {
         let mut rng: MockTetrisRng = MockTetrisRng::new();
 
         rng.set_block_type(BlockType::SquareBlock);
-        rng.set_block_color(WHITE); // for Graphics Controller
+        rng.set_block_color(ColorFamily::Green); // for Graphics Controller
         bc.init_block_queue(&mut rng);
 
         bc.settle_and_get_new_block(&mut mc, &mut rng);
@@ -474,10 +645,26 @@ mod test {
             .collect();
 
         for (x, y) in [(5, 0), (6, 0), (5, 1), (6, 1)] {
-            assert!(settled_fields.contains(&Field::new(x, y, WHITE, 1)))
+            assert!(settled_fields.contains(&Field::new(x, y, SETTLED_COLOR, 1)))
         }
     }
 
+    #[test]
+    fn settled_block_count() {
+        let mut bc: BlockController = BlockController::new(&TEST_CONSTANTS);
+        let mut mc: MapController = MapController::new(&TEST_CONSTANTS);
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        rng.set_block_type(BlockType::SquareBlock);
+        bc.init_block_queue(&mut rng);
+
+        bc.settle_and_get_new_block(&mut mc, &mut rng);
+        bc.settle_and_get_new_block(&mut mc, &mut rng);
+        assert_eq!(bc.get_settled_block_count(), 2);
+
+        bc.clear(&mut rng);
+        assert_eq!(bc.get_settled_block_count(), 0);
+    }
+
     #[test]
     fn handle_move_down() {
         let mut bc: BlockController = BlockController::new(&TEST_CONSTANTS);
@@ -498,7 +685,7 @@ mod test {
         let mut mc: MapController = MapController::new(&TEST_CONSTANTS);
         let mut rng: MockTetrisRng = MockTetrisRng::new();
         rng.set_block_type(BlockType::SquareBlock);
-        rng.set_block_color(WHITE);
+        rng.set_block_color(ColorFamily::Green);
 
         bc.init_block_queue(&mut rng);
         bc.block_center_pos = (5, 8);
@@ -515,7 +702,7 @@ mod test {
             .collect();
 
         for (x, y) in [(5, 8), (5, 9), (6, 8), (6, 9)] {
-            assert!(settled_fields.contains(&Field::new(x, y, WHITE, 1)));
+            assert!(settled_fields.contains(&Field::new(x, y, SETTLED_COLOR, 1)));
         }
     }
 
@@ -525,11 +712,11 @@ mod test {
         let mut mc: MapController = MapController::new(&TEST_CONSTANTS);
         let mut rng: MockTetrisRng = MockTetrisRng::new();
         rng.set_block_type(BlockType::LBlock);
-        rng.set_block_color(WHITE);
+        rng.set_block_color(ColorFamily::Green);
 
         bc.init_block_queue(&mut rng);
         bc.block_center_pos = (5, 0);
-        mc.spawn_block(Vec::from([(5, 1)]), WHITE);
+        mc.spawn_block(Vec::from([(5, 1)]), ColorFamily::Green);
 
         let game_over = bc.handle_move_down(&mut mc, &mut rng);
 
@@ -569,11 +756,11 @@ mod test {
         let mut mc: MapController = MapController::new(&TEST_CONSTANTS);
         let mut rng: MockTetrisRng = MockTetrisRng::new();
         rng.set_block_type(BlockType::SquareBlock);
-        rng.set_block_color(WHITE);
+        rng.set_block_color(ColorFamily::Green);
 
         bc.init_block_queue(&mut rng);
         bc.block_center_pos = (5, 7);
-        mc.spawn_block(Vec::from([(6, 7)]), WHITE);
+        mc.spawn_block(Vec::from([(6, 7)]), ColorFamily::Green);
 
         let game_over = bc.handle_move_right(&mut mc, &mut rng);
 
@@ -587,7 +774,7 @@ mod test {
             .collect();
 
         for (x, y) in [(5, 7), (5, 8), (6, 7), (6, 8)] {
-            assert!(settled_fields.contains(&Field::new(x, y, WHITE, 2)));
+            assert!(settled_fields.contains(&Field::new(x, y, SETTLED_COLOR, 2)));
         }
     }
 
@@ -597,11 +784,11 @@ mod test {
         let mut mc: MapController = MapController::new(&TEST_CONSTANTS);
         let mut rng: MockTetrisRng = MockTetrisRng::new();
         rng.set_block_type(BlockType::LBlock);
-        rng.set_block_color(WHITE);
+        rng.set_block_color(ColorFamily::Green);
 
         bc.init_block_queue(&mut rng);
         bc.block_center_pos = (5, 0);
-        mc.spawn_block(Vec::from([(6, 0)]), WHITE);
+        mc.spawn_block(Vec::from([(6, 0)]), ColorFamily::Green);
 
         let game_over = bc.handle_move_right(&mut mc, &mut rng);
 
@@ -641,11 +828,11 @@ mod test {
         let mut mc: MapController = MapController::new(&TEST_CONSTANTS);
         let mut rng: MockTetrisRng = MockTetrisRng::new();
         rng.set_block_type(BlockType::SquareBlock);
-        rng.set_block_color(WHITE);
+        rng.set_block_color(ColorFamily::Green);
 
         bc.init_block_queue(&mut rng);
         bc.block_center_pos = (5, 7);
-        mc.spawn_block(Vec::from([(4, 7)]), WHITE);
+        mc.spawn_block(Vec::from([(4, 7)]), ColorFamily::Green);
 
         let game_over = bc.handle_move_left(&mut mc, &mut rng);
 
@@ -659,7 +846,7 @@ mod test {
             .collect();
 
         for (x, y) in [(5, 7), (5, 8), (6, 7), (6, 8)] {
-            assert!(settled_fields.contains(&Field::new(x, y, WHITE, 2)));
+            assert!(settled_fields.contains(&Field::new(x, y, SETTLED_COLOR, 2)));
         }
     }
 
@@ -669,11 +856,11 @@ mod test {
         let mut mc: MapController = MapController::new(&TEST_CONSTANTS);
         let mut rng: MockTetrisRng = MockTetrisRng::new();
         rng.set_block_type(BlockType::LBlock);
-        rng.set_block_color(WHITE);
+        rng.set_block_color(ColorFamily::Green);
 
         bc.init_block_queue(&mut rng);
         bc.block_center_pos = (5, 0);
-        mc.spawn_block(Vec::from([(4, 0)]), WHITE);
+        mc.spawn_block(Vec::from([(4, 0)]), ColorFamily::Green);
 
         let game_over = bc.handle_move_left(&mut mc, &mut rng);
 
@@ -699,7 +886,7 @@ mod test {
         let mut mc: MapController = MapController::new(&TEST_CONSTANTS);
         let mut rng: MockTetrisRng = MockTetrisRng::new();
         rng.set_block_type(BlockType::IBlock);
-        rng.set_block_color(WHITE);
+        rng.set_block_color(ColorFamily::Green);
 
         bc.init_block_queue(&mut rng);
         bc.block_center_pos = (5, 5);
@@ -716,11 +903,11 @@ mod test {
         let mut mc: MapController = MapController::new(&TEST_CONSTANTS);
         let mut rng: MockTetrisRng = MockTetrisRng::new();
         rng.set_block_type(BlockType::IBlock);
-        rng.set_block_color(WHITE);
+        rng.set_block_color(ColorFamily::Green);
 
         bc.init_block_queue(&mut rng);
         bc.block_center_pos = (5, 5);
-        mc.spawn_block(Vec::from([(6, 5)]), WHITE);
+        mc.spawn_block(Vec::from([(6, 5)]), ColorFamily::Green);
 
         let game_over = bc.handle_rotate_clockwise(&mut mc, &mut rng);
 
@@ -734,7 +921,7 @@ mod test {
             .collect();
 
         for (x, y) in [(5, 4), (5, 5), (5, 6), (5, 7)] {
-            assert!(settled_fields.contains(&Field::new(x, y, WHITE, 2)));
+            assert!(settled_fields.contains(&Field::new(x, y, SETTLED_COLOR, 2)));
         }
     }
 
@@ -744,11 +931,11 @@ mod test {
         let mut mc: MapController = MapController::new(&TEST_CONSTANTS);
         let mut rng: MockTetrisRng = MockTetrisRng::new();
         rng.set_block_type(BlockType::LBlock);
-        rng.set_block_color(WHITE);
+        rng.set_block_color(ColorFamily::Green);
 
         bc.init_block_queue(&mut rng);
         bc.block_center_pos = (5, 0);
-        mc.spawn_block(Vec::from([(4, 0)]), WHITE);
+        mc.spawn_block(Vec::from([(4, 0)]), ColorFamily::Green);
 
         let game_over = bc.handle_rotate_clockwise(&mut mc, &mut rng);
 
@@ -761,7 +948,7 @@ mod test {
         let mut mc: MapController = MapController::new(&TEST_CONSTANTS);
         let mut rng: MockTetrisRng = MockTetrisRng::new();
         rng.set_block_type(BlockType::IBlock);
-        rng.set_block_color(WHITE);
+        rng.set_block_color(ColorFamily::Green);
 
         bc.init_block_queue(&mut rng);
         bc.block_center_pos = (5, 5);
@@ -781,7 +968,7 @@ mod test {
         let mut mc: MapController = MapController::new(&TEST_CONSTANTS);
         let mut rng: MockTetrisRng = MockTetrisRng::new();
         rng.set_block_type(BlockType::IBlock);
-        rng.set_block_color(WHITE);
+        rng.set_block_color(ColorFamily::Green);
 
         bc.init_block_queue(&mut rng);
         bc.block_center_pos = (5, 5);
@@ -798,11 +985,11 @@ mod test {
         let mut mc: MapController = MapController::new(&TEST_CONSTANTS);
         let mut rng: MockTetrisRng = MockTetrisRng::new();
         rng.set_block_type(BlockType::IBlock);
-        rng.set_block_color(WHITE);
+        rng.set_block_color(ColorFamily::Green);
 
         bc.init_block_queue(&mut rng);
         bc.block_center_pos = (5, 5);
-        mc.spawn_block(Vec::from([(6, 5)]), WHITE);
+        mc.spawn_block(Vec::from([(6, 5)]), ColorFamily::Green);
 
         let game_over = bc.handle_rotate_counter_clockwise(&mut mc, &mut rng);
 
@@ -816,7 +1003,7 @@ mod test {
             .collect();
 
         for (x, y) in [(5, 4), (5, 5), (5, 6), (5, 7)] {
-            assert!(settled_fields.contains(&Field::new(x, y, WHITE, 2)));
+            assert!(settled_fields.contains(&Field::new(x, y, SETTLED_COLOR, 2)));
         }
     }
 
@@ -826,11 +1013,11 @@ mod test {
         let mut mc: MapController = MapController::new(&TEST_CONSTANTS);
         let mut rng: MockTetrisRng = MockTetrisRng::new();
         rng.set_block_type(BlockType::LBlock);
-        rng.set_block_color(WHITE);
+        rng.set_block_color(ColorFamily::Green);
 
         bc.init_block_queue(&mut rng);
         bc.block_center_pos = (5, 0);
-        mc.spawn_block(Vec::from([(4, 0)]), WHITE);
+        mc.spawn_block(Vec::from([(4, 0)]), ColorFamily::Green);
 
         let game_over = bc.handle_rotate_counter_clockwise(&mut mc, &mut rng);
 
@@ -843,7 +1030,7 @@ mod test {
         let mut mc: MapController = MapController::new(&TEST_CONSTANTS);
         let mut rng: MockTetrisRng = MockTetrisRng::new();
         rng.set_block_type(BlockType::IBlock);
-        rng.set_block_color(WHITE);
+        rng.set_block_color(ColorFamily::Green);
 
         bc.init_block_queue(&mut rng);
         bc.block_center_pos = (5, 5);
@@ -862,17 +1049,341 @@ mod test {
         let mut bc: BlockController = BlockController::new(&TEST_CONSTANTS);
         let mut rng: MockTetrisRng = MockTetrisRng::new();
         rng.set_block_type(BlockType::IBlock);
-        rng.set_block_color(WHITE);
+        rng.set_block_color(ColorFamily::Green);
 
         bc.init_block_queue(&mut rng);
         bc.block_center_pos = (5, 5);
 
         let (miniature, color) = bc.get_next_block_miniature();
 
-        assert_eq!(color, WHITE);
+        assert_eq!(color, ColorFamily::Green);
         assert_eq!(miniature, Vec::from([(0, 0), (0, -1), (0, 1), (0, 2)]));
     }
 
+    #[test]
+    fn fall_height() {
+        let mut bc: BlockController = BlockController::new(&TEST_CONSTANTS);
+        let mut mc: MapController = MapController::new(&TEST_CONSTANTS);
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        rng.set_block_type(BlockType::SquareBlock);
+        bc.init_block_queue(&mut rng);
+
+        bc.handle_move_down(&mut mc, &mut rng);
+        bc.handle_move_down(&mut mc, &mut rng);
+        bc.handle_move_right(&mut mc, &mut rng);
+
+        assert_eq!(bc.fall_height, 2);
+
+        bc.get_new_block(&mut rng);
+
+        assert_eq!(bc.fall_height, 0);
+    }
+
+    #[test]
+    fn get_impact_momentum() {
+        let mut constants = get_test_constants();
+        constants.rules.impact_mode = ImpactMode::Shatter;
+        let mut bc: BlockController = BlockController::new(&constants);
+
+        for (fall_height, expected_momentum) in [
+            (0, 0),
+            (SHATTER_MIN_FALL_HEIGHT - 1, 0),
+            (SHATTER_MIN_FALL_HEIGHT, 1),
+            (
+                SHATTER_MIN_FALL_HEIGHT + SHATTER_FALL_HEIGHT_PER_MOMENTUM,
+                2,
+            ),
+            (1000, SHATTER_MAX_MOMENTUM),
+        ] {
+            bc.fall_height = fall_height;
+            assert_eq!(bc.get_impact_momentum(), expected_momentum);
+        }
+    }
+
+    #[test]
+    fn get_impact_momentum_settle_mode() {
+        let mut bc: BlockController = BlockController::new(&TEST_CONSTANTS);
+
+        bc.fall_height = 1000;
+
+        assert_eq!(bc.get_impact_momentum(), 0);
+    }
+
+    #[test]
+    fn settle_block_shatter() {
+        let mut constants = get_test_constants();
+        constants.map_height = 100;
+        constants.rules.impact_mode = ImpactMode::Shatter;
+        let mut bc: BlockController = BlockController::new(&constants);
+        let mut mc: MapController = MapController::new(&constants);
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        rng.set_block_type(BlockType::SquareBlock);
+        rng.set_scatter_momentum(5);
+        bc.init_block_queue(&mut rng);
+
+        while bc.fall_height < SHATTER_MIN_FALL_HEIGHT {
+            bc.handle_move_down(&mut mc, &mut rng);
+        }
+        bc.settle_block(&mut mc, &mut rng);
+
+        let momentums: Vec<i32> = mc
+            .get_fields_to_draw()
+            .iter()
+            .map(|field| field.get_momentum())
+            .collect();
+        assert_eq!(momentums, Vec::from([-1, 1, -1, 1]));
+    }
+
+    #[test]
+    fn handle_cursor_move() {
+        let mut bc: BlockController = BlockController::new(&TEST_CONSTANTS);
+        let mut mc: MapController = MapController::new(&TEST_CONSTANTS);
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        rng.set_block_type(BlockType::SquareBlock);
+        bc.init_block_queue(&mut rng);
+        let start = bc.block_center_pos;
+
+        bc.handle_cursor_move(&mc, (0, -1)); // Can't be lifted above where it spawned
+        assert_eq!(bc.block_center_pos, start);
+
+        for _ in 0..3 {
+            bc.handle_cursor_move(&mc, (0, 1));
+        }
+        bc.handle_cursor_move(&mc, (0, -1));
+        assert_eq!(bc.block_center_pos, (start.0, start.1 + 2));
+
+        mc.spawn_block(Vec::from([(start.0 - 1, start.1 + 2)]), ColorFamily::Red);
+        bc.handle_cursor_move(&mc, (-1, 0)); // Sand in the way, but touching it doesn't settle
+        assert_eq!(bc.block_center_pos, (start.0, start.1 + 2));
+        assert_eq!(bc.get_settled_block_count(), 0);
+    }
+
+    #[test]
+    fn handle_place() {
+        let mut bc: BlockController = BlockController::new(&TEST_CONSTANTS);
+        let mut mc: MapController = MapController::new(&TEST_CONSTANTS);
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        rng.set_block_type(BlockType::SquareBlock);
+        bc.init_block_queue(&mut rng);
+        bc.block_center_pos.1 = -1;
+
+        bc.handle_place(&mut mc, &mut rng); // Sticking out above the map
+        assert_eq!(bc.get_settled_block_count(), 0);
+
+        bc.handle_cursor_move(&mc, (0, 1));
+        bc.handle_place(&mut mc, &mut rng);
+        assert_eq!(bc.get_settled_block_count(), 1);
+        assert_eq!(mc.get_fields_to_draw().len(), 4);
+    }
+
+    fn get_lock_delay_constants() -> TetrisConstants {
+        let mut constants = get_test_constants();
+        constants.rules.settle_policy = SettlePolicy::AfterLockDelay;
+        constants.rules.lock_delay = LockDelay {
+            ticks: Some(3),
+            max_resets: 1,
+        };
+
+        constants
+    }
+
+    #[test]
+    fn lock_delay_settles_after_delay() {
+        let constants = get_lock_delay_constants();
+        let mut bc: BlockController = BlockController::new(&constants);
+        let mut mc: MapController = MapController::new(&constants);
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        rng.set_block_type(BlockType::SquareBlock);
+        bc.init_block_queue(&mut rng);
+        bc.block_center_pos = (5, 8);
+
+        bc.tick_and_check_game_over(&mut mc, &mut rng);
+        bc.tick_and_check_game_over(&mut mc, &mut rng);
+
+        assert_eq!(bc.block_center_pos, (5, 8)); // Still resting on the floor
+        assert_eq!(mc.get_fields_to_draw().len(), 0);
+
+        bc.tick_and_check_game_over(&mut mc, &mut rng);
+
+        assert_eq!(bc.block_center_pos, constants.block_starting_pos);
+        assert_eq!(mc.get_fields_to_draw().len(), 4);
+    }
+
+    #[test]
+    fn lock_delay_reset_by_move() {
+        let constants = get_lock_delay_constants();
+        let mut bc: BlockController = BlockController::new(&constants);
+        let mut mc: MapController = MapController::new(&constants);
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        rng.set_block_type(BlockType::SquareBlock);
+        bc.init_block_queue(&mut rng);
+        bc.block_center_pos = (5, 8);
+
+        bc.tick_and_check_game_over(&mut mc, &mut rng);
+        bc.tick_and_check_game_over(&mut mc, &mut rng);
+        bc.handle_move_left(&mut mc, &mut rng);
+
+        assert_eq!(bc.lock_timer, Some(0));
+        assert_eq!(bc.lock_resets, 1);
+
+        bc.handle_move_left(&mut mc, &mut rng); // Out of resets
+
+        assert_eq!(bc.lock_timer, Some(0));
+        assert_eq!(bc.lock_resets, 1);
+
+        bc.tick_and_check_game_over(&mut mc, &mut rng);
+        bc.tick_and_check_game_over(&mut mc, &mut rng);
+
+        assert_eq!(bc.block_center_pos, (3, 8));
+
+        bc.tick_and_check_game_over(&mut mc, &mut rng);
+
+        assert_eq!(bc.block_center_pos, constants.block_starting_pos);
+        assert_eq!(mc.get_fields_to_draw().len(), 4);
+    }
+
+    #[test]
+    fn lock_delay_cleared_when_falling() {
+        let constants = get_lock_delay_constants();
+        let mut bc: BlockController = BlockController::new(&constants);
+        let mut mc: MapController = MapController::new(&constants);
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        rng.set_block_type(BlockType::SquareBlock);
+        bc.init_block_queue(&mut rng);
+        bc.block_center_pos = (5, 5);
+        mc.spawn_block(Vec::from([(5, 7), (6, 7)]), ColorFamily::Red);
+        /*
+            5|   [o][o]
+            6|   [o][o]
+            7|   [x][x]      <- Sliding right drops the block off the ledge
+               4  5  6  7
+        */
+
+        bc.tick_and_check_game_over(&mut mc, &mut rng);
+
+        assert_eq!(bc.lock_timer, Some(1));
+
+        bc.handle_move_right(&mut mc, &mut rng);
+        bc.handle_move_right(&mut mc, &mut rng);
+        bc.tick_and_check_game_over(&mut mc, &mut rng);
+
+        assert_eq!(bc.block_center_pos, (7, 6));
+        assert_eq!(bc.lock_timer, None);
+    }
+
+    #[test]
+    fn lock_delay_sideways_sand_contact() {
+        let constants = get_lock_delay_constants();
+        let mut bc: BlockController = BlockController::new(&constants);
+        let mut mc: MapController = MapController::new(&constants);
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        rng.set_block_type(BlockType::SquareBlock);
+        bc.init_block_queue(&mut rng);
+        bc.block_center_pos = (5, 7);
+        mc.spawn_block(Vec::from([(7, 7)]), ColorFamily::Red);
+
+        bc.handle_move_right(&mut mc, &mut rng);
+
+        assert_eq!(bc.block_center_pos, (5, 7)); // Bumping the pile doesn't settle the block
+        assert_eq!(mc.get_fields_to_draw().len(), 1);
+    }
+
+    #[test]
+    fn lock_delay_rotate_into_sand() {
+        let constants = get_lock_delay_constants();
+        let mut bc: BlockController = BlockController::new(&constants);
+        let mut mc: MapController = MapController::new(&constants);
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        rng.set_block_type(BlockType::LBlock);
+        bc.init_block_queue(&mut rng);
+        bc.block_center_pos = (5, 5);
+        mc.spawn_block(Vec::from([(4, 5), (6, 5)]), ColorFamily::Red);
+
+        bc.handle_rotate_clockwise(&mut mc, &mut rng);
+        bc.handle_rotate_counter_clockwise(&mut mc, &mut rng);
+
+        assert_eq!(bc.block_center_pos, (5, 5));
+        assert_eq!(bc.get_current_block().get_schema(), L_BLOCK);
+        assert_eq!(mc.get_fields_to_draw().len(), 2);
+    }
+
+    fn get_downward_contact_constants() -> TetrisConstants {
+        let mut constants = get_test_constants();
+        constants.rules.settle_policy = SettlePolicy::DownwardContact;
+
+        constants
+    }
+
+    #[test]
+    fn downward_contact_ignores_sideways_sand() {
+        let constants = get_downward_contact_constants();
+        let mut bc: BlockController = BlockController::new(&constants);
+        let mut mc: MapController = MapController::new(&constants);
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        rng.set_block_type(BlockType::SquareBlock);
+        bc.init_block_queue(&mut rng);
+        bc.block_center_pos = (5, 5);
+        mc.spawn_block(Vec::from([(7, 5), (4, 6)]), ColorFamily::Red);
+
+        let game_over =
+            bc.handle_move_right(&mut mc, &mut rng) || bc.handle_move_left(&mut mc, &mut rng);
+
+        assert!(!game_over);
+        assert_eq!(bc.block_center_pos, (5, 5));
+        assert_eq!(mc.get_fields_to_draw().len(), 2);
+    }
+
+    #[test]
+    fn downward_contact_ignores_rotation_into_sand() {
+        let constants = get_downward_contact_constants();
+        let mut bc: BlockController = BlockController::new(&constants);
+        let mut mc: MapController = MapController::new(&constants);
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        rng.set_block_type(BlockType::LBlock);
+        bc.init_block_queue(&mut rng);
+        bc.block_center_pos = (5, 5);
+        mc.spawn_block(Vec::from([(4, 5), (6, 5)]), ColorFamily::Red);
+
+        bc.handle_rotate_clockwise(&mut mc, &mut rng);
+        bc.handle_rotate_counter_clockwise(&mut mc, &mut rng);
+
+        assert_eq!(bc.block_center_pos, (5, 5));
+        assert_eq!(mc.get_fields_to_draw().len(), 2);
+    }
+
+    #[test]
+    fn downward_contact_settles_on_landing() {
+        let constants = get_downward_contact_constants();
+        let mut bc: BlockController = BlockController::new(&constants);
+        let mut mc: MapController = MapController::new(&constants);
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        rng.set_block_type(BlockType::SquareBlock);
+        bc.init_block_queue(&mut rng);
+        bc.block_center_pos = (5, 6);
+        mc.spawn_block(Vec::from([(5, 8)]), ColorFamily::Red);
+
+        bc.handle_move_down(&mut mc, &mut rng);
+
+        assert_eq!(bc.block_center_pos, constants.block_starting_pos);
+        assert_eq!(mc.get_fields_to_draw().len(), 5);
+    }
+
+    #[test]
+    fn does_contact_settle() {
+        let mut constants = get_test_constants();
+        for (settle_policy, expected_downward, expected_sideways) in [
+            (SettlePolicy::AnyContact, true, true),
+            (SettlePolicy::DownwardContact, true, false),
+            (SettlePolicy::AfterLockDelay, false, false),
+        ] {
+            constants.rules.settle_policy = settle_policy;
+            let bc: BlockController = BlockController::new(&constants);
+
+            assert_eq!(bc.does_contact_settle(true), expected_downward);
+            assert_eq!(bc.does_contact_settle(false), expected_sideways);
+        }
+    }
+
     #[test]
     fn tick_and_check_game_over() {
         let mut bc: BlockController = BlockController::new(&TEST_CONSTANTS);
@@ -0,0 +1,566 @@
+use macroquad::input::KeyCode;
+
+use crate::{
+    constants::{
+        colors::WHITE,
+        editor_constants::{DEFAULT_BRUSH_RADIUS, MAX_BRUSH_RADIUS, UNDO_LIMIT},
+        interface_constants::{
+            BRUSH_TEXT, ERASER_TEXT, FILL_TEXT, SHAPE_TEXT, SIZE_TEXT, UNSAVED_TEXT,
+        },
+        TetrisConstants,
+    },
+    controllers::map_controller::MapController,
+    objects::{color_family::ColorFamily, piece_set::PieceSet, puzzle::Puzzle},
+    renderers::{renderer::Renderer, viewport::Viewport},
+    utils::brush::{get_disc, get_key_color},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EditorTool {
+    Brush,
+    Fill,  // Repaints the region under the cursor
+    Shape, // Stamps a piece from the piece set
+}
+
+// Paints the board of a puzzle, which is saved to the puzzle's file. Grains are all the same sand,
+// so the brush only picks a color and a size, there's no material to choose
+pub struct EditorController<'a, G>
+where
+    G: Renderer,
+{
+    tool: EditorTool,
+    family: Option<ColorFamily>, // None erases
+    brush_radius: i32,
+    shape_index: usize,
+    cursor: (i32, i32),
+    last_stroke_point: Option<(i32, i32)>, // Where the brush was when the mouse last moved while held
+    undo_stack: Vec<Vec<(i32, i32, ColorFamily)>>,
+    redo_stack: Vec<Vec<(i32, i32, ColorFamily)>>,
+    is_dirty: bool, // Changed since the last save
+    puzzle: Puzzle, // Keeps the goal and the pieces, only the board is edited
+    path: String,
+    piece_set: PieceSet,
+    map_controller: MapController<'a>,
+    renderer: &'a mut G,
+    constants: &'a TetrisConstants,
+}
+
+impl<'a, G> EditorController<'a, G>
+where
+    G: Renderer,
+{
+    pub fn new(
+        renderer: &'a mut G,
+        constants: &'a TetrisConstants,
+        piece_set: PieceSet,
+        puzzle: Puzzle,
+        path: &str,
+    ) -> Self {
+        let mut map_controller = MapController::new(constants);
+        map_controller.load_layout(&puzzle.get_layout(constants.map_height));
+
+        EditorController {
+            tool: EditorTool::Brush,
+            family: Some(ColorFamily::Red),
+            brush_radius: DEFAULT_BRUSH_RADIUS,
+            shape_index: 0,
+            cursor: (0, 0),
+            last_stroke_point: None,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            is_dirty: false,
+            puzzle,
+            path: path.to_string(),
+            piece_set,
+            map_controller,
+            renderer,
+            constants,
+        }
+    }
+
+    pub async fn tick(&mut self) {
+        let viewport = self.get_viewport();
+        self.renderer
+            .draw_background(&viewport, self.constants.theme);
+        self.renderer.draw_fields(
+            &self.map_controller.get_fields_to_draw(),
+            &viewport,
+            self.constants.theme,
+        );
+        self.draw_cursor(&viewport);
+        self.draw_hud(&viewport);
+        self.renderer.present().await;
+    }
+
+    fn get_viewport(&self) -> Viewport {
+        Viewport::fit(
+            self.renderer.get_screen_size(),
+            (self.constants.map_width, self.constants.map_height),
+            self.constants.grain_side_size,
+        )
+    }
+
+    pub fn set_cursor(&mut self, (x, y): (f32, f32)) {
+        self.cursor = self.get_viewport().screen_to_map(x, y);
+    }
+
+    // Mouse button went down, every tool but the brush is done right away
+    pub fn start_stroke(&mut self) {
+        if !self.get_viewport().contains(self.cursor.0, self.cursor.1) {
+            return;
+        }
+
+        self.push_undo();
+        match self.tool {
+            EditorTool::Brush => {
+                self.paint(self.get_brush_grains(self.cursor));
+                self.last_stroke_point = Some(self.cursor);
+            }
+            EditorTool::Fill => {
+                self.paint(self.map_controller.get_region(self.cursor.0, self.cursor.1))
+            }
+            EditorTool::Shape => self.paint(self.get_shape_grains()),
+        }
+    }
+
+    // Mouse button held, the brush follows the cursor without leaving gaps
+    pub fn continue_stroke(&mut self) {
+        let last_point = match self.last_stroke_point {
+            Some(point) if point != self.cursor => point,
+            _ => return,
+        };
+
+        let steps = (self.cursor.0 - last_point.0)
+            .abs()
+            .max((self.cursor.1 - last_point.1).abs());
+        for step in 1..=steps {
+            let progress = step as f32 / steps as f32;
+            let point = (
+                last_point.0 + ((self.cursor.0 - last_point.0) as f32 * progress).round() as i32,
+                last_point.1 + ((self.cursor.1 - last_point.1) as f32 * progress).round() as i32,
+            );
+            self.paint(self.get_brush_grains(point));
+        }
+        self.last_stroke_point = Some(self.cursor);
+    }
+
+    pub fn end_stroke(&mut self) {
+        self.last_stroke_point = None;
+    }
+
+    pub fn do_key(&mut self, key: KeyCode) -> Result<(), String> {
+        if let Some(family) = get_key_color(key) {
+            self.family = Some(family);
+            return Ok(());
+        }
+
+        let shape_count = self.piece_set.get_pieces().len();
+        match key {
+            KeyCode::Key0 => self.family = None,
+            KeyCode::B => self.tool = EditorTool::Brush,
+            KeyCode::F => self.tool = EditorTool::Fill,
+            KeyCode::T => self.tool = EditorTool::Shape,
+            KeyCode::E => self.shape_index = (self.shape_index + 1) % shape_count,
+            KeyCode::Q => self.shape_index = (self.shape_index + shape_count - 1) % shape_count,
+            KeyCode::Equal => self.brush_radius = (self.brush_radius + 1).min(MAX_BRUSH_RADIUS),
+            KeyCode::Minus => self.brush_radius = (self.brush_radius - 1).max(0),
+            KeyCode::Z => self.undo(),
+            KeyCode::Y => self.redo(),
+            KeyCode::C => {
+                self.push_undo();
+                self.map_controller.clear();
+            }
+            KeyCode::S => self.save()?,
+            KeyCode::L => self.reload()?,
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn paint(&mut self, grains: Vec<(i32, i32)>) {
+        for (x, y) in grains {
+            self.map_controller.paint_grain(x, y, self.family);
+        }
+    }
+
+    fn get_brush_grains(&self, point: (i32, i32)) -> Vec<(i32, i32)> {
+        get_disc(point, self.brush_radius)
+    }
+
+    // The piece's pivot square starts at the cursor
+    fn get_shape_grains(&self) -> Vec<(i32, i32)> {
+        let chunk_side = self.constants.block_chunk_side;
+        let mut grains = Vec::new();
+        for (cell_x, cell_y) in self.piece_set.get_piece(self.shape_index).get_cells() {
+            for x_offset in 0..chunk_side {
+                for y_offset in 0..chunk_side {
+                    grains.push((
+                        self.cursor.0 + *cell_x as i32 * chunk_side + x_offset,
+                        self.cursor.1 + *cell_y as i32 * chunk_side + y_offset,
+                    ));
+                }
+            }
+        }
+
+        grains
+    }
+
+    fn get_cursor_grains(&self) -> Vec<(i32, i32)> {
+        match self.tool {
+            EditorTool::Brush => self.get_brush_grains(self.cursor),
+            EditorTool::Fill => Vec::from([self.cursor]),
+            EditorTool::Shape => self.get_shape_grains(),
+        }
+    }
+
+    // Every change can be undone, starting a new one forgets what was undone
+    fn push_undo(&mut self) {
+        if self.undo_stack.len() == UNDO_LIMIT {
+            self.undo_stack.remove(0);
+        }
+        self.undo_stack.push(self.map_controller.get_layout());
+        self.redo_stack.clear();
+        self.is_dirty = true;
+    }
+
+    fn undo(&mut self) {
+        if let Some(layout) = self.undo_stack.pop() {
+            self.redo_stack.push(self.map_controller.get_layout());
+            self.map_controller.load_layout(&layout);
+            self.is_dirty = true;
+        }
+    }
+
+    fn redo(&mut self) {
+        if let Some(layout) = self.redo_stack.pop() {
+            self.undo_stack.push(self.map_controller.get_layout());
+            self.map_controller.load_layout(&layout);
+            self.is_dirty = true;
+        }
+    }
+
+    fn save(&mut self) -> Result<(), String> {
+        self.puzzle.set_board(
+            &self.map_controller.get_layout(),
+            (self.constants.map_width, self.constants.map_height),
+        );
+        self.puzzle.save(&self.path)?;
+        self.is_dirty = false;
+
+        Ok(())
+    }
+
+    // Throws away the changes since the last save, the reload itself can be undone
+    fn reload(&mut self) -> Result<(), String> {
+        let puzzle = Puzzle::load_or_blank(&self.path, &self.piece_set)?;
+        self.constants.check_puzzle(&puzzle)?;
+
+        self.push_undo();
+        self.map_controller
+            .load_layout(&puzzle.get_layout(self.constants.map_height));
+        self.puzzle = puzzle;
+        self.is_dirty = false;
+
+        Ok(())
+    }
+
+    fn draw_cursor(&mut self, viewport: &Viewport) {
+        let color = match self.family {
+            Some(family) => self.constants.theme.get_display_color(family),
+            None => WHITE,
+        };
+        let grains = self.get_cursor_grains();
+        self.renderer.draw_cursor(&grains, viewport, color);
+    }
+
+    fn get_hud_lines(&self) -> Vec<String> {
+        let color_name = match self.family {
+            Some(family) => family.get_name(),
+            None => ERASER_TEXT,
+        };
+        let mut lines = match self.tool {
+            EditorTool::Brush => Vec::from([
+                format!("{} {}", BRUSH_TEXT, color_name),
+                format!("{}:{}", SIZE_TEXT, self.brush_radius),
+            ]),
+            EditorTool::Fill => Vec::from([format!("{} {}", FILL_TEXT, color_name)]),
+            EditorTool::Shape => Vec::from([format!(
+                "{} {} {}",
+                SHAPE_TEXT,
+                self.piece_set.get_piece(self.shape_index).get_name(),
+                color_name
+            )]),
+        };
+        if self.is_dirty {
+            lines.push(UNSAVED_TEXT.to_string());
+        }
+
+        lines
+    }
+
+    fn draw_hud(&mut self, viewport: &Viewport) {
+        let lines = self.get_hud_lines();
+        self.renderer
+            .draw_hud_lines(&lines, viewport, self.constants.theme);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        constants::{interface_constants::CURSOR_OPACITY, GameRules},
+        objects::theme::Theme,
+        renderers::recording_renderer::{DrawCommand, RecordingRenderer},
+        utils::block_on::block_on,
+    };
+
+    use super::*;
+
+    const TEST_CONSTANTS: TetrisConstants = TetrisConstants {
+        map_width: 10,
+        map_height: 10,
+        block_chunk_side: 1,
+        grain_side_size: 1,
+        preview_block_chunk_side: 1,
+        block_starting_pos: (0, 0),
+        rules: GameRules::CLASSIC,
+        theme: &Theme::CLASSIC,
+    };
+
+    fn get_editor(renderer: &mut RecordingRenderer) -> EditorController<'_, RecordingRenderer> {
+        renderer.set_screen_size((10.0, 10.0));
+        EditorController::new(
+            renderer,
+            &TEST_CONSTANTS,
+            PieceSet::classic(),
+            Puzzle::blank(&PieceSet::classic()),
+            "editor_test.txt",
+        )
+    }
+
+    // The screen is set to the map at one pixel per grain
+    fn click(editor: &mut EditorController<RecordingRenderer>, (x, y): (i32, i32)) {
+        editor.set_cursor((x as f32 + 0.5, y as f32 + 0.5));
+        editor.start_stroke();
+        editor.end_stroke();
+    }
+
+    fn get_sorted_layout(
+        editor: &EditorController<RecordingRenderer>,
+    ) -> Vec<(i32, i32, ColorFamily)> {
+        let mut layout = editor.map_controller.get_layout();
+        layout.sort_by_key(|(x, y, _)| (*x, *y));
+        layout
+    }
+
+    #[test]
+    fn brush_paints_disc() {
+        let mut renderer = RecordingRenderer::new();
+        let mut editor = get_editor(&mut renderer);
+        editor.do_key(KeyCode::Minus).unwrap(); // Radius 1
+
+        click(&mut editor, (5, 5));
+
+        assert_eq!(
+            get_sorted_layout(&editor)
+                .iter()
+                .map(|(x, y, _)| (*x, *y))
+                .collect::<Vec<_>>(),
+            Vec::from([(4, 5), (5, 4), (5, 5), (5, 6), (6, 5)])
+        );
+    }
+
+    #[test]
+    fn brush_stroke_leaves_no_gaps() {
+        let mut renderer = RecordingRenderer::new();
+        let mut editor = get_editor(&mut renderer);
+        for _ in 0..DEFAULT_BRUSH_RADIUS {
+            editor.do_key(KeyCode::Minus).unwrap();
+        }
+
+        editor.set_cursor((0.5, 9.5));
+        editor.start_stroke();
+        editor.set_cursor((9.5, 9.5));
+        editor.continue_stroke();
+        editor.end_stroke();
+
+        assert_eq!(editor.map_controller.get_layout().len(), 10);
+    }
+
+    #[test]
+    fn eraser() {
+        let mut renderer = RecordingRenderer::new();
+        let mut editor = get_editor(&mut renderer);
+        click(&mut editor, (5, 5));
+
+        editor.do_key(KeyCode::Key0).unwrap();
+        click(&mut editor, (5, 5));
+
+        assert!(editor.map_controller.get_layout().is_empty());
+    }
+
+    #[test]
+    fn fill() {
+        let mut renderer = RecordingRenderer::new();
+        let mut editor = get_editor(&mut renderer);
+        editor.do_key(KeyCode::F).unwrap();
+        editor.do_key(KeyCode::Key2).unwrap();
+
+        click(&mut editor, (0, 0));
+
+        let layout = editor.map_controller.get_layout();
+        assert_eq!(layout.len(), 100);
+        assert!(layout
+            .iter()
+            .all(|(_, _, family)| *family == ColorFamily::Blue));
+    }
+
+    #[test]
+    fn shape() {
+        let mut renderer = RecordingRenderer::new();
+        let mut editor = get_editor(&mut renderer);
+        editor.do_key(KeyCode::T).unwrap();
+        editor.do_key(KeyCode::E).unwrap();
+        editor.do_key(KeyCode::E).unwrap(); // O, after L and J
+
+        click(&mut editor, (3, 3));
+
+        assert_eq!(
+            get_sorted_layout(&editor)
+                .iter()
+                .map(|(x, y, _)| (*x, *y))
+                .collect::<Vec<_>>(),
+            Vec::from([(3, 3), (3, 4), (4, 3), (4, 4)])
+        );
+    }
+
+    #[test]
+    fn click_off_map() {
+        let mut renderer = RecordingRenderer::new();
+        let mut editor = get_editor(&mut renderer);
+
+        click(&mut editor, (-3, 5));
+
+        assert!(editor.undo_stack.is_empty());
+        assert!(!editor.is_dirty);
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let mut renderer = RecordingRenderer::new();
+        let mut editor = get_editor(&mut renderer);
+        click(&mut editor, (2, 2));
+        let first = get_sorted_layout(&editor);
+        click(&mut editor, (7, 7));
+        let second = get_sorted_layout(&editor);
+
+        editor.do_key(KeyCode::Z).unwrap();
+        assert_eq!(get_sorted_layout(&editor), first);
+
+        editor.do_key(KeyCode::Z).unwrap();
+        assert!(get_sorted_layout(&editor).is_empty());
+
+        editor.do_key(KeyCode::Z).unwrap(); // Nothing left to undo
+        editor.do_key(KeyCode::Y).unwrap();
+        editor.do_key(KeyCode::Y).unwrap();
+        assert_eq!(get_sorted_layout(&editor), second);
+
+        editor.do_key(KeyCode::Z).unwrap();
+        click(&mut editor, (5, 5)); // A new change forgets the redo
+        editor.do_key(KeyCode::Y).unwrap();
+        assert_ne!(get_sorted_layout(&editor), second);
+    }
+
+    #[test]
+    fn clear_can_be_undone() {
+        let mut renderer = RecordingRenderer::new();
+        let mut editor = get_editor(&mut renderer);
+        click(&mut editor, (2, 2));
+        let painted = get_sorted_layout(&editor);
+
+        editor.do_key(KeyCode::C).unwrap();
+        assert!(editor.map_controller.get_layout().is_empty());
+
+        editor.do_key(KeyCode::Z).unwrap();
+        assert_eq!(get_sorted_layout(&editor), painted);
+    }
+
+    #[test]
+    fn undo_limit() {
+        let mut renderer = RecordingRenderer::new();
+        let mut editor = get_editor(&mut renderer);
+
+        for _ in 0..UNDO_LIMIT + 5 {
+            click(&mut editor, (2, 2));
+        }
+
+        assert_eq!(editor.undo_stack.len(), UNDO_LIMIT);
+    }
+
+    #[test]
+    fn hud_lines() {
+        let mut renderer = RecordingRenderer::new();
+        let mut editor = get_editor(&mut renderer);
+
+        assert_eq!(editor.get_hud_lines(), Vec::from(["Brush red", "Size:2"]));
+
+        editor.do_key(KeyCode::T).unwrap();
+        editor.do_key(KeyCode::Q).unwrap(); // Wraps around to the last piece
+        editor.do_key(KeyCode::Key0).unwrap();
+        click(&mut editor, (2, 2));
+
+        assert_eq!(
+            editor.get_hud_lines(),
+            Vec::from(["Shape T eraser", "Unsaved"])
+        );
+    }
+
+    #[test]
+    fn save_and_reload() {
+        let path = std::env::temp_dir().join("sand_tetris_editor_test.txt");
+        let path = path.to_str().unwrap();
+        let mut renderer = RecordingRenderer::new();
+        renderer.set_screen_size((10.0, 10.0));
+        let mut editor = EditorController::new(
+            &mut renderer,
+            &TEST_CONSTANTS,
+            PieceSet::classic(),
+            Puzzle::blank(&PieceSet::classic()),
+            path,
+        );
+        click(&mut editor, (5, 8));
+        let painted = get_sorted_layout(&editor);
+
+        editor.do_key(KeyCode::S).unwrap();
+        assert!(!editor.is_dirty);
+
+        editor.do_key(KeyCode::C).unwrap();
+        editor.do_key(KeyCode::L).unwrap();
+
+        assert_eq!(get_sorted_layout(&editor), painted);
+        assert_eq!(
+            Puzzle::load(path).unwrap().get_layout(10).len(),
+            painted.len()
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn tick_draws_cursor() {
+        let mut renderer = RecordingRenderer::new();
+        let mut editor = get_editor(&mut renderer);
+        editor.do_key(KeyCode::F).unwrap();
+        editor.set_cursor((4.5, 4.5));
+
+        block_on(editor.tick());
+
+        let commands = renderer.get_commands();
+        assert_eq!(commands[0], DrawCommand::Background);
+        assert!(commands.contains(&DrawCommand::Overlay(
+            (4.0, 4.0, 1.0, 1.0),
+            Theme::CLASSIC.get_display_color(ColorFamily::Red),
+            CURSOR_OPACITY
+        )));
+    }
+}
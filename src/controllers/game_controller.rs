@@ -1,70 +1,199 @@
+use std::{f32::consts::TAU, time::Duration};
+
 use macroquad::{color::Color, input::KeyCode};
 
 use crate::{
     constants::{
-        animation_constants::DEMOLISHION_CHUNK_SIZE,
-        colors::{BLACK, WHITE},
+        animation_constants::{
+            BANNER_DURATION, CLEAR_VANISH_DURATION, GAME_OVER_FADE_DURATION,
+            GAME_OVER_FADE_OPACITY, SCORE_POPUP_DURATION, SCORE_POPUP_RISE, SCREEN_SHAKE_AMPLITUDE,
+            SCREEN_SHAKE_DURATION, SCREEN_SHAKE_WAVES,
+        },
+        colors::{RED, WHITE},
+        garbage_constants::{
+            GARBAGE_WARNING_DURATION, GARBAGE_WARNING_FLASHES, GARBAGE_WARNING_OPACITY,
+        },
         interface_constants::{
+            ALL_CLEAR_FONT_SIZE, ALL_CLEAR_TEXT, BEST_TEXT, CHAIN_TEXT, COMBO_TEXT, CURSOR_TEXT,
             GAME_OVER_BOTTOM_FONT_SIZE, GAME_OVER_BOTTOM_TEXT, GAME_OVER_FONT_SIZE,
-            GAME_OVER_OUTLINE_WIDTH, GAME_OVER_TEXT, H_BORDER_OFFSET, SCORE_FONT_SIZE,
-            SCORE_OUTLINE_WIDTH, SCORE_TEXT, V_BORDER_OFFSET,
+            GAME_OVER_OUTLINE_WIDTH, GAME_OVER_TEXT, GRAINS_LEFT_TEXT, H_BORDER_OFFSET, LEVEL_TEXT,
+            MARATHON_FINISHED_TEXT, NEW_RECORD_TEXT, PIECES_LEFT_TEXT, PUZZLE_FAILED_TEXT,
+            PUZZLE_SOLVED_TEXT, SCORE_OUTLINE_WIDTH, SCORE_POPUP_FONT_SIZE,
+            SCORE_POPUP_LINE_SPACING, SCORE_TEXT, SPRINT_FINISHED_TEXT, SURVIVE_TEXT, TIME_TEXT,
+            ULTRA_FINISHED_TEXT, V_BORDER_OFFSET,
         },
-        map_constants::{MAP_HEIGHT, MAP_WIDTH},
+        mode_constants::ZEN_ERASED_SHARE,
         TetrisConstants,
     },
     controllers::{
-        block_controller::BlockController, graphic_controller::GraphicController,
-        map_controller::MapController,
+        block_controller::BlockController, map_controller::MapController,
+        particle_controller::ParticleController,
+    },
+    objects::{
+        game_mode::GameMode,
+        garbage::Garbage,
+        high_scores::{format_time, HighScores},
+        piece_set::PieceSet,
+        puzzle::{Puzzle, PuzzleGoal},
+        theme::mix_colors,
+        timeline::{Effect, Timeline},
     },
-    utils::tetris_rng::TetrisRng,
+    renderers::{renderer::Renderer, viewport::Viewport},
+    utils::{clock::Clock, tetris_rng::TetrisRng},
 };
 
-pub struct GameController<'a, R>
+pub struct GameController<'a, R, G, C>
 where
     R: TetrisRng,
+    G: Renderer,
+    C: Clock,
 {
     score: u32,
+    combo: u32,                    // Blocks in a row that each brought a clear
+    chain: u32,                    // Clears set off by the same block
+    last_clear_block: Option<u32>, // Settled block count at the last clear
+    cleared_grains: u32,
     is_game_over: bool,
+    is_goal_reached: bool, // The game ended by winning the mode instead of topping out
+    started_at: Duration,
+    ended_at: Option<Duration>,
+    high_scores: HighScores,
+    is_cursor_active: bool, // Free placement in zen mode, the block doesn't fall on its own
+    puzzle: Option<Puzzle>,
+    checked_blocks: u32, // Settled block count when the puzzle goal was last checked
+    garbage_at: Duration, // When the next garbage is due
+    garbage_blocks: u32, // Settled block count when the garbage was last checked
     block_controller: BlockController<'a>,
     map_controller: MapController<'a>,
+    particle_controller: ParticleController<'a>,
+    timeline: Timeline,
     rng: &'a mut R,
+    renderer: &'a mut G,
+    clock: &'a C,
     constants: &'a TetrisConstants,
 }
 
-impl<'a, R> GameController<'a, R>
+impl<'a, R, G, C> GameController<'a, R, G, C>
 where
     R: TetrisRng,
+    G: Renderer,
+    C: Clock,
 {
-    pub fn new(rng: &'a mut R, constants: &'a TetrisConstants) -> Self {
+    pub fn new(
+        rng: &'a mut R,
+        renderer: &'a mut G,
+        clock: &'a C,
+        constants: &'a TetrisConstants,
+    ) -> Self {
         let block_controller: BlockController = BlockController::new(constants);
         let map_controller: MapController = MapController::new(constants);
+        let particle_controller: ParticleController = ParticleController::new(constants);
 
         GameController {
             score: 0,
+            combo: 0,
+            chain: 0,
+            last_clear_block: None,
+            cleared_grains: 0,
             is_game_over: false,
+            is_goal_reached: false,
+            started_at: clock.now(),
+            ended_at: None,
+            high_scores: HighScores::new(),
+            is_cursor_active: false,
+            puzzle: None,
+            checked_blocks: 0,
+            garbage_at: clock.now(),
+            garbage_blocks: 0,
             block_controller,
             map_controller,
+            particle_controller,
+            timeline: Timeline::new(),
             rng,
+            renderer,
+            clock,
             constants,
         }
     }
 
+    pub fn get_renderer(&self) -> &G {
+        self.renderer
+    }
+
+    pub fn set_piece_set(&mut self, piece_set: PieceSet) {
+        self.block_controller.set_piece_set(piece_set);
+    }
+
+    pub fn set_high_scores(&mut self, high_scores: HighScores) {
+        self.high_scores = high_scores;
+    }
+
+    pub fn get_high_scores(&self) -> &HighScores {
+        &self.high_scores
+    }
+
+    // The board is laid out and the pieces start over on every reset, the rng has to follow the puzzle's script
+    pub fn set_puzzle(&mut self, puzzle: Puzzle) {
+        self.puzzle = Some(puzzle);
+    }
+
+    pub fn toggle_cursor(&mut self) {
+        self.is_cursor_active = !self.is_cursor_active && self.get_mode() == GameMode::Zen;
+    }
+
+    fn get_mode(&self) -> GameMode {
+        self.constants.rules.mode
+    }
+
+    // Game time, stopped once the game is over
+    fn get_elapsed(&self) -> Duration {
+        self.ended_at.unwrap_or(self.clock.now()) - self.started_at
+    }
+
     pub fn reset_game(&mut self) {
         self.score = 0;
+        self.combo = 0;
+        self.chain = 0;
+        self.last_clear_block = None;
+        self.cleared_grains = 0;
         self.is_game_over = false;
+        self.is_goal_reached = false;
+        self.started_at = self.clock.now();
+        self.ended_at = None;
+        self.checked_blocks = 0;
+        if let Some(garbage) = self.constants.rules.garbage {
+            self.garbage_at = self.started_at + garbage.interval;
+        }
+        self.garbage_blocks = 0;
         self.map_controller.clear();
+        if let Some(puzzle) = &self.puzzle {
+            self.map_controller
+                .load_layout(&puzzle.get_layout(self.constants.map_height));
+        }
+        self.particle_controller.clear();
+        self.timeline.clear();
+        self.rng.rewind();
         self.block_controller.clear(self.rng);
     }
 
     pub async fn tick(&mut self) {
+        self.timeline.advance(self.clock.now());
         self.draw_game().await;
+        self.particle_controller.tick(); // Still fading out after the game is over
 
         if self.is_game_over {
             return ();
         }
 
-        self.tick_map_and_update_score().await;
-        self.tick_block_and_check_game_over().await;
+        self.tick_map_and_update_score();
+        self.check_goal_reached();
+        self.check_puzzle();
+        if !self.is_game_over {
+            self.tick_garbage();
+        }
+        if !self.is_game_over && !self.is_cursor_active && self.get_pieces_left() != Some(0) {
+            self.tick_block_and_check_game_over().await;
+        }
     }
 
     async fn draw_game(&mut self) {
@@ -74,83 +203,488 @@ where
             self.display_game_over();
         }
 
-        GraphicController::flush().await;
+        self.renderer.present().await;
     }
 
-    async fn tick_map_and_update_score(&mut self) {
+    fn tick_map_and_update_score(&mut self) {
         let score_fields: Vec<(i32, i32)> = self.map_controller.tick_and_get_score_fields(self.rng);
         if score_fields.len() > 0 {
-            self.draw_row_demolishion(&score_fields).await;
+            self.particle_controller
+                .burst(&self.map_controller.get_fields(&score_fields), self.rng);
             self.map_controller.demolish_fields(&score_fields);
             self.score += score_fields.len() as u32;
+            self.cleared_grains += score_fields.len() as u32;
+            self.update_streaks(self.block_controller.get_settled_block_count());
+            self.schedule_clear_effects(&score_fields);
+        }
+    }
+
+    fn update_streaks(&mut self, settled_blocks: u32) {
+        match self.last_clear_block {
+            Some(block) if block == settled_blocks => self.chain += 1,
+            Some(block) if block + 1 == settled_blocks => {
+                self.combo += 1;
+                self.chain = 1;
+            }
+            _ => {
+                self.combo = 1;
+                self.chain = 1;
+            }
+        }
+        self.last_clear_block = Some(settled_blocks);
+    }
+
+    fn get_center(fields: &[(i32, i32)]) -> (i32, i32) {
+        let count = fields.len() as i32;
+        let sum = fields
+            .iter()
+            .fold((0, 0), |sum, (x, y)| (sum.0 + x, sum.1 + y));
+
+        (sum.0 / count, sum.1 / count)
+    }
+
+    fn schedule_clear_effects(&mut self, score_fields: &[(i32, i32)]) {
+        let now = self.clock.now();
+
+        self.timeline.schedule(
+            Effect::ClearVanish(score_fields.to_vec()),
+            now,
+            CLEAR_VANISH_DURATION,
+        );
+        for component in MapController::split_into_components(score_fields) {
+            self.timeline.schedule(
+                Effect::ScorePopup {
+                    text: format!("+{}", component.len()),
+                    position: Self::get_center(&component),
+                    line: 0,
+                },
+                now,
+                SCORE_POPUP_DURATION,
+            );
+        }
+
+        let mut labels = Vec::new();
+        if self.combo > 1 {
+            labels.push(format!("{} x{}", COMBO_TEXT, self.combo));
+        }
+        if self.chain > 1 {
+            labels.push(format!("{} x{}", CHAIN_TEXT, self.chain));
+        }
+        for (line, text) in labels.into_iter().enumerate() {
+            self.timeline.schedule(
+                Effect::ScorePopup {
+                    text,
+                    position: Self::get_center(score_fields),
+                    line: line as u32 + 1,
+                },
+                now,
+                SCORE_POPUP_DURATION,
+            );
+        }
+
+        self.timeline.schedule(
+            Effect::ScreenShake {
+                amplitude: SCREEN_SHAKE_AMPLITUDE,
+            },
+            now,
+            SCREEN_SHAKE_DURATION,
+        );
+        if self.map_controller.get_fields_to_draw().is_empty() {
+            self.timeline.schedule(
+                Effect::Banner(ALL_CLEAR_TEXT.to_string()),
+                now,
+                BANNER_DURATION,
+            );
         }
     }
 
     async fn tick_block_and_check_game_over(&mut self) {
-        let is_game_over = self
-            .block_controller
-            .tick_and_check_game_over(&mut self.map_controller, self.rng);
-        if is_game_over {
+        for _ in 0..self.get_mode().get_fall_steps(self.cleared_grains) {
+            let is_game_over = self
+                .block_controller
+                .tick_and_check_game_over(&mut self.map_controller, self.rng);
+            if is_game_over {
+                self.handle_top_out();
+                break;
+            }
+        }
+    }
+
+    fn handle_top_out(&mut self) {
+        match self.get_mode() {
+            GameMode::Zen => self.erase_upper_rows(),
+            _ => self.handle_game_over(),
+        }
+    }
+
+    // Makes room for the block, the erased sand bursts like a clear but scores nothing
+    fn erase_upper_rows(&mut self) {
+        let erased_rows = (self.constants.map_height as f32 * ZEN_ERASED_SHARE) as i32;
+        let erased_fields = self.map_controller.get_sand_above(erased_rows);
+        self.particle_controller
+            .burst(&self.map_controller.get_fields(&erased_fields), self.rng);
+        self.map_controller.demolish_fields(&erased_fields);
+        self.timeline.schedule(
+            Effect::ClearVanish(erased_fields),
+            self.clock.now(),
+            CLEAR_VANISH_DURATION,
+        );
+    }
+
+    // Once due, the garbage waits for the block in play to settle, so it never rises into a falling block
+    fn tick_garbage(&mut self) {
+        let garbage = match self.constants.rules.garbage {
+            Some(garbage) => garbage,
+            None => return,
+        };
+        let settled_blocks = self.block_controller.get_settled_block_count();
+        let has_block_settled = settled_blocks > self.garbage_blocks;
+        self.garbage_blocks = settled_blocks;
+
+        if has_block_settled && self.clock.now() >= self.garbage_at {
+            self.raise_garbage(garbage);
+        }
+    }
+
+    fn raise_garbage(&mut self, garbage: Garbage) {
+        self.garbage_at = self.clock.now() + garbage.interval;
+        if self.map_controller.push_garbage(garbage.rows, self.rng) {
+            self.handle_top_out();
+        }
+    }
+
+    fn check_goal_reached(&mut self) {
+        if self
+            .get_mode()
+            .is_goal_reached(self.cleared_grains, self.get_elapsed())
+        {
+            self.is_goal_reached = true;
             self.handle_game_over();
         }
     }
 
+    // Once the sand has come to rest after a block settled, the last block's clears count too
+    fn check_puzzle(&mut self) {
+        let settled_blocks = self.block_controller.get_settled_block_count();
+        let goal = match &self.puzzle {
+            Some(puzzle)
+                if !self.is_game_over
+                    && settled_blocks > self.checked_blocks
+                    && self.map_controller.is_still() =>
+            {
+                puzzle.get_goal()
+            }
+            _ => return,
+        };
+
+        self.checked_blocks = settled_blocks;
+        if self.get_goal_left(goal) == 0 {
+            self.is_goal_reached = true;
+            self.handle_game_over();
+        } else if self.get_pieces_left() == Some(0) {
+            self.handle_game_over();
+        }
+    }
+
+    // Grains or pieces still to go
+    fn get_goal_left(&self, goal: PuzzleGoal) -> u32 {
+        match goal {
+            PuzzleGoal::ClearColor(family) => self.map_controller.count_grains(family) as u32,
+            PuzzleGoal::ClearGrains(count) => count.saturating_sub(self.cleared_grains),
+            PuzzleGoal::Survive(count) => {
+                count.saturating_sub(self.block_controller.get_settled_block_count())
+            }
+        }
+    }
+
+    // Blocks of the puzzle's sequence not settled yet, None outside of puzzles
+    fn get_pieces_left(&self) -> Option<u32> {
+        self.puzzle.as_ref().map(|puzzle| {
+            (puzzle.get_pieces().len() as u32)
+                .saturating_sub(self.block_controller.get_settled_block_count())
+        })
+    }
+
     fn handle_game_over(&mut self) {
         self.is_game_over = true;
+        self.ended_at = Some(self.clock.now());
+        self.timeline.schedule(
+            Effect::GameOverFade,
+            self.clock.now(),
+            GAME_OVER_FADE_DURATION,
+        );
+        self.submit_record();
+    }
+
+    fn submit_record(&mut self) {
+        let mode = self.get_mode();
+        let record = mode.get_record(self.score, self.get_elapsed(), self.is_goal_reached);
+        if let Some(record) = record {
+            if self.high_scores.submit(mode.get_name(), record) {
+                self.timeline.schedule(
+                    Effect::Banner(NEW_RECORD_TEXT.to_string()),
+                    self.clock.now(),
+                    BANNER_DURATION,
+                );
+            }
+        }
     }
 
-    fn draw_gamefield(&self) {
-        GraphicController::draw_background();
-        GraphicController::draw_block(
-            self.block_controller.get_block_to_draw(),
+    // Recomputed every frame, so the map follows the window when it is resized
+    fn get_viewport(&self) -> Viewport {
+        Viewport::fit(
+            self.renderer.get_screen_size(),
+            (self.constants.map_width, self.constants.map_height),
             self.constants.grain_side_size,
-            self.constants.block_chunk_side,
-        );
-        GraphicController::draw_fields(
+        )
+    }
+
+    // Shakes die down over their duration, in grains
+    fn get_shake_offset(&self) -> (f32, f32) {
+        let mut offset = (0.0, 0.0);
+        for (effect, progress) in self.timeline.get_active(self.clock.now()) {
+            if let Effect::ScreenShake { amplitude } = effect {
+                let strength = amplitude * (1.0 - progress);
+                let angle = progress * SCREEN_SHAKE_WAVES * TAU;
+                offset.0 += strength * angle.sin();
+                offset.1 += strength * (2.0 * angle).sin() / 2.0;
+            }
+        }
+
+        offset
+    }
+
+    fn draw_gamefield(&mut self) {
+        let viewport = self.get_viewport().shifted(self.get_shake_offset());
+        self.renderer
+            .draw_background(&viewport, self.constants.theme);
+        if self.get_pieces_left() != Some(0) {
+            self.renderer.draw_block(
+                self.block_controller.get_block_to_draw(),
+                &viewport,
+                self.constants.block_chunk_side,
+                self.constants.theme,
+            );
+        }
+        self.renderer.draw_fields(
             &self.map_controller.get_fields_to_draw(),
-            self.constants.grain_side_size,
+            &viewport,
+            self.constants.theme,
+        );
+        self.renderer.draw_particles(
+            self.particle_controller.get_particles(),
+            &viewport,
+            self.constants.theme,
         );
-        self.draw_interface();
+        self.draw_clear_vanish(&viewport);
+        self.draw_garbage_warning(&viewport);
+        self.draw_interface(&self.get_viewport()); // Stays put while the map shakes
     }
 
-    fn draw_interface(&self) {
-        self.draw_score();
-        self.draw_next_block();
+    fn draw_clear_vanish(&mut self, viewport: &Viewport) {
+        let grain_size = viewport.get_grain_size();
+        for (effect, progress) in self.timeline.get_active(self.clock.now()) {
+            if let Effect::ClearVanish(cells) = effect {
+                for (x, y) in cells.iter().filter(|(x, y)| viewport.contains(*x, *y)) {
+                    let (screen_x, screen_y) = viewport.map_to_screen(*x, *y);
+                    self.renderer.draw_overlay(
+                        (screen_x, screen_y, grain_size, grain_size),
+                        WHITE,
+                        1.0 - progress,
+                    );
+                }
+            }
+        }
     }
 
-    fn draw_score(&self) {
-        let score_text = format!("{}:{}", SCORE_TEXT, self.score);
-        let text_center = GraphicController::get_text_center(&score_text, SCORE_FONT_SIZE);
-        let score_position = GraphicController::map_to_window_dimensions(
-            MAP_WIDTH,
-            0,
-            self.constants.grain_side_size,
-        );
+    // Flashes over the rows the garbage will fill, from shortly before it is due until it rises
+    fn draw_garbage_warning(&mut self, viewport: &Viewport) {
+        let garbage = match self.constants.rules.garbage {
+            Some(garbage) if !self.is_game_over => garbage,
+            _ => return,
+        };
+        let now = self.clock.now();
+        let warning_start = self.garbage_at.saturating_sub(GARBAGE_WARNING_DURATION);
+        if now < warning_start {
+            return;
+        }
 
-        GraphicController::draw_text_with_outline(
-            &score_text,
-            score_position.0 - 2.0 * text_center.0 - H_BORDER_OFFSET,
-            score_position.1 + 2.0 * text_center.1 + V_BORDER_OFFSET,
-            SCORE_FONT_SIZE,
-            BLACK,
-            WHITE,
-            SCORE_OUTLINE_WIDTH,
+        let flash = (now - warning_start).as_secs_f32() * GARBAGE_WARNING_FLASHES * TAU;
+        let grain_size = viewport.get_grain_size();
+        let (screen_x, screen_y) =
+            viewport.map_to_screen(0, self.constants.map_height - garbage.rows);
+        self.renderer.draw_overlay(
+            (
+                screen_x,
+                screen_y,
+                self.constants.map_width as f32 * grain_size,
+                garbage.rows as f32 * grain_size,
+            ),
+            RED,
+            GARBAGE_WARNING_OPACITY * (1.0 - flash.cos()) / 2.0,
         );
     }
 
-    fn draw_next_block(&self) {
+    fn draw_interface(&mut self, viewport: &Viewport) {
+        self.draw_hud(viewport);
+        if self
+            .get_pieces_left()
+            .is_none_or(|pieces_left| pieces_left > 1)
+        {
+            self.draw_next_block(viewport);
+        }
+        self.draw_score_popups(viewport);
+        self.draw_banners(viewport);
+    }
+
+    // Text and outline colors fading into the background as the effect goes on
+    fn get_faded_hud_colors(&self, progress: f32) -> (Color, Color) {
+        let theme = self.constants.theme;
+        (
+            mix_colors(theme.background_color, theme.hud_text_color, 1.0 - progress),
+            mix_colors(
+                theme.background_color,
+                theme.hud_outline_color,
+                1.0 - progress,
+            ),
+        )
+    }
+
+    // Rise from the cleared rows while fading into the background
+    fn draw_score_popups(&mut self, viewport: &Viewport) {
+        let font_size = viewport.scale_size(SCORE_POPUP_FONT_SIZE);
+        for (effect, progress) in self.timeline.get_active(self.clock.now()) {
+            if let Effect::ScorePopup {
+                text,
+                position,
+                line,
+            } = effect
+            {
+                let text_center = self.renderer.get_text_center(text, font_size);
+                let (x, y) = viewport.map_to_screen(position.0, position.1);
+                let (text_color, outline_color) = self.get_faded_hud_colors(progress);
+                self.renderer.draw_text_with_outline(
+                    text,
+                    (
+                        x - text_center.0,
+                        y - progress * SCORE_POPUP_RISE * viewport.get_grain_size()
+                            - *line as f32 * viewport.scale_length(SCORE_POPUP_LINE_SPACING),
+                    ),
+                    font_size,
+                    text_color,
+                    outline_color,
+                    viewport.scale_size(SCORE_OUTLINE_WIDTH),
+                );
+            }
+        }
+    }
+
+    // Above the middle of the map, so they don't cover the game over text
+    fn draw_banners(&mut self, viewport: &Viewport) {
+        let font_size = viewport.scale_size(ALL_CLEAR_FONT_SIZE);
+        let map_center =
+            viewport.map_to_screen(self.constants.map_width / 2, self.constants.map_height / 3);
+        for (effect, progress) in self.timeline.get_active(self.clock.now()) {
+            if let Effect::Banner(text) = effect {
+                let text_center = self.renderer.get_text_center(text, font_size);
+                let (text_color, outline_color) = self.get_faded_hud_colors(progress);
+                self.renderer.draw_text_with_outline(
+                    text,
+                    (map_center.0 - text_center.0, map_center.1 + text_center.1),
+                    font_size,
+                    text_color,
+                    outline_color,
+                    viewport.scale_size(GAME_OVER_OUTLINE_WIDTH),
+                );
+            }
+        }
+    }
+
+    fn get_hud_lines(&self) -> Vec<String> {
+        let mode = self.get_mode();
+        let score_line = format!("{}:{}", SCORE_TEXT, self.score);
+        let mut lines = match mode {
+            GameMode::Endless => Vec::from([score_line]),
+            GameMode::Marathon => Vec::from([
+                score_line,
+                format!("{}:{}", LEVEL_TEXT, mode.get_level(self.cleared_grains)),
+            ]),
+            GameMode::Sprint { target } => Vec::from([
+                format!(
+                    "{}:{}",
+                    GRAINS_LEFT_TEXT,
+                    target.saturating_sub(self.cleared_grains)
+                ),
+                format!("{}:{}", TIME_TEXT, format_time(self.get_elapsed())),
+            ]),
+            GameMode::Zen => Vec::new(), // Nothing to chase
+            GameMode::Puzzle => self.get_puzzle_hud_lines(),
+            GameMode::Ultra { time_limit } => Vec::from([
+                score_line,
+                format!(
+                    "{}:{}",
+                    TIME_TEXT,
+                    format_time(time_limit.saturating_sub(self.get_elapsed()))
+                ),
+            ]),
+        };
+        if let Some(record) = self.high_scores.get_record(mode.get_name()) {
+            lines.push(format!("{}:{}", BEST_TEXT, record.get_text()));
+        }
+        if self.is_cursor_active {
+            lines.push(CURSOR_TEXT.to_string());
+        }
+
+        lines
+    }
+
+    fn get_puzzle_hud_lines(&self) -> Vec<String> {
+        let (goal, pieces_left) = match (&self.puzzle, self.get_pieces_left()) {
+            (Some(puzzle), Some(pieces_left)) => (puzzle.get_goal(), pieces_left),
+            _ => return Vec::new(),
+        };
+        let goal_line = match goal {
+            PuzzleGoal::ClearColor(family) => format!(
+                "{} {}:{}",
+                GRAINS_LEFT_TEXT,
+                family.get_name(),
+                self.get_goal_left(goal)
+            ),
+            PuzzleGoal::ClearGrains(_) => {
+                format!("{}:{}", GRAINS_LEFT_TEXT, self.get_goal_left(goal))
+            }
+            PuzzleGoal::Survive(_) => {
+                return Vec::from([format!("{}:{}", SURVIVE_TEXT, self.get_goal_left(goal))])
+            }
+        };
+
+        Vec::from([goal_line, format!("{}:{}", PIECES_LEFT_TEXT, pieces_left)])
+    }
+
+    fn draw_hud(&mut self, viewport: &Viewport) {
+        let lines = self.get_hud_lines();
+        self.renderer
+            .draw_hud_lines(&lines, viewport, self.constants.theme);
+    }
+
+    fn draw_next_block(&mut self, viewport: &Viewport) {
         let next_block = self.get_next_block_miniature();
+        let map_origin = viewport.map_to_screen(0, 0);
 
-        GraphicController::draw_block_miniature(
+        self.renderer.draw_block_miniature(
             next_block,
-            (H_BORDER_OFFSET, V_BORDER_OFFSET),
+            (
+                map_origin.0 + viewport.scale_length(H_BORDER_OFFSET),
+                map_origin.1 + viewport.scale_length(V_BORDER_OFFSET),
+            ),
             self.constants.preview_block_chunk_side,
-            self.constants.grain_side_size,
+            viewport,
         );
     }
 
     fn get_next_block_miniature(&self) -> (Vec<(i32, i32)>, Color) {
-        let mut next_block = self.block_controller.get_next_block_miniature();
+        let (schema, family) = self.block_controller.get_next_block_miniature();
+        let mut next_block = (schema, self.constants.theme.get_display_color(family));
         let x_normalize = next_block.0.iter().min_by_key(|block| block.0).unwrap().0;
         let y_normalize = next_block.0.iter().min_by_key(|block| block.1).unwrap().1;
 
@@ -162,59 +696,76 @@ where
         next_block
     }
 
-    fn display_game_over(&self) {
-        let text_center = GraphicController::get_text_center(GAME_OVER_TEXT, GAME_OVER_FONT_SIZE);
-        let bottom_text_center =
-            GraphicController::get_text_center(GAME_OVER_BOTTOM_TEXT, GAME_OVER_BOTTOM_FONT_SIZE);
-        let map_center = GraphicController::map_to_window_dimensions(
-            MAP_WIDTH / 2,
-            MAP_HEIGHT / 2,
-            self.constants.grain_side_size,
+    // Finished once the fade is no longer on the timeline
+    fn get_game_over_fade(&self) -> f32 {
+        self.timeline
+            .get_active(self.clock.now())
+            .into_iter()
+            .find(|(effect, _)| **effect == Effect::GameOverFade)
+            .map_or(1.0, |(_, progress)| progress)
+    }
+
+    fn get_game_over_title(&self) -> &'static str {
+        match (self.get_mode(), self.is_goal_reached) {
+            (GameMode::Puzzle, true) => PUZZLE_SOLVED_TEXT,
+            (GameMode::Puzzle, false) => PUZZLE_FAILED_TEXT,
+            (_, false) | (GameMode::Endless | GameMode::Zen, _) => GAME_OVER_TEXT,
+            (GameMode::Marathon, true) => MARATHON_FINISHED_TEXT,
+            (GameMode::Sprint { .. }, true) => SPRINT_FINISHED_TEXT,
+            (GameMode::Ultra { .. }, true) => ULTRA_FINISHED_TEXT,
+        }
+    }
+
+    fn display_game_over(&mut self) {
+        let viewport = self.get_viewport();
+        self.renderer.draw_overlay(
+            viewport.get_map_rect(),
+            self.constants.theme.letterbox_color,
+            GAME_OVER_FADE_OPACITY * self.get_game_over_fade(),
         );
+        let font_size = viewport.scale_size(GAME_OVER_FONT_SIZE);
+        let bottom_font_size = viewport.scale_size(GAME_OVER_BOTTOM_FONT_SIZE);
+        let outline_width = viewport.scale_size(GAME_OVER_OUTLINE_WIDTH);
+        let title = self.get_game_over_title();
+        let text_center = self.renderer.get_text_center(title, font_size);
+        let bottom_text_center = self
+            .renderer
+            .get_text_center(GAME_OVER_BOTTOM_TEXT, bottom_font_size);
+        let map_center =
+            viewport.map_to_screen(self.constants.map_width / 2, self.constants.map_height / 2);
 
-        GraphicController::draw_text_with_outline(
-            GAME_OVER_TEXT,
-            map_center.0 - text_center.0,
-            map_center.1 + text_center.1,
-            GAME_OVER_FONT_SIZE,
-            BLACK,
-            WHITE,
-            GAME_OVER_OUTLINE_WIDTH,
+        self.renderer.draw_text_with_outline(
+            title,
+            (map_center.0 - text_center.0, map_center.1 + text_center.1),
+            font_size,
+            self.constants.theme.hud_text_color,
+            self.constants.theme.hud_outline_color,
+            outline_width,
         );
-        GraphicController::draw_text_with_outline(
+        self.renderer.draw_text_with_outline(
             GAME_OVER_BOTTOM_TEXT,
-            map_center.0 - bottom_text_center.0,
-            map_center.1 + text_center.1 + 2.0 * bottom_text_center.1,
-            GAME_OVER_BOTTOM_FONT_SIZE,
-            BLACK,
-            WHITE,
-            GAME_OVER_OUTLINE_WIDTH,
+            (
+                map_center.0 - bottom_text_center.0,
+                map_center.1 + text_center.1 + 2.0 * bottom_text_center.1,
+            ),
+            bottom_font_size,
+            self.constants.theme.hud_text_color,
+            self.constants.theme.hud_outline_color,
+            outline_width,
         );
     }
 
-    async fn draw_row_demolishion(&mut self, fields_coords: &Vec<(i32, i32)>) {
-        let fields_to_demolish = self
-            .map_controller
-            .get_shuffled_fields(fields_coords, self.rng);
-        let mut demolishion_stash = Vec::new();
-
-        for fields in fields_to_demolish.chunks(DEMOLISHION_CHUNK_SIZE) {
-            demolishion_stash.extend(fields);
-
-            self.draw_gamefield();
-            GraphicController::draw_fields_vanish(
-                &demolishion_stash,
-                self.constants.grain_side_size,
-            );
-
-            GraphicController::flush().await;
-        }
-    }
-
     pub fn do_move(&mut self, key: KeyCode) {
         if self.is_game_over {
             return ();
         }
+        if self.get_pieces_left() == Some(0) {
+            return; // Waiting for the sand before the puzzle is judged
+        }
+        if self.is_cursor_active {
+            self.do_cursor_move(key);
+            return;
+        }
         let is_game_over = match key {
             KeyCode::D => self
                 .block_controller
@@ -234,14 +785,37 @@ where
             _ => false,
         };
         if is_game_over {
-            self.handle_game_over();
+            self.handle_top_out();
+        }
+    }
+
+    fn do_cursor_move(&mut self, key: KeyCode) {
+        let mc = &mut self.map_controller;
+        match key {
+            KeyCode::W => self.block_controller.handle_cursor_move(mc, (0, -1)),
+            KeyCode::A => self.block_controller.handle_cursor_move(mc, (-1, 0)),
+            KeyCode::S => self.block_controller.handle_cursor_move(mc, (0, 1)),
+            KeyCode::D => self.block_controller.handle_cursor_move(mc, (1, 0)),
+            KeyCode::E => self.block_controller.handle_cursor_rotate(mc, true),
+            KeyCode::Q => self.block_controller.handle_cursor_rotate(mc, false),
+            KeyCode::Space => self.block_controller.handle_place(mc, self.rng),
+            _ => {}
         }
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::utils::tetris_rng::{MockTetrisRng, ThreadTetrisRng};
+    use crate::{
+        constants::{
+            colors::BLACK, mode_constants::MARATHON_GRAINS_PER_LEVEL, BoardSize, GameRules,
+        },
+        objects::{block::BlockType, color_family::ColorFamily, high_scores::Record, theme::Theme},
+        renderers::recording_renderer::{DrawCommand, RecordingRenderer},
+        utils::block_on::block_on,
+        utils::clock::FakeClock,
+        utils::tetris_rng::{MockTetrisRng, ScriptedTetrisRng, ThreadTetrisRng},
+    };
 
     use super::*;
 
@@ -252,12 +826,16 @@ mod test {
         grain_side_size: 1,
         preview_block_chunk_side: 1,
         block_starting_pos: (0, 0),
+        rules: GameRules::CLASSIC,
+        theme: &Theme::CLASSIC,
     };
 
     #[test]
     fn create_game_controller() {
         let mut rng: MockTetrisRng = MockTetrisRng::new();
-        let gc = GameController::new(&mut rng, &TEST_CONSTANTS);
+        let mut renderer: RecordingRenderer = RecordingRenderer::new();
+        let clock = FakeClock::new();
+        let gc = GameController::new(&mut rng, &mut renderer, &clock, &TEST_CONSTANTS);
 
         assert_eq!(gc.score, 0);
         assert_eq!(gc.is_game_over, false);
@@ -267,7 +845,9 @@ mod test {
     #[test]
     fn reset_game() {
         let mut rng: ThreadTetrisRng = ThreadTetrisRng::new();
-        let mut gc = GameController::new(&mut rng, &TEST_CONSTANTS);
+        let mut renderer: RecordingRenderer = RecordingRenderer::new();
+        let clock = FakeClock::new();
+        let mut gc = GameController::new(&mut rng, &mut renderer, &clock, &TEST_CONSTANTS);
         gc.score = 100;
         gc.is_game_over = true;
 
@@ -280,10 +860,880 @@ mod test {
     #[test]
     fn handle_game_over() {
         let mut rng: MockTetrisRng = MockTetrisRng::new();
-        let mut gc = GameController::new(&mut rng, &TEST_CONSTANTS);
+        let mut renderer: RecordingRenderer = RecordingRenderer::new();
+        let clock = FakeClock::new();
+        let mut gc = GameController::new(&mut rng, &mut renderer, &clock, &TEST_CONSTANTS);
 
         gc.handle_game_over();
 
         assert!(gc.is_game_over);
     }
+
+    #[test]
+    fn tick_draws_frame() {
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        rng.set_block_type(BlockType::SquareBlock);
+        rng.set_block_color(ColorFamily::Red);
+        let mut renderer: RecordingRenderer = RecordingRenderer::new();
+        let clock = FakeClock::new();
+        let mut gc = GameController::new(&mut rng, &mut renderer, &clock, &TEST_CONSTANTS);
+        gc.reset_game();
+
+        block_on(gc.tick());
+
+        let commands = renderer.get_commands();
+        assert_eq!(commands[0], DrawCommand::Background);
+        assert_eq!(
+            commands[1],
+            DrawCommand::Block(
+                Vec::from([(0, 0), (0, 1), (1, 0), (1, 1)]),
+                ColorFamily::Red
+            )
+        );
+        assert_eq!(commands[2], DrawCommand::Fields(Vec::new()));
+        assert!(renderer.get_texts().contains(&"Score:0"));
+        assert!(commands
+            .iter()
+            .any(|command| matches!(command, DrawCommand::BlockMiniature(..))));
+        assert_eq!(*commands.last().unwrap(), DrawCommand::Present);
+        assert_eq!(renderer.get_frame_count(), 1);
+    }
+
+    #[test]
+    fn tick_game_over_draws_text() {
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        let mut renderer: RecordingRenderer = RecordingRenderer::new();
+        let clock = FakeClock::new();
+        let mut gc = GameController::new(&mut rng, &mut renderer, &clock, &TEST_CONSTANTS);
+        gc.reset_game();
+        gc.handle_game_over();
+
+        block_on(gc.tick());
+
+        let texts = renderer.get_texts();
+        assert!(texts.contains(&GAME_OVER_TEXT));
+        assert!(texts.contains(&GAME_OVER_BOTTOM_TEXT));
+    }
+
+    #[test]
+    fn tick_bursts_cleared_rows_into_particles() {
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        rng.set_particle_velocity((0.0, -1.0));
+        let mut renderer: RecordingRenderer = RecordingRenderer::new();
+        let clock = FakeClock::new();
+        let mut gc = GameController::new(&mut rng, &mut renderer, &clock, &TEST_CONSTANTS);
+        gc.reset_game();
+        gc.map_controller
+            .spawn_block((0..10).map(|x| (x, 8)).collect(), ColorFamily::Red);
+
+        block_on(gc.tick());
+
+        assert_eq!(gc.score, 10);
+        assert_eq!(gc.particle_controller.get_particles().len(), 10);
+        assert!(gc.map_controller.get_fields_to_draw().is_empty());
+
+        block_on(gc.tick());
+
+        // The game goes on while they fly, one frame per tick
+        assert_eq!(renderer.get_frame_count(), 2);
+        assert!(renderer.get_commands().iter().any(
+            |command| matches!(command, DrawCommand::Particles(particles) if particles.len() == 10)
+        ));
+    }
+
+    // Commands drawn since the one to last present
+    fn get_last_frame(commands: &[DrawCommand]) -> &[DrawCommand] {
+        let frame_start = commands[..commands.len() - 1]
+            .iter()
+            .rposition(|command| *command == DrawCommand::Present)
+            .map_or(0, |index| index + 1);
+
+        &commands[frame_start..]
+    }
+
+    fn get_overlays(commands: &[DrawCommand]) -> Vec<(Color, f32)> {
+        commands
+            .iter()
+            .filter_map(|command| match command {
+                DrawCommand::Overlay(_, color, opacity) => Some((*color, *opacity)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn clear_effects_follow_clock() {
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        let mut renderer: RecordingRenderer = RecordingRenderer::new();
+        let clock = FakeClock::new();
+        let mut gc = GameController::new(&mut rng, &mut renderer, &clock, &TEST_CONSTANTS);
+        gc.reset_game();
+        gc.map_controller
+            .spawn_block((0..10).map(|x| (x, 8)).collect(), ColorFamily::Red);
+        block_on(gc.tick());
+
+        clock.advance(CLEAR_VANISH_DURATION / 2);
+        block_on(gc.tick());
+
+        let frame = get_last_frame(gc.get_renderer().get_commands());
+        let overlays = get_overlays(frame);
+        assert_eq!(overlays.len(), 10); // One per cleared grain
+        assert!(overlays
+            .iter()
+            .all(|(color, opacity)| *color == WHITE && (opacity - 0.5).abs() < 1e-3));
+        assert!(frame.iter().any(
+            |command| matches!(command, DrawCommand::Text(text, ..) if text.as_str() == "+10")
+        ));
+
+        clock.advance(SCORE_POPUP_DURATION);
+        block_on(gc.tick());
+
+        let frame = get_last_frame(gc.get_renderer().get_commands());
+        assert!(get_overlays(frame).is_empty());
+        assert!(!frame.iter().any(
+            |command| matches!(command, DrawCommand::Text(text, ..) if text.as_str() == "+10")
+        ));
+
+        clock.advance(BANNER_DURATION);
+        block_on(gc.tick());
+        assert!(gc.timeline.is_empty());
+    }
+
+    fn get_texts(commands: &[DrawCommand]) -> Vec<&str> {
+        commands
+            .iter()
+            .filter_map(|command| match command {
+                DrawCommand::Text(text, ..) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn update_streaks() {
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        let mut renderer: RecordingRenderer = RecordingRenderer::new();
+        let clock = FakeClock::new();
+        let mut gc = GameController::new(&mut rng, &mut renderer, &clock, &TEST_CONSTANTS);
+
+        gc.update_streaks(3);
+        assert_eq!((gc.combo, gc.chain), (1, 1));
+
+        gc.update_streaks(3); // Same block set off another clear
+        assert_eq!((gc.combo, gc.chain), (1, 2));
+
+        gc.update_streaks(4); // The very next block cleared too
+        assert_eq!((gc.combo, gc.chain), (2, 1));
+
+        gc.update_streaks(6); // A block in between cleared nothing
+        assert_eq!((gc.combo, gc.chain), (1, 1));
+    }
+
+    #[test]
+    fn clear_popup_per_component() {
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        let mut renderer: RecordingRenderer = RecordingRenderer::new();
+        let clock = FakeClock::new();
+        let mut gc = GameController::new(&mut rng, &mut renderer, &clock, &TEST_CONSTANTS);
+        gc.reset_game();
+        gc.update_streaks(0);
+
+        gc.schedule_clear_effects(&[(0, 4), (1, 4), (2, 4), (8, 2), (9, 2)]);
+
+        let popups: Vec<&Effect> = gc
+            .timeline
+            .get_active(clock.now())
+            .into_iter()
+            .map(|(effect, _)| effect)
+            .filter(|effect| matches!(effect, Effect::ScorePopup { .. }))
+            .collect();
+        assert_eq!(
+            popups,
+            Vec::from([
+                &Effect::ScorePopup {
+                    text: "+3".to_string(),
+                    position: (1, 4),
+                    line: 0
+                },
+                &Effect::ScorePopup {
+                    text: "+2".to_string(),
+                    position: (8, 2),
+                    line: 0
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn clear_streak_labels() {
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        let mut renderer: RecordingRenderer = RecordingRenderer::new();
+        let clock = FakeClock::new();
+        let mut gc = GameController::new(&mut rng, &mut renderer, &clock, &TEST_CONSTANTS);
+        gc.reset_game();
+        gc.update_streaks(1);
+        gc.update_streaks(2);
+        gc.update_streaks(2);
+
+        gc.schedule_clear_effects(&[(0, 4), (1, 4)]);
+
+        let active = gc.timeline.get_active(clock.now());
+        assert!(active.contains(&(
+            &Effect::ScorePopup {
+                text: "Combo x2".to_string(),
+                position: (0, 4),
+                line: 1
+            },
+            0.0
+        )));
+        assert!(active.contains(&(
+            &Effect::ScorePopup {
+                text: "Chain x2".to_string(),
+                position: (0, 4),
+                line: 2
+            },
+            0.0
+        )));
+    }
+
+    #[test]
+    fn clears_without_settling_chain() {
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        let mut renderer: RecordingRenderer = RecordingRenderer::new();
+        let clock = FakeClock::new();
+        let mut gc = GameController::new(&mut rng, &mut renderer, &clock, &TEST_CONSTANTS);
+        gc.reset_game();
+        gc.map_controller
+            .spawn_block((0..10).map(|x| (x, 8)).collect(), ColorFamily::Red);
+        block_on(gc.tick());
+        gc.map_controller
+            .spawn_block((0..10).map(|x| (x, 8)).collect(), ColorFamily::Red);
+        block_on(gc.tick());
+
+        block_on(gc.tick());
+
+        assert_eq!(gc.chain, 2);
+        let frame = get_last_frame(gc.get_renderer().get_commands());
+        assert!(get_texts(frame).contains(&"Chain x2"));
+    }
+
+    #[test]
+    fn emptied_board_shows_all_clear() {
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        let mut renderer: RecordingRenderer = RecordingRenderer::new();
+        let clock = FakeClock::new();
+        let mut gc = GameController::new(&mut rng, &mut renderer, &clock, &TEST_CONSTANTS);
+        gc.reset_game();
+        gc.map_controller
+            .spawn_block((0..10).map(|x| (x, 8)).collect(), ColorFamily::Red);
+        block_on(gc.tick());
+
+        block_on(gc.tick());
+
+        let frame = get_last_frame(gc.get_renderer().get_commands());
+        assert!(get_texts(frame).contains(&ALL_CLEAR_TEXT));
+    }
+
+    #[test]
+    fn leftover_grains_no_all_clear() {
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        let mut renderer: RecordingRenderer = RecordingRenderer::new();
+        let clock = FakeClock::new();
+        let mut gc = GameController::new(&mut rng, &mut renderer, &clock, &TEST_CONSTANTS);
+        gc.reset_game();
+        gc.map_controller
+            .spawn_block(Vec::from([(5, 9)]), ColorFamily::Blue);
+        gc.map_controller
+            .spawn_block((0..10).map(|x| (x, 7)).collect(), ColorFamily::Red);
+        block_on(gc.tick());
+
+        block_on(gc.tick());
+
+        assert!(gc.score > 0);
+        let frame = get_last_frame(gc.get_renderer().get_commands());
+        assert!(!get_texts(frame).contains(&ALL_CLEAR_TEXT));
+    }
+
+    #[test]
+    fn clear_shakes_map() {
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        let mut renderer: RecordingRenderer = RecordingRenderer::new();
+        let clock = FakeClock::new();
+        let mut gc = GameController::new(&mut rng, &mut renderer, &clock, &TEST_CONSTANTS);
+        gc.reset_game();
+        gc.map_controller
+            .spawn_block((0..10).map(|x| (x, 8)).collect(), ColorFamily::Red);
+        block_on(gc.tick());
+        assert_eq!(gc.get_shake_offset(), (0.0, 0.0));
+
+        clock.advance(SCREEN_SHAKE_DURATION / 16);
+        let offset = gc.get_shake_offset();
+        assert!(offset.0.abs() > 0.0 && offset.0.abs() <= SCREEN_SHAKE_AMPLITUDE);
+
+        clock.advance(SCREEN_SHAKE_DURATION);
+        assert_eq!(gc.get_shake_offset(), (0.0, 0.0));
+    }
+
+    #[test]
+    fn game_over_fades_in() {
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        let mut renderer: RecordingRenderer = RecordingRenderer::new();
+        let clock = FakeClock::new();
+        let mut gc = GameController::new(&mut rng, &mut renderer, &clock, &TEST_CONSTANTS);
+        gc.reset_game();
+        gc.handle_game_over();
+
+        clock.advance(GAME_OVER_FADE_DURATION / 2);
+        block_on(gc.tick());
+
+        let overlays = get_overlays(get_last_frame(gc.get_renderer().get_commands()));
+        assert_eq!(overlays.len(), 1);
+        assert_eq!(overlays[0].0, Theme::CLASSIC.letterbox_color);
+        assert!((overlays[0].1 - GAME_OVER_FADE_OPACITY / 2.0).abs() < 1e-3);
+
+        clock.advance(GAME_OVER_FADE_DURATION);
+        block_on(gc.tick());
+
+        let overlays = get_overlays(get_last_frame(gc.get_renderer().get_commands()));
+        assert_eq!(
+            overlays,
+            Vec::from([(Theme::CLASSIC.letterbox_color, GAME_OVER_FADE_OPACITY)])
+        );
+    }
+
+    #[test]
+    fn reset_game_clears_timeline() {
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        let mut renderer: RecordingRenderer = RecordingRenderer::new();
+        let clock = FakeClock::new();
+        let mut gc = GameController::new(&mut rng, &mut renderer, &clock, &TEST_CONSTANTS);
+        gc.reset_game();
+        gc.handle_game_over();
+
+        gc.reset_game();
+
+        assert!(gc.timeline.is_empty());
+    }
+
+    #[test]
+    fn reset_game_clears_particles() {
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        let mut renderer: RecordingRenderer = RecordingRenderer::new();
+        let clock = FakeClock::new();
+        let mut gc = GameController::new(&mut rng, &mut renderer, &clock, &TEST_CONSTANTS);
+        gc.reset_game();
+        gc.map_controller
+            .spawn_block((0..10).map(|x| (x, 8)).collect(), ColorFamily::Red);
+        block_on(gc.tick());
+
+        gc.reset_game();
+
+        assert!(gc.particle_controller.get_particles().is_empty());
+    }
+
+    #[test]
+    fn tick_lays_interface_out_on_viewport() {
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        let mut renderer: RecordingRenderer = RecordingRenderer::new();
+        renderer.set_screen_size((40.0, 20.0)); // Map scaled twice and centered between bars
+        let clock = FakeClock::new();
+        let mut gc = GameController::new(&mut rng, &mut renderer, &clock, &TEST_CONSTANTS);
+        gc.reset_game();
+        gc.handle_game_over();
+
+        block_on(gc.tick());
+
+        let commands = renderer.get_commands();
+        assert!(commands.iter().any(|command| matches!(
+            command,
+            DrawCommand::BlockMiniature(_, _, origin) if *origin == (50.0, 40.0)
+        )));
+        assert!(commands.contains(&DrawCommand::Text(
+            GAME_OVER_TEXT.to_string(),
+            (-250.0, 70.0),
+            2 * GAME_OVER_FONT_SIZE,
+            BLACK
+        )));
+    }
+
+    fn get_mode_constants(mode: GameMode) -> TetrisConstants {
+        TetrisConstants {
+            rules: GameRules {
+                mode,
+                ..GameRules::CLASSIC
+            },
+            ..TEST_CONSTANTS
+        }
+    }
+
+    #[test]
+    fn hud_lines_per_mode() {
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        let mut renderer: RecordingRenderer = RecordingRenderer::new();
+        let clock = FakeClock::new();
+        let constants = get_mode_constants(GameMode::Marathon);
+        let mut gc = GameController::new(&mut rng, &mut renderer, &clock, &constants);
+        gc.reset_game();
+        gc.score = 7;
+        gc.cleared_grains = MARATHON_GRAINS_PER_LEVEL;
+
+        assert_eq!(gc.get_hud_lines(), Vec::from(["Score:7", "Level:2"]));
+
+        gc.high_scores.submit("marathon", Record::Score(30));
+        assert_eq!(
+            gc.get_hud_lines(),
+            Vec::from(["Score:7", "Level:2", "Best:30"])
+        );
+        block_on(gc.tick());
+        let texts = get_texts(gc.get_renderer().get_commands());
+        assert!(texts.contains(&"Level:2"));
+        assert!(texts.contains(&"Best:30"));
+    }
+
+    #[test]
+    fn marathon_levels_speed_up_fall() {
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        rng.set_block_type(BlockType::SquareBlock);
+        let mut renderer: RecordingRenderer = RecordingRenderer::new();
+        let clock = FakeClock::new();
+        let constants = get_mode_constants(GameMode::Marathon);
+        let mut gc = GameController::new(&mut rng, &mut renderer, &clock, &constants);
+        gc.reset_game();
+        let get_block_top = |gc: &GameController<_, _, _>| {
+            gc.block_controller
+                .get_block_to_draw()
+                .0
+                .iter()
+                .map(|cell| cell.1)
+                .min()
+                .unwrap()
+        };
+
+        let top = get_block_top(&gc);
+        block_on(gc.tick());
+        assert_eq!(get_block_top(&gc), top + 1);
+
+        gc.cleared_grains = MARATHON_GRAINS_PER_LEVEL * 3; // Level 4
+        block_on(gc.tick());
+        assert_eq!(get_block_top(&gc), top + 3);
+    }
+
+    #[test]
+    fn sprint_finishes_at_target() {
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        let mut renderer: RecordingRenderer = RecordingRenderer::new();
+        let clock = FakeClock::new();
+        let constants = get_mode_constants(GameMode::Sprint { target: 10 });
+        let mut gc = GameController::new(&mut rng, &mut renderer, &clock, &constants);
+        gc.reset_game();
+        gc.map_controller
+            .spawn_block((0..10).map(|x| (x, 8)).collect(), ColorFamily::Red);
+        clock.advance(Duration::from_secs(3));
+
+        block_on(gc.tick());
+
+        assert!(gc.is_game_over);
+        assert!(gc.is_goal_reached);
+        assert_eq!(
+            gc.high_scores.get_record("sprint"),
+            Some(Record::Time(Duration::from_secs(3)))
+        );
+
+        clock.advance(Duration::from_secs(1)); // The timer stopped with the game
+        block_on(gc.tick());
+
+        let texts = get_texts(get_last_frame(gc.get_renderer().get_commands()));
+        assert!(texts.contains(&"Left:0"));
+        assert!(texts.contains(&"Time:0:03.00"));
+        assert!(texts.contains(&SPRINT_FINISHED_TEXT));
+        assert!(texts.contains(&NEW_RECORD_TEXT));
+    }
+
+    #[test]
+    fn sprint_topped_out_keeps_no_record() {
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        let mut renderer: RecordingRenderer = RecordingRenderer::new();
+        let clock = FakeClock::new();
+        let constants = get_mode_constants(GameMode::Sprint { target: 10 });
+        let mut gc = GameController::new(&mut rng, &mut renderer, &clock, &constants);
+        gc.reset_game();
+
+        gc.handle_game_over();
+        block_on(gc.tick());
+
+        assert_eq!(gc.high_scores, HighScores::new());
+        let texts = get_texts(get_last_frame(gc.get_renderer().get_commands()));
+        assert!(texts.contains(&GAME_OVER_TEXT));
+    }
+
+    #[test]
+    fn ultra_ends_when_time_runs_out() {
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        let mut renderer: RecordingRenderer = RecordingRenderer::new();
+        let clock = FakeClock::new();
+        let constants = get_mode_constants(GameMode::Ultra {
+            time_limit: Duration::from_secs(60),
+        });
+        let mut gc = GameController::new(&mut rng, &mut renderer, &clock, &constants);
+        gc.reset_game();
+        gc.score = 25;
+
+        clock.advance(Duration::from_secs(15));
+        assert_eq!(gc.get_hud_lines(), Vec::from(["Score:25", "Time:0:45.00"]));
+
+        clock.advance(Duration::from_secs(45));
+        block_on(gc.tick());
+
+        assert!(gc.is_game_over);
+        assert!(gc.is_goal_reached);
+        assert_eq!(gc.high_scores.get_record("ultra"), Some(Record::Score(25)));
+        assert_eq!(gc.get_game_over_title(), ULTRA_FINISHED_TEXT);
+    }
+
+    #[test]
+    fn reset_game_restarts_mode() {
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        let mut renderer: RecordingRenderer = RecordingRenderer::new();
+        let clock = FakeClock::new();
+        let constants = get_mode_constants(GameMode::Ultra {
+            time_limit: Duration::from_secs(60),
+        });
+        let mut gc = GameController::new(&mut rng, &mut renderer, &clock, &constants);
+        gc.reset_game();
+        gc.score = 25;
+        clock.advance(Duration::from_secs(60));
+        block_on(gc.tick());
+
+        gc.reset_game();
+
+        assert!(!gc.is_goal_reached);
+        assert_eq!(gc.cleared_grains, 0);
+        assert_eq!(gc.get_elapsed(), Duration::ZERO);
+        assert_eq!(gc.high_scores.get_record("ultra"), Some(Record::Score(25)));
+    }
+
+    #[test]
+    fn zen_top_out_erases_upper_rows() {
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        let mut renderer: RecordingRenderer = RecordingRenderer::new();
+        let clock = FakeClock::new();
+        let constants = get_mode_constants(GameMode::Zen);
+        let mut gc = GameController::new(&mut rng, &mut renderer, &clock, &constants);
+        gc.reset_game();
+        gc.map_controller
+            .spawn_block((0..10).map(|y| (0, y)).collect(), ColorFamily::Red);
+
+        gc.handle_top_out();
+
+        assert!(!gc.is_game_over);
+        let remaining: Vec<i32> = gc
+            .map_controller
+            .get_fields_to_draw()
+            .iter()
+            .map(|field| field.get_y())
+            .collect();
+        assert_eq!(remaining, Vec::from([5, 6, 7, 8, 9]));
+        assert_eq!(gc.particle_controller.get_particles().len(), 5);
+        assert!(gc
+            .timeline
+            .get_active(clock.now())
+            .iter()
+            .any(|(effect, _)| matches!(effect, Effect::ClearVanish(cells) if cells.len() == 5)));
+    }
+
+    #[test]
+    fn zen_hides_score() {
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        let mut renderer: RecordingRenderer = RecordingRenderer::new();
+        let clock = FakeClock::new();
+        let constants = get_mode_constants(GameMode::Zen);
+        let mut gc = GameController::new(&mut rng, &mut renderer, &clock, &constants);
+        gc.reset_game();
+        gc.score = 40;
+
+        assert!(gc.get_hud_lines().is_empty());
+
+        gc.toggle_cursor();
+        assert_eq!(gc.get_hud_lines(), Vec::from([CURSOR_TEXT]));
+    }
+
+    #[test]
+    fn cursor_only_in_zen() {
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        let mut renderer: RecordingRenderer = RecordingRenderer::new();
+        let clock = FakeClock::new();
+        let mut gc = GameController::new(&mut rng, &mut renderer, &clock, &TEST_CONSTANTS);
+
+        gc.toggle_cursor();
+
+        assert!(!gc.is_cursor_active);
+    }
+
+    #[test]
+    fn cursor_places_block() {
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        rng.set_block_type(BlockType::SquareBlock);
+        let mut renderer: RecordingRenderer = RecordingRenderer::new();
+        let clock = FakeClock::new();
+        let constants = get_mode_constants(GameMode::Zen);
+        let mut gc = GameController::new(&mut rng, &mut renderer, &clock, &constants);
+        gc.reset_game();
+        gc.toggle_cursor();
+        let start = gc.block_controller.get_block_to_draw().0;
+
+        block_on(gc.tick()); // Held by the cursor instead of falling
+        assert_eq!(gc.block_controller.get_block_to_draw().0, start);
+
+        for key in [KeyCode::S, KeyCode::S, KeyCode::W, KeyCode::D] {
+            gc.do_move(key);
+        }
+        let moved = gc.block_controller.get_block_to_draw().0;
+        assert_eq!(moved[0], (start[0].0 + 1, start[0].1 + 1));
+
+        gc.do_move(KeyCode::Space);
+
+        assert_eq!(gc.block_controller.get_settled_block_count(), 1);
+        let mut placed: Vec<(i32, i32)> = gc
+            .map_controller
+            .get_fields_to_draw()
+            .iter()
+            .map(|field| (field.get_x(), field.get_y()))
+            .collect();
+        placed.sort();
+        assert_eq!(placed, moved);
+    }
+
+    fn get_puzzle_rng(puzzle: &Puzzle) -> ScriptedTetrisRng<MockTetrisRng> {
+        ScriptedTetrisRng::new(
+            MockTetrisRng::new(),
+            puzzle.get_script(&PieceSet::classic()).unwrap(),
+        )
+    }
+
+    fn tick_until_game_over<R: TetrisRng, G: Renderer, C: Clock>(gc: &mut GameController<R, G, C>) {
+        for _ in 0..100 {
+            if gc.is_game_over {
+                break;
+            }
+            block_on(gc.tick());
+        }
+    }
+
+    #[test]
+    fn puzzle_lays_out_board() {
+        let puzzle =
+            Puzzle::parse("goal clear red\npiece O blue\npiece I green\nboard\n........rr\nend")
+                .unwrap();
+        let mut rng = get_puzzle_rng(&puzzle);
+        let mut renderer: RecordingRenderer = RecordingRenderer::new();
+        let clock = FakeClock::new();
+        let constants = get_mode_constants(GameMode::Puzzle);
+        let mut gc = GameController::new(&mut rng, &mut renderer, &clock, &constants);
+        gc.set_puzzle(puzzle);
+
+        gc.reset_game();
+
+        assert_eq!(gc.map_controller.count_grains(ColorFamily::Red), 2);
+        assert_eq!(gc.block_controller.get_block_to_draw().1, ColorFamily::Blue);
+        assert_eq!(
+            gc.block_controller.get_next_block_miniature().1,
+            ColorFamily::Green
+        );
+        assert_eq!(gc.get_hud_lines(), Vec::from(["Left red:2", "Pieces:2"]));
+    }
+
+    #[test]
+    fn puzzle_solved() {
+        let puzzle =
+            Puzzle::parse("goal clear red\npiece O blue\npiece O blue\nboard\n........rr\nend")
+                .unwrap();
+        let mut rng = get_puzzle_rng(&puzzle);
+        let mut renderer: RecordingRenderer = RecordingRenderer::new();
+        let clock = FakeClock::new();
+        let constants = get_mode_constants(GameMode::Puzzle);
+        let mut gc = GameController::new(&mut rng, &mut renderer, &clock, &constants);
+        gc.set_puzzle(puzzle);
+        gc.reset_game();
+        gc.map_controller
+            .demolish_fields(&Vec::from([(8, 9), (9, 9)])); // As if a clear took them
+
+        tick_until_game_over(&mut gc);
+
+        assert!(gc.is_goal_reached);
+        assert_eq!(gc.block_controller.get_settled_block_count(), 1); // Judged after the first block
+        assert_eq!(gc.get_game_over_title(), PUZZLE_SOLVED_TEXT);
+    }
+
+    #[test]
+    fn puzzle_failed_out_of_pieces() {
+        let puzzle = Puzzle::parse("goal clear red\npiece O blue\nboard\n........rr\nend").unwrap();
+        let mut rng = get_puzzle_rng(&puzzle);
+        let mut renderer: RecordingRenderer = RecordingRenderer::new();
+        let clock = FakeClock::new();
+        let constants = get_mode_constants(GameMode::Puzzle);
+        let mut gc = GameController::new(&mut rng, &mut renderer, &clock, &constants);
+        gc.set_puzzle(puzzle);
+        gc.reset_game();
+
+        tick_until_game_over(&mut gc);
+
+        assert!(gc.is_game_over);
+        assert!(!gc.is_goal_reached);
+        assert_eq!(gc.get_game_over_title(), PUZZLE_FAILED_TEXT);
+
+        gc.reset_game(); // Same board and pieces again
+
+        assert_eq!(gc.map_controller.count_grains(ColorFamily::Red), 2);
+        assert_eq!(gc.get_pieces_left(), Some(1));
+        assert_eq!(gc.block_controller.get_block_to_draw().1, ColorFamily::Blue);
+    }
+
+    #[test]
+    fn puzzle_survive() {
+        let puzzle = Puzzle::parse("goal survive 1\npiece O blue").unwrap();
+        let mut rng = get_puzzle_rng(&puzzle);
+        let mut renderer: RecordingRenderer = RecordingRenderer::new();
+        let clock = FakeClock::new();
+        let constants = get_mode_constants(GameMode::Puzzle);
+        let mut gc = GameController::new(&mut rng, &mut renderer, &clock, &constants);
+        gc.set_puzzle(puzzle);
+        gc.reset_game();
+        assert_eq!(gc.get_hud_lines(), Vec::from(["Survive:1"]));
+
+        tick_until_game_over(&mut gc);
+
+        assert!(gc.is_goal_reached);
+    }
+
+    fn get_garbage_constants(mode: GameMode) -> TetrisConstants {
+        TetrisConstants {
+            rules: GameRules {
+                mode,
+                garbage: Some(Garbage {
+                    interval: Duration::from_secs(5),
+                    rows: 2,
+                }),
+                ..GameRules::CLASSIC
+            },
+            ..TEST_CONSTANTS
+        }
+    }
+
+    #[test]
+    fn garbage_waits_for_block_to_settle() {
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        rng.set_block_type(BlockType::SquareBlock);
+        let mut renderer: RecordingRenderer = RecordingRenderer::new();
+        let clock = FakeClock::new();
+        let constants = get_garbage_constants(GameMode::Endless);
+        let mut gc = GameController::new(&mut rng, &mut renderer, &clock, &constants);
+        gc.reset_game();
+        clock.advance(Duration::from_secs(6)); // Due, but the first block is still falling
+
+        while gc.block_controller.get_settled_block_count() == 0 {
+            block_on(gc.tick());
+        }
+        assert_eq!(gc.map_controller.get_layout().len(), 4);
+
+        block_on(gc.tick());
+
+        let layout = gc.map_controller.get_layout();
+        assert_eq!(layout.iter().filter(|(_, y, _)| *y >= 8).count(), 20);
+        assert_eq!(layout.len(), 24);
+        assert!(!gc.is_game_over);
+        assert_eq!(gc.garbage_at, Duration::from_secs(11));
+    }
+
+    #[test]
+    fn garbage_pushing_sand_out_tops_out() {
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        let mut renderer: RecordingRenderer = RecordingRenderer::new();
+        let clock = FakeClock::new();
+        let constants = get_garbage_constants(GameMode::Endless);
+        let garbage = constants.rules.garbage.unwrap();
+        let mut gc = GameController::new(&mut rng, &mut renderer, &clock, &constants);
+        gc.reset_game();
+        gc.map_controller
+            .spawn_block((3..10).map(|y| (0, y)).collect(), ColorFamily::Red);
+
+        gc.raise_garbage(garbage); // The column now reaches the second row
+        assert!(!gc.is_game_over);
+
+        gc.raise_garbage(garbage);
+        assert!(gc.is_game_over);
+    }
+
+    #[test]
+    fn zen_garbage_erases_upper_rows() {
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        let mut renderer: RecordingRenderer = RecordingRenderer::new();
+        let clock = FakeClock::new();
+        let constants = get_garbage_constants(GameMode::Zen);
+        let garbage = constants.rules.garbage.unwrap();
+        let mut gc = GameController::new(&mut rng, &mut renderer, &clock, &constants);
+        gc.reset_game();
+        gc.map_controller
+            .spawn_block((0..10).map(|y| (0, y)).collect(), ColorFamily::Red);
+
+        gc.raise_garbage(garbage);
+
+        assert!(!gc.is_game_over);
+        assert!(gc.map_controller.get_sand_above(5).is_empty());
+    }
+
+    #[test]
+    fn garbage_warning_flashes_before_rising() {
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        let mut renderer: RecordingRenderer = RecordingRenderer::new();
+        let clock = FakeClock::new();
+        let constants = get_garbage_constants(GameMode::Endless);
+        let mut gc = GameController::new(&mut rng, &mut renderer, &clock, &constants);
+        gc.reset_game();
+
+        block_on(gc.tick());
+        let overlays = get_overlays(get_last_frame(gc.get_renderer().get_commands()));
+        assert!(!overlays.iter().any(|(color, _)| *color == RED));
+
+        // Half a flash into the warning, when it is the brightest
+        clock.advance(
+            Duration::from_secs(5) - GARBAGE_WARNING_DURATION + Duration::from_millis(250),
+        );
+        block_on(gc.tick());
+        let overlays = get_overlays(get_last_frame(gc.get_renderer().get_commands()));
+        assert!(overlays.contains(&(RED, GARBAGE_WARNING_OPACITY)));
+    }
+
+    // Blocks move a grain at a time, so they get as close to the wall as on an even board
+    #[test]
+    fn block_reaches_wall_of_uneven_board() {
+        for width in [20, 21] {
+            let mut rng: MockTetrisRng = MockTetrisRng::new();
+            rng.set_block_type(BlockType::SquareBlock);
+            let mut renderer: RecordingRenderer = RecordingRenderer::new();
+            let clock = FakeClock::new();
+            let board = BoardSize {
+                width, // Ten chunks, then ten and a half
+                height: 12,
+                grain_side_size: 1,
+                block_chunk_side: 2,
+            };
+            let constants = TetrisConstants::with_board(board, GameRules::CLASSIC).unwrap();
+            let mut gc = GameController::new(&mut rng, &mut renderer, &clock, &constants);
+            gc.reset_game();
+            for _ in 0..5 {
+                block_on(gc.tick()); // Into the map, pushing against a wall above it tops out
+            }
+
+            for _ in 0..width {
+                gc.do_move(KeyCode::D);
+            }
+            while gc.block_controller.get_settled_block_count() == 0 {
+                block_on(gc.tick());
+            }
+
+            assert!(!gc.is_game_over);
+            let layout = gc.map_controller.get_layout();
+            assert_eq!(layout.iter().map(|(x, _, _)| *x).max(), Some(width - 2));
+        }
+    }
 }
@@ -1,9 +1,16 @@
-use macroquad::color::Color;
+use std::{
+    cmp::Ordering,
+    collections::{HashSet, VecDeque},
+};
 
 use crate::{
-    constants::{colors::BACKGROUND_COLOR, TetrisConstants},
+    constants::TetrisConstants,
     controllers::graphic_controller::GraphicController,
-    objects::{field::Field, map::Map},
+    objects::{
+        color_family::{ColorFamily, GrainColor},
+        field::Field,
+        map::Map,
+    },
     utils::tetris_rng::TetrisRng,
 };
 
@@ -22,7 +29,8 @@ pub enum ColisionType {
 
 impl<'a> MapController<'a> {
     pub fn new(constants: &'a TetrisConstants) -> Self {
-        let map = Map::new(constants.map_width, constants.map_height);
+        let mut map = Map::new(constants.map_width, constants.map_height);
+        map.set_matching_rules(constants.rules.matching_rules);
 
         MapController {
             map,
@@ -44,28 +52,65 @@ impl<'a> MapController<'a> {
         self.map.tick_and_get_score_fields(rng)
     }
 
+    pub fn is_still(&self) -> bool {
+        self.map.is_still()
+    }
+
     pub fn demolish_fields(&mut self, fields: &Vec<(i32, i32)>) {
         for (x, y) in fields {
-            self.map.change_field(*x, *y, BACKGROUND_COLOR, 0);
+            self.map.change_field(*x, *y, None, 0);
         }
     }
 
+    // Sand in the rows above the given one
+    pub fn get_sand_above(&self, row: i32) -> Vec<(i32, i32)> {
+        self.map
+            .filter_fields(|field: &Field| !field.is_empty() && field.get_y() < row)
+            .into_iter()
+            .map(|field: &Field| (field.get_x(), field.get_y()))
+            .collect()
+    }
+
     pub fn get_fields_to_draw(&self) -> Vec<&Field> {
         self.map.filter_fields(|field: &Field| field.do_draw())
     }
 
-    pub fn get_shuffled_fields(
-        &self,
-        fields_coords: &Vec<(i32, i32)>,
-        rng: &mut impl TetrisRng,
-    ) -> Vec<&Field> {
-        let mut fields_to_demolish = Vec::new();
-        for (x, y) in fields_coords {
-            fields_to_demolish.push(self.map.get_field(*x, *y).unwrap());
+    pub fn count_grains(&self, family: ColorFamily) -> usize {
+        self.map
+            .filter_fields(|field: &Field| field.get_family() == Some(family))
+            .len()
+    }
+
+    pub fn get_fields(&self, fields_coords: &[(i32, i32)]) -> Vec<&Field> {
+        fields_coords
+            .iter()
+            .filter_map(|(x, y)| self.map.get_field(*x, *y))
+            .collect()
+    }
+
+    // Cleared fields grouped by touching each other, diagonals included
+    pub fn split_into_components(fields: &[(i32, i32)]) -> Vec<Vec<(i32, i32)>> {
+        let mut remaining: HashSet<(i32, i32)> = fields.iter().copied().collect();
+        let mut components = Vec::new();
+        for start in fields {
+            if !remaining.remove(start) {
+                continue; // Already part of an earlier component
+            }
+
+            let mut component = Vec::from([*start]);
+            let mut queue = VecDeque::from([*start]);
+            while let Some((x, y)) = queue.pop_front() {
+                for (n_x, n_y) in (-1..=1).flat_map(|n_x| (-1..=1).map(move |n_y| (n_x, n_y))) {
+                    if remaining.remove(&(x + n_x, y + n_y)) {
+                        component.push((x + n_x, y + n_y));
+                        queue.push_back((x + n_x, y + n_y));
+                    }
+                }
+            }
+            components.push(component);
         }
-        rng.shuffle_fields(&mut fields_to_demolish);
 
-        fields_to_demolish
+        components
     }
 
     pub fn can_block_move_down(
@@ -124,6 +169,14 @@ impl<'a> MapController<'a> {
         (true, ColisionType::NoColision)
     }
 
+    // Sides, floor and sand only, a block can still stick out above the map
+    pub fn can_block_fit(&self, schema: &Vec<(i8, i8)>, center_pos: (i32, i32)) -> bool {
+        !self.is_block_coliding_bottom_border(schema, center_pos)
+            && !self.is_block_coliding_left_border(schema, center_pos)
+            && !self.is_block_coliding_right_border(schema, center_pos)
+            && !self.is_block_coliding_with_sand(schema, center_pos)
+    }
+
     fn is_block_coliding_with_any_border(
         &self,
         schema: &Vec<(i8, i8)>,
@@ -208,11 +261,192 @@ impl<'a> MapController<'a> {
         false
     }
 
-    pub fn spawn_block(&mut self, schema: Vec<(i32, i32)>, color: Color) {
-        for (x, y, color) in
-            GraphicController::get_skin_for_schema(schema, color, self.constants.block_chunk_side)
+    pub fn spawn_block(&mut self, schema: Vec<(i32, i32)>, family: ColorFamily) {
+        for (x, y, color) in GraphicController::get_skin_for_schema(
+            schema,
+            family,
+            self.constants.block_chunk_side,
+            self.constants.theme,
+        ) {
+            self.map
+                .change_field(x, y, Some(color), self.current_group_id);
+        }
+        self.current_group_id += 1;
+    }
+
+    // Replaces the map with a handcrafted board, matching grains that touch start out in one group
+    pub fn load_layout(&mut self, layout: &[(i32, i32, ColorFamily)]) {
+        self.clear();
+        for (x, y, family) in layout {
+            self.paint_grain(*x, *y, Some(*family));
+        }
+
+        let matching_rules = self.constants.rules.matching_rules;
+        for (x, y, _) in layout {
+            if self.map.get_field_group_id(*x, *y) != Some(0) {
+                continue; // Out of the map or already grouped
+            }
+
+            let mut queue = VecDeque::from([(*x, *y)]);
+            self.map.set_field_group_id(*x, *y, self.current_group_id);
+            while let Some((cur_x, cur_y)) = queue.pop_front() {
+                let family = self
+                    .map
+                    .get_field(cur_x, cur_y)
+                    .unwrap()
+                    .get_family()
+                    .unwrap();
+                for (n_x, n_y) in matching_rules.get_neighbour_offsets() {
+                    let neighbour = (cur_x + n_x, cur_y + n_y);
+                    let does_match = match self.map.get_field(neighbour.0, neighbour.1) {
+                        Some(field) => {
+                            field.get_group_id() == 0
+                                && field.get_family().is_some_and(|other_family| {
+                                    matching_rules.do_colors_match(family, other_family)
+                                })
+                        }
+                        None => false,
+                    };
+                    if does_match {
+                        self.map.set_field_group_id(
+                            neighbour.0,
+                            neighbour.1,
+                            self.current_group_id,
+                        );
+                        queue.push_back(neighbour);
+                    }
+                }
+            }
+            self.current_group_id += 1;
+        }
+    }
+
+    // Grains of the map as a layout, the other way around from load_layout
+    pub fn get_layout(&self) -> Vec<(i32, i32, ColorFamily)> {
+        self.map
+            .filter_fields(|field: &Field| !field.is_empty())
+            .into_iter()
+            .map(|field: &Field| (field.get_x(), field.get_y(), field.get_family().unwrap()))
+            .collect()
+    }
+
+    // Moves the sand up and fills the freed rows with patches of random colors, a chunk wide each.
+    // True when sand was pushed out past the top.
+    pub fn push_garbage(&mut self, rows: i32, rng: &mut impl TetrisRng) -> bool {
+        let is_overflowing = !self.get_sand_above(rows).is_empty();
+        let mut layout: Vec<(i32, i32, ColorFamily)> = self
+            .get_layout()
+            .into_iter()
+            .filter(|(_, y, _)| *y >= rows)
+            .map(|(x, y, family)| (x, y - rows, family))
+            .collect();
+
+        let (width, height) = (self.map.get_width(), self.map.get_height());
+        let patch_width = self.constants.block_chunk_side;
+        for patch_x in (0..width).step_by(patch_width as usize) {
+            let family = rng.generate_block_color(self.constants.rules.color_weights.get_weights());
+            for x in patch_x..(patch_x + patch_width).min(width) {
+                for y in height - rows..height {
+                    layout.push((x, y, family));
+                }
+            }
+        }
+        self.load_layout(&layout); // Regrouped, so the garbage joins matching sand it touches
+
+        is_overflowing
+    }
+
+    // Puts down a grain outside of any group, or empties the field
+    pub fn paint_grain(&mut self, x: i32, y: i32, family: Option<ColorFamily>) {
+        let color = family.map(|family| self.get_grain_color(x, y, family));
+        self.map.change_field(x, y, color, 0);
+    }
+
+    // Drops a loose grain into an empty field, it joins matching sand once it falls against it
+    pub fn pour_grain(&mut self, x: i32, y: i32, family: ColorFamily) -> bool {
+        if !self
+            .map
+            .get_field(x, y)
+            .is_some_and(|field| field.is_empty())
         {
-            self.map.change_field(x, y, color, self.current_group_id);
+            return false;
+        }
+
+        let color = self.get_grain_color(x, y, family);
+        self.map
+            .change_field(x, y, Some(color), self.current_group_id);
+        self.current_group_id += 1;
+
+        true
+    }
+
+    // Shaded by the theme's skin like the grains of a block in the same spot
+    fn get_grain_color(&self, x: i32, y: i32, family: ColorFamily) -> GrainColor {
+        GrainColor::new(
+            family,
+            self.constants.theme.get_skin(family).get_shade_index(x, y),
+        )
+    }
+
+    // Fields reachable from the given one through their sides, holding the same family or all empty
+    pub fn get_region(&self, x: i32, y: i32) -> Vec<(i32, i32)> {
+        let family = match self.map.get_field(x, y) {
+            Some(field) => field.get_family(),
+            None => return Vec::new(),
+        };
+
+        let mut region = Vec::from([(x, y)]);
+        let mut visited = HashSet::from([(x, y)]);
+        let mut queue = VecDeque::from([(x, y)]);
+        while let Some((cur_x, cur_y)) = queue.pop_front() {
+            for neighbour in [
+                (cur_x - 1, cur_y),
+                (cur_x + 1, cur_y),
+                (cur_x, cur_y - 1),
+                (cur_x, cur_y + 1),
+            ] {
+                let is_same = self
+                    .map
+                    .get_field(neighbour.0, neighbour.1)
+                    .is_some_and(|field| field.get_family() == family);
+                if is_same && visited.insert(neighbour) {
+                    region.push(neighbour);
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+
+        region
+    }
+
+    pub fn shatter_block(
+        &mut self,
+        schema: Vec<(i32, i32)>,
+        family: ColorFamily,
+        max_momentum: i32,
+        rng: &mut impl TetrisRng,
+    ) {
+        let skin = GraphicController::get_skin_for_schema(
+            schema,
+            family,
+            self.constants.block_chunk_side,
+            self.constants.theme,
+        );
+        let min_x = skin.iter().map(|grain| grain.0).min().unwrap_or(0);
+        let max_x = skin.iter().map(|grain| grain.0).max().unwrap_or(0);
+
+        for (x, y, color) in skin {
+            // Grains fly away from the middle of the block
+            let direction = match (2 * x).cmp(&(min_x + max_x)) {
+                Ordering::Less => -1,
+                Ordering::Greater => 1,
+                Ordering::Equal if rng.gen_do_go_right() => 1,
+                Ordering::Equal => -1,
+            };
+            self.map
+                .change_field(x, y, Some(color), self.current_group_id);
+            self.map
+                .set_field_momentum(x, y, direction * rng.gen_scatter_momentum(max_momentum));
         }
         self.current_group_id += 1;
     }
@@ -243,12 +477,15 @@ impl<'a> MapController<'a> {
 #[cfg(test)]
 mod test {
     use crate::{
-        constants::colors::{BACKGROUND_COLOR, RED, WHITE},
-        utils::tetris_rng::ThreadTetrisRng,
+        constants::GameRules,
+        objects::{color_family::GrainColor, theme::Theme},
+        utils::tetris_rng::{MockTetrisRng, ThreadTetrisRng},
     };
 
     use super::*;
 
+    const RED: Option<GrainColor> = Some(GrainColor::new(ColorFamily::Red, 0));
+
     fn get_test_constants(width: i32, height: i32) -> TetrisConstants {
         TetrisConstants {
             map_width: width,
@@ -257,6 +494,8 @@ mod test {
             grain_side_size: 1,
             preview_block_chunk_side: 1,
             block_starting_pos: (5, 0),
+            rules: GameRules::CLASSIC,
+            theme: &Theme::CLASSIC,
         }
     }
 
@@ -284,15 +523,149 @@ mod test {
         let test_schema: Vec<(i32, i32)> = Vec::from([(0, 0)]);
 
         mc.current_group_id = 10;
-        mc.spawn_block(test_schema, RED);
+        mc.spawn_block(test_schema, ColorFamily::Red);
 
         mc.clear();
 
         assert_eq!(mc.current_group_id, 1);
+        assert_eq!(mc.map.get_field(0, 0).unwrap().get_color(), None);
+    }
+
+    #[test]
+    fn load_layout() {
+        let constants = get_test_constants(4, 4);
+        let mut mc: MapController = MapController::new(&constants);
+        mc.spawn_block(Vec::from([(3, 0)]), ColorFamily::Blue);
+        /*
+            0|            |
+            1|            |
+            2|[r][r]      |
+            3|[b]   [r][r]|   <- the right pair only touches the others diagonally
+               0  1  2  3
+        */
+
+        mc.load_layout(&[
+            (0, 2, ColorFamily::Red),
+            (1, 2, ColorFamily::Red),
+            (0, 3, ColorFamily::Blue),
+            (2, 3, ColorFamily::Red),
+            (3, 3, ColorFamily::Red),
+            (9, 9, ColorFamily::Red), // Off the map
+        ]);
+
+        assert_eq!(mc.map.get_field(3, 0).unwrap().get_color(), None);
+        assert_eq!(
+            mc.map.get_field(2, 3).unwrap().get_family(),
+            Some(ColorFamily::Red)
+        );
+        let group = mc.map.get_field_group_id(0, 2).unwrap();
+        assert_ne!(group, 0);
+        for (x, y) in [(1, 2), (2, 3), (3, 3)] {
+            assert_eq!(mc.map.get_field_group_id(x, y), Some(group));
+        }
+        assert_ne!(mc.map.get_field_group_id(0, 3), Some(group));
+        assert_eq!(mc.get_fields_to_draw().len(), 5);
+    }
+
+    #[test]
+    fn get_layout_and_paint_grain() {
+        let constants = get_test_constants(4, 4);
+        let mut mc: MapController = MapController::new(&constants);
+
+        mc.paint_grain(1, 2, Some(ColorFamily::Green));
+        mc.paint_grain(3, 3, Some(ColorFamily::Red));
+        mc.paint_grain(9, 9, Some(ColorFamily::Red)); // Off the map
+
+        assert_eq!(
+            mc.get_layout(),
+            Vec::from([(1, 2, ColorFamily::Green), (3, 3, ColorFamily::Red)])
+        );
+        assert_eq!(mc.map.get_field_group_id(1, 2), Some(0));
+
+        mc.paint_grain(1, 2, None);
+
+        assert_eq!(mc.get_layout(), Vec::from([(3, 3, ColorFamily::Red)]));
+    }
+
+    #[test]
+    fn pour_grain() {
+        let constants = get_test_constants(4, 4);
+        let mut mc: MapController = MapController::new(&constants);
+
+        assert!(mc.pour_grain(1, 0, ColorFamily::Red));
+        assert!(mc.pour_grain(2, 0, ColorFamily::Red));
+        assert!(!mc.pour_grain(1, 0, ColorFamily::Blue)); // Taken
+        assert!(!mc.pour_grain(4, 0, ColorFamily::Blue)); // Off the map
+
         assert_eq!(
-            mc.map.get_field(0, 0).unwrap().get_color(),
-            BACKGROUND_COLOR
+            mc.get_layout(),
+            Vec::from([(1, 0, ColorFamily::Red), (2, 0, ColorFamily::Red)])
         );
+        let group_id = mc.map.get_field_group_id(1, 0).unwrap();
+        assert_ne!(group_id, 0);
+        assert_ne!(mc.map.get_field_group_id(2, 0), Some(group_id));
+    }
+
+    #[test]
+    fn push_garbage() {
+        let constants = TetrisConstants {
+            block_chunk_side: 2,
+            ..get_test_constants(3, 4)
+        };
+        let mut mc: MapController = MapController::new(&constants);
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        rng.set_block_color(ColorFamily::Blue);
+        mc.paint_grain(0, 3, Some(ColorFamily::Red));
+        mc.paint_grain(2, 2, Some(ColorFamily::Red));
+
+        assert!(!mc.push_garbage(2, &mut rng));
+
+        let mut layout = mc.get_layout();
+        layout.sort_by_key(|(x, y, _)| (*y, *x));
+        assert_eq!(
+            layout,
+            Vec::from([
+                (2, 0, ColorFamily::Red),
+                (0, 1, ColorFamily::Red),
+                (0, 2, ColorFamily::Blue),
+                (1, 2, ColorFamily::Blue),
+                (2, 2, ColorFamily::Blue),
+                (0, 3, ColorFamily::Blue),
+                (1, 3, ColorFamily::Blue),
+                (2, 3, ColorFamily::Blue),
+            ])
+        );
+        assert_eq!(
+            mc.map.get_field_group_id(0, 2),
+            mc.map.get_field_group_id(2, 3)
+        ); // One group across both patches
+
+        assert!(mc.push_garbage(1, &mut rng)); // The top row had sand
+        assert_eq!(mc.get_layout().len(), 10);
+    }
+
+    #[test]
+    fn get_region() {
+        let constants = get_test_constants(3, 3);
+        let mut mc: MapController = MapController::new(&constants);
+        /*
+            0|[r]      |
+            1|   [r][b]|   <- the top left grain only touches the others diagonally
+            2|   [r]   |
+               0  1  2
+        */
+        mc.paint_grain(0, 0, Some(ColorFamily::Red));
+        mc.paint_grain(1, 1, Some(ColorFamily::Red));
+        mc.paint_grain(1, 2, Some(ColorFamily::Red));
+        mc.paint_grain(2, 1, Some(ColorFamily::Blue));
+
+        let mut region = mc.get_region(1, 2);
+        region.sort();
+        assert_eq!(region, Vec::from([(1, 1), (1, 2)]));
+        assert_eq!(mc.get_region(0, 2).len(), 2); // Up the left side to the top left grain
+        assert_eq!(mc.get_region(2, 2).len(), 1); // Walled in
+        assert_eq!(mc.get_region(1, 0).len(), 2);
+        assert!(mc.get_region(-1, 0).is_empty());
     }
 
     #[test]
@@ -333,14 +706,22 @@ mod test {
         mc.demolish_fields(&fields);
 
         for (x, y) in fields {
-            assert_eq!(
-                mc.map.get_field(x, y).unwrap().get_color(),
-                BACKGROUND_COLOR
-            );
+            assert_eq!(mc.map.get_field(x, y).unwrap().get_color(), None);
             assert_eq!(mc.map.get_field_group_id(x, y).unwrap(), 0);
         }
     }
 
+    #[test]
+    fn get_sand_above() {
+        let constants = get_test_constants(10, 10);
+        let mut mc: MapController = MapController::new(&constants);
+        mc.map.change_field(1, 2, RED, 1);
+        mc.map.change_field(3, 5, RED, 1);
+
+        assert_eq!(mc.get_sand_above(5), Vec::from([(1, 2)]));
+        assert_eq!(mc.get_sand_above(6).len(), 2);
+    }
+
     #[test]
     fn get_fields_to_draw() {
         let constants = get_test_constants(200, 400);
@@ -368,20 +749,56 @@ mod test {
     }
 
     #[test]
-    fn get_shuffled_fields() {
+    fn count_grains() {
+        let constants = get_test_constants(4, 4);
+        let mut mc: MapController = MapController::new(&constants);
+
+        mc.spawn_block(Vec::from([(0, 0), (1, 0)]), ColorFamily::Red);
+        mc.spawn_block(Vec::from([(0, 1)]), ColorFamily::Blue);
+
+        assert_eq!(mc.count_grains(ColorFamily::Red), 2);
+        assert_eq!(mc.count_grains(ColorFamily::Green), 0);
+    }
+
+    #[test]
+    fn get_fields() {
         let constants = get_test_constants(10, 10);
         let mc: MapController = MapController::new(&constants);
-        let mut rng: ThreadTetrisRng = ThreadTetrisRng::new();
 
         let fields_coords: Vec<(i32, i32)> = Vec::from([(0, 0), (5, 5), (9, 9)]);
 
-        let shuffled_fields = mc.get_shuffled_fields(&fields_coords, &mut rng);
-        assert_eq!(shuffled_fields.len(), 3);
-        for (x, y) in fields_coords {
-            assert!(shuffled_fields.contains(&mc.map.get_field(x, y).unwrap()));
+        let fields = mc.get_fields(&fields_coords);
+        assert_eq!(fields.len(), 3);
+        for ((x, y), field) in fields_coords.into_iter().zip(fields) {
+            assert_eq!(field, mc.map.get_field(x, y).unwrap());
         }
     }
 
+    #[test]
+    fn split_into_components() {
+        let fields = [(0, 0), (1, 1), (2, 1), (5, 5), (6, 4), (9, 0)];
+
+        let mut components = MapController::split_into_components(&fields);
+        for component in components.iter_mut() {
+            component.sort();
+        }
+        components.sort();
+
+        assert_eq!(
+            components,
+            Vec::from([
+                Vec::from([(0, 0), (1, 1), (2, 1)]),
+                Vec::from([(5, 5), (6, 4)]),
+                Vec::from([(9, 0)]),
+            ])
+        );
+    }
+
+    #[test]
+    fn split_into_components_empty() {
+        assert!(MapController::split_into_components(&[]).is_empty());
+    }
+
     #[test]
     fn can_block_move_down() {
         let constants = get_test_constants(4, 4);
@@ -490,6 +907,20 @@ mod test {
         assert_eq!(collision_type, ColisionType::BorderColision);
     }
 
+    #[test]
+    fn can_block_fit() {
+        let constants = get_test_constants(4, 4);
+        let mut mc: MapController = MapController::new(&constants);
+        let test_schema: Vec<(i8, i8)> = Vec::from([(0, 0), (1, 0)]);
+        mc.map.change_field(2, 2, RED, 1);
+
+        assert!(mc.can_block_fit(&test_schema, (0, 0)));
+        assert!(mc.can_block_fit(&test_schema, (0, -1))); // Above the map
+        assert!(!mc.can_block_fit(&test_schema, (1, 2))); // Sand
+        assert!(!mc.can_block_fit(&test_schema, (3, 0))); // Right border
+        assert!(!mc.can_block_fit(&test_schema, (0, 3))); // Floor
+    }
+
     #[test]
     fn is_block_coliding_with_any_border() {
         let constants = get_test_constants(4, 4);
@@ -587,17 +1018,83 @@ mod test {
         let mut mc: MapController = MapController::new(&constants);
         let test_schema: Vec<(i32, i32)> = Vec::from([(0, 0), (0, 2)]);
 
-        mc.spawn_block(test_schema, WHITE);
+        mc.spawn_block(test_schema, ColorFamily::Green);
+
+        assert_eq!(mc.current_group_id, 2);
+        assert_eq!(
+            mc.map.get_field(0, 0).unwrap().get_family(),
+            Some(ColorFamily::Green)
+        );
+        assert_eq!(
+            mc.map.get_field(0, 1).unwrap().get_family(),
+            Some(ColorFamily::Green)
+        );
+        assert_eq!(
+            mc.map.get_field(1, 0).unwrap().get_family(),
+            Some(ColorFamily::Green)
+        );
+        assert_eq!(
+            mc.map.get_field(1, 1).unwrap().get_family(),
+            Some(ColorFamily::Green)
+        );
+        assert_eq!(
+            mc.map.get_field(0, 2).unwrap().get_family(),
+            Some(ColorFamily::Green)
+        );
+        assert_eq!(
+            mc.map.get_field(0, 3).unwrap().get_family(),
+            Some(ColorFamily::Green)
+        );
+        assert_eq!(
+            mc.map.get_field(1, 2).unwrap().get_family(),
+            Some(ColorFamily::Green)
+        );
+        assert_eq!(
+            mc.map.get_field(1, 3).unwrap().get_family(),
+            Some(ColorFamily::Green)
+        );
+    }
+
+    #[test]
+    fn shatter_block() {
+        let mut constants = get_test_constants(10, 10);
+        constants.block_chunk_side = 2;
+        let mut mc: MapController = MapController::new(&constants);
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        rng.set_scatter_momentum(3);
+        let test_schema: Vec<(i32, i32)> = Vec::from([(0, 0), (2, 0)]);
+        /*
+            0|[<][<][>][>]
+            1|[<][<][>][>]
+               0  1  2  3
+        */
+
+        mc.shatter_block(test_schema, ColorFamily::Green, 2, &mut rng);
 
         assert_eq!(mc.current_group_id, 2);
-        assert_eq!(mc.map.get_field(0, 0).unwrap().get_color(), WHITE);
-        assert_eq!(mc.map.get_field(0, 1).unwrap().get_color(), WHITE);
-        assert_eq!(mc.map.get_field(1, 0).unwrap().get_color(), WHITE);
-        assert_eq!(mc.map.get_field(1, 1).unwrap().get_color(), WHITE);
-        assert_eq!(mc.map.get_field(0, 2).unwrap().get_color(), WHITE);
-        assert_eq!(mc.map.get_field(0, 3).unwrap().get_color(), WHITE);
-        assert_eq!(mc.map.get_field(1, 2).unwrap().get_color(), WHITE);
-        assert_eq!(mc.map.get_field(1, 3).unwrap().get_color(), WHITE);
+        for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+            assert_eq!(
+                mc.map.get_field(x, y).unwrap().get_family(),
+                Some(ColorFamily::Green)
+            );
+            assert_eq!(mc.map.get_field(x, y).unwrap().get_momentum(), -2);
+        }
+        for (x, y) in [(2, 0), (3, 0), (2, 1), (3, 1)] {
+            assert_eq!(mc.map.get_field(x, y).unwrap().get_momentum(), 2);
+        }
+    }
+
+    #[test]
+    fn shatter_block_middle_column() {
+        let constants = get_test_constants(10, 10);
+        let mut mc: MapController = MapController::new(&constants);
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        rng.set_scatter_momentum(1);
+        rng.set_go_right(true);
+
+        mc.shatter_block(Vec::from([(4, 0)]), ColorFamily::Green, 2, &mut rng);
+
+        assert_eq!(mc.map.get_field(4, 0).unwrap().get_momentum(), 1);
     }
 
     #[test]
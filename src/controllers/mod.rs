@@ -1,4 +1,7 @@
 pub mod block_controller;
+pub mod editor_controller;
 pub mod game_controller;
 pub mod graphic_controller;
 pub mod map_controller;
+pub mod particle_controller;
+pub mod sandbox_controller;
@@ -0,0 +1,146 @@
+use crate::{
+    constants::{
+        animation_constants::{PARTICLE_GRAVITY, PARTICLE_LIFETIME, PARTICLE_MAX_SPEED},
+        TetrisConstants,
+    },
+    objects::{field::Field, particle::Particle},
+    utils::tetris_rng::TetrisRng,
+};
+
+pub struct ParticleController<'a> {
+    particles: Vec<Particle>,
+    constants: &'a TetrisConstants,
+}
+
+impl<'a> ParticleController<'a> {
+    pub fn new(constants: &'a TetrisConstants) -> Self {
+        ParticleController {
+            particles: Vec::new(),
+            constants,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.particles.clear();
+    }
+
+    // Every grain of the fields flies off from the middle of its cell
+    pub fn burst(&mut self, fields: &[&Field], rng: &mut impl TetrisRng) {
+        for field in fields {
+            if let Some(color) = field.get_color() {
+                self.particles.push(Particle::new(
+                    field.get_x() as f32 + 0.5,
+                    field.get_y() as f32 + 0.5,
+                    rng.gen_particle_velocity(PARTICLE_MAX_SPEED),
+                    color,
+                    PARTICLE_LIFETIME,
+                ));
+            }
+        }
+    }
+
+    pub fn tick(&mut self) {
+        for particle in self.particles.iter_mut() {
+            particle.tick(PARTICLE_GRAVITY);
+        }
+
+        // Gravity keeps pulling them down, so ones past the sides or the bottom won't come back
+        let (map_width, map_height) = (
+            self.constants.map_width as f32,
+            self.constants.map_height as f32,
+        );
+        self.particles.retain(|particle| {
+            particle.is_alive()
+                && (0.0..map_width).contains(&particle.get_x())
+                && particle.get_y() < map_height
+        });
+    }
+
+    pub fn get_particles(&self) -> &[Particle] {
+        &self.particles
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        constants::GameRules,
+        objects::{
+            color_family::{ColorFamily, GrainColor},
+            theme::Theme,
+        },
+        utils::tetris_rng::MockTetrisRng,
+    };
+
+    use super::*;
+
+    const TEST_CONSTANTS: TetrisConstants = TetrisConstants {
+        map_width: 10,
+        map_height: 10,
+        block_chunk_side: 1,
+        grain_side_size: 1,
+        preview_block_chunk_side: 1,
+        block_starting_pos: (0, 0),
+        rules: GameRules::CLASSIC,
+        theme: &Theme::CLASSIC,
+    };
+
+    const RED: Option<GrainColor> = Some(GrainColor::new(ColorFamily::Red, 2));
+
+    #[test]
+    fn burst() {
+        let mut pc = ParticleController::new(&TEST_CONSTANTS);
+        let mut rng = MockTetrisRng::new();
+        rng.set_particle_velocity((0.5, -1.0));
+        let fields = [Field::new(2, 3, RED, 1), Field::new(4, 3, None, 0)];
+
+        pc.burst(&fields.iter().collect::<Vec<&Field>>(), &mut rng);
+
+        let particles = pc.get_particles();
+        assert_eq!(particles.len(), 1); // Empty fields have nothing to throw
+        assert_eq!((particles[0].get_x(), particles[0].get_y()), (2.5, 3.5));
+        assert_eq!(particles[0].get_velocity(), (0.5, -1.0));
+        assert_eq!(Some(particles[0].get_color()), RED);
+    }
+
+    #[test]
+    fn tick_removes_faded_particles() {
+        let mut pc = ParticleController::new(&TEST_CONSTANTS);
+        let mut rng = MockTetrisRng::new();
+        rng.set_particle_velocity((0.0, -1.2)); // Stays over the map until it fades
+        pc.burst(&[&Field::new(2, 0, RED, 1)], &mut rng);
+
+        for _ in 0..PARTICLE_LIFETIME - 1 {
+            pc.tick();
+        }
+        assert_eq!(pc.get_particles().len(), 1);
+
+        pc.tick();
+        assert!(pc.get_particles().is_empty());
+    }
+
+    #[test]
+    fn tick_removes_particles_leaving_map() {
+        let mut pc = ParticleController::new(&TEST_CONSTANTS);
+        let mut rng = MockTetrisRng::new();
+        rng.set_particle_velocity((-1.0, 1.0));
+        pc.burst(
+            &[&Field::new(0, 0, RED, 1), &Field::new(5, 9, RED, 1)],
+            &mut rng,
+        );
+
+        pc.tick();
+
+        assert!(pc.get_particles().is_empty());
+    }
+
+    #[test]
+    fn clear() {
+        let mut pc = ParticleController::new(&TEST_CONSTANTS);
+        pc.burst(&[&Field::new(2, 3, RED, 1)], &mut MockTetrisRng::new());
+
+        pc.clear();
+
+        assert!(pc.get_particles().is_empty());
+    }
+}
@@ -0,0 +1,341 @@
+use macroquad::input::KeyCode;
+
+use crate::{
+    constants::{
+        interface_constants::{POUR_RATE_TEXT, SAND_TEXT, SIZE_TEXT},
+        sandbox_constants::{
+            DEFAULT_BRUSH_RADIUS, DEFAULT_POUR_RATE, MAX_BRUSH_RADIUS, MAX_POUR_RATE,
+        },
+        TetrisConstants,
+    },
+    controllers::map_controller::MapController,
+    objects::color_family::ColorFamily,
+    renderers::{renderer::Renderer, viewport::Viewport},
+    utils::{
+        brush::{get_disc, get_key_color},
+        tetris_rng::TetrisRng,
+    },
+};
+
+// Sand poured straight into the map with the mouse, without blocks or a score
+pub struct SandboxController<'a, R, G>
+where
+    R: TetrisRng,
+    G: Renderer,
+{
+    family: ColorFamily,
+    brush_radius: i32,
+    pour_rate: usize,
+    cursor: (i32, i32),
+    is_pouring: bool,
+    is_erasing: bool,
+    map_controller: MapController<'a>,
+    rng: &'a mut R,
+    renderer: &'a mut G,
+    constants: &'a TetrisConstants,
+}
+
+impl<'a, R, G> SandboxController<'a, R, G>
+where
+    R: TetrisRng,
+    G: Renderer,
+{
+    pub fn new(rng: &'a mut R, renderer: &'a mut G, constants: &'a TetrisConstants) -> Self {
+        SandboxController {
+            family: ColorFamily::Red,
+            brush_radius: DEFAULT_BRUSH_RADIUS,
+            pour_rate: DEFAULT_POUR_RATE,
+            cursor: (0, 0),
+            is_pouring: false,
+            is_erasing: false,
+            map_controller: MapController::new(constants),
+            rng,
+            renderer,
+            constants,
+        }
+    }
+
+    pub async fn tick(&mut self) {
+        if self.is_pouring {
+            self.pour();
+        }
+        if self.is_erasing {
+            for (x, y) in self.get_brush_grains() {
+                self.map_controller.paint_grain(x, y, None);
+            }
+        }
+
+        // Sand touching both walls still clears, there is just no score for it
+        let score_fields = self.map_controller.tick_and_get_score_fields(self.rng);
+        self.map_controller.demolish_fields(&score_fields);
+
+        let viewport = self.get_viewport();
+        self.renderer
+            .draw_background(&viewport, self.constants.theme);
+        self.renderer.draw_fields(
+            &self.map_controller.get_fields_to_draw(),
+            &viewport,
+            self.constants.theme,
+        );
+        self.draw_cursor(&viewport);
+        self.draw_hud(&viewport);
+        self.renderer.present().await;
+    }
+
+    fn get_viewport(&self) -> Viewport {
+        Viewport::fit(
+            self.renderer.get_screen_size(),
+            (self.constants.map_width, self.constants.map_height),
+            self.constants.grain_side_size,
+        )
+    }
+
+    pub fn set_cursor(&mut self, (x, y): (f32, f32)) {
+        self.cursor = self.get_viewport().screen_to_map(x, y);
+    }
+
+    pub fn set_pouring(&mut self, is_pouring: bool) {
+        self.is_pouring = is_pouring;
+    }
+
+    pub fn set_erasing(&mut self, is_erasing: bool) {
+        self.is_erasing = is_erasing;
+    }
+
+    pub fn do_key(&mut self, key: KeyCode) {
+        if let Some(family) = get_key_color(key) {
+            self.family = family;
+            return;
+        }
+
+        match key {
+            KeyCode::Equal => self.brush_radius = (self.brush_radius + 1).min(MAX_BRUSH_RADIUS),
+            KeyCode::Minus => self.brush_radius = (self.brush_radius - 1).max(0),
+            KeyCode::Up => self.pour_rate = (self.pour_rate * 2).min(MAX_POUR_RATE),
+            KeyCode::Down => self.pour_rate = (self.pour_rate / 2).max(1),
+            KeyCode::C => self.map_controller.clear(),
+            _ => {}
+        }
+    }
+
+    // Up to the pour rate of grains into random empty fields under the brush
+    fn pour(&mut self) {
+        let brush_grains = self.get_brush_grains();
+        let mut poured = 0;
+        for index in self.rng.get_random_row_order(brush_grains.len() as i32) {
+            if poured == self.pour_rate {
+                break;
+            }
+            let (x, y) = brush_grains[index as usize];
+            if self.map_controller.pour_grain(x, y, self.family) {
+                poured += 1;
+            }
+        }
+    }
+
+    fn get_brush_grains(&self) -> Vec<(i32, i32)> {
+        get_disc(self.cursor, self.brush_radius)
+    }
+
+    fn draw_cursor(&mut self, viewport: &Viewport) {
+        let color = self.constants.theme.get_display_color(self.family);
+        let grains = self.get_brush_grains();
+        self.renderer.draw_cursor(&grains, viewport, color);
+    }
+
+    fn get_hud_lines(&self) -> Vec<String> {
+        Vec::from([
+            format!("{} {}", SAND_TEXT, self.family.get_name()),
+            format!("{}:{}", SIZE_TEXT, self.brush_radius),
+            format!("{}:{}", POUR_RATE_TEXT, self.pour_rate),
+        ])
+    }
+
+    fn draw_hud(&mut self, viewport: &Viewport) {
+        let lines = self.get_hud_lines();
+        self.renderer
+            .draw_hud_lines(&lines, viewport, self.constants.theme);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        constants::{interface_constants::CURSOR_OPACITY, GameRules},
+        objects::theme::Theme,
+        renderers::recording_renderer::{DrawCommand, RecordingRenderer},
+        utils::{block_on::block_on, tetris_rng::MockTetrisRng},
+    };
+
+    use super::*;
+
+    const TEST_CONSTANTS: TetrisConstants = TetrisConstants {
+        map_width: 10,
+        map_height: 10,
+        block_chunk_side: 1,
+        grain_side_size: 1,
+        preview_block_chunk_side: 1,
+        block_starting_pos: (0, 0),
+        rules: GameRules::CLASSIC,
+        theme: &Theme::CLASSIC,
+    };
+
+    fn get_sandbox<'a>(
+        rng: &'a mut MockTetrisRng,
+        renderer: &'a mut RecordingRenderer,
+    ) -> SandboxController<'a, MockTetrisRng, RecordingRenderer> {
+        renderer.set_screen_size((10.0, 10.0)); // One pixel per grain
+        SandboxController::new(rng, renderer, &TEST_CONSTANTS)
+    }
+
+    fn count_grains(sandbox: &SandboxController<MockTetrisRng, RecordingRenderer>) -> usize {
+        sandbox.map_controller.get_layout().len()
+    }
+
+    #[test]
+    fn pours_at_pour_rate() {
+        let mut rng = MockTetrisRng::new();
+        let mut renderer = RecordingRenderer::new();
+        let mut sandbox = get_sandbox(&mut rng, &mut renderer);
+        sandbox.set_cursor((5.5, 2.5));
+        sandbox.set_pouring(true);
+
+        block_on(sandbox.tick());
+        assert_eq!(count_grains(&sandbox), DEFAULT_POUR_RATE);
+
+        sandbox.set_pouring(false);
+        block_on(sandbox.tick());
+        assert_eq!(count_grains(&sandbox), DEFAULT_POUR_RATE);
+    }
+
+    #[test]
+    fn pours_only_into_empty_fields() {
+        let mut rng = MockTetrisRng::new();
+        let mut renderer = RecordingRenderer::new();
+        let mut sandbox = get_sandbox(&mut rng, &mut renderer);
+        for _ in 0..DEFAULT_BRUSH_RADIUS {
+            sandbox.do_key(KeyCode::Minus);
+        }
+        sandbox.set_cursor((5.5, 9.5)); // Bottom row, the grain can't fall out of the way
+        sandbox.set_pouring(true);
+
+        for _ in 0..3 {
+            block_on(sandbox.tick());
+        }
+
+        assert_eq!(
+            sandbox.map_controller.get_layout(),
+            Vec::from([(5, 9, ColorFamily::Red)])
+        );
+    }
+
+    #[test]
+    fn poured_sand_falls() {
+        let mut rng = MockTetrisRng::new();
+        let mut renderer = RecordingRenderer::new();
+        let mut sandbox = get_sandbox(&mut rng, &mut renderer);
+        for _ in 0..DEFAULT_BRUSH_RADIUS {
+            sandbox.do_key(KeyCode::Minus);
+        }
+        sandbox.do_key(KeyCode::Key2);
+        sandbox.set_cursor((3.5, 0.5));
+        sandbox.set_pouring(true);
+        block_on(sandbox.tick());
+        sandbox.set_pouring(false);
+
+        for _ in 0..20 {
+            block_on(sandbox.tick());
+        }
+
+        assert_eq!(
+            sandbox.map_controller.get_layout(),
+            Vec::from([(3, 9, ColorFamily::Blue)])
+        );
+    }
+
+    #[test]
+    fn erasing_and_clearing() {
+        let mut rng = MockTetrisRng::new();
+        let mut renderer = RecordingRenderer::new();
+        let mut sandbox = get_sandbox(&mut rng, &mut renderer);
+        for _ in 1..DEFAULT_BRUSH_RADIUS {
+            sandbox.do_key(KeyCode::Minus); // Radius 1, a brush of 5 fields
+        }
+        sandbox.set_cursor((7.5, 2.5));
+        sandbox.set_pouring(true);
+        block_on(sandbox.tick());
+        sandbox.set_pouring(false);
+        assert_eq!(count_grains(&sandbox), 5);
+
+        sandbox.set_cursor((1.5, 1.5));
+        sandbox.set_erasing(true);
+        block_on(sandbox.tick());
+        assert_eq!(count_grains(&sandbox), 5); // Nothing under the brush
+
+        sandbox.set_cursor((7.5, 9.5));
+        for _ in 0..20 {
+            block_on(sandbox.tick());
+        }
+        assert_eq!(count_grains(&sandbox), 0); // The sand fell into the brush
+
+        sandbox.set_erasing(false);
+        sandbox.set_pouring(true);
+        block_on(sandbox.tick());
+        sandbox.do_key(KeyCode::C);
+        assert_eq!(count_grains(&sandbox), 0);
+    }
+
+    #[test]
+    fn pour_rate_and_brush_limits() {
+        let mut rng = MockTetrisRng::new();
+        let mut renderer = RecordingRenderer::new();
+        let mut sandbox = get_sandbox(&mut rng, &mut renderer);
+
+        for _ in 0..10 {
+            sandbox.do_key(KeyCode::Up);
+            sandbox.do_key(KeyCode::Equal);
+        }
+        assert_eq!(sandbox.pour_rate, MAX_POUR_RATE);
+        assert_eq!(sandbox.brush_radius, DEFAULT_BRUSH_RADIUS + 10);
+
+        for _ in 0..30 {
+            sandbox.do_key(KeyCode::Down);
+            sandbox.do_key(KeyCode::Minus);
+        }
+        assert_eq!(sandbox.pour_rate, 1);
+        assert_eq!(sandbox.brush_radius, 0);
+    }
+
+    #[test]
+    fn hud_lines() {
+        let mut rng = MockTetrisRng::new();
+        let mut renderer = RecordingRenderer::new();
+        let mut sandbox = get_sandbox(&mut rng, &mut renderer);
+
+        sandbox.do_key(KeyCode::Key8);
+        sandbox.do_key(KeyCode::Down);
+
+        assert_eq!(
+            sandbox.get_hud_lines(),
+            Vec::from(["Sand pink", "Size:4", "Rate:4"])
+        );
+    }
+
+    #[test]
+    fn tick_draws_cursor() {
+        let mut rng = MockTetrisRng::new();
+        let mut renderer = RecordingRenderer::new();
+        let mut sandbox = get_sandbox(&mut rng, &mut renderer);
+        sandbox.set_cursor((4.5, 4.5));
+
+        block_on(sandbox.tick());
+
+        let commands = renderer.get_commands();
+        assert_eq!(commands[0], DrawCommand::Background);
+        assert!(commands.contains(&DrawCommand::Overlay(
+            (4.0, 4.0, 1.0, 1.0),
+            Theme::CLASSIC.get_display_color(ColorFamily::Red),
+            CURSOR_OPACITY
+        )));
+    }
+}
@@ -0,0 +1,5 @@
+pub mod constants;
+pub mod controllers;
+pub mod objects;
+pub mod renderers;
+pub mod utils;
@@ -1,51 +1,125 @@
-mod constants;
-mod controllers;
-mod objects;
-mod utils;
-
-use constants::{
-    block_constants::{BLOCK_CHUNK_SIDE, BLOCK_STARTING_POS, PREVIEW_BLOCK_CHUNK_SIDE},
-    map_constants::{GRAIN_SIDE_SIZE, MAP_HEIGHT, MAP_WIDTH},
-    window_constants::{WINDOW_HEIGHT, WINDOW_TITLE, WINDOW_WIDTH},
-    TetrisConstants,
-};
-use controllers::game_controller::GameController;
 use macroquad::prelude::*;
-use utils::tetris_rng::ThreadTetrisRng;
+use rust_sand_tetris::{
+    constants::{
+        mode_constants::HIGH_SCORES_PATH,
+        window_constants::{WINDOW_HEIGHT, WINDOW_TITLE, WINDOW_WIDTH},
+        TetrisConstants,
+    },
+    controllers::{
+        editor_controller::EditorController, game_controller::GameController,
+        sandbox_controller::SandboxController,
+    },
+    objects::{high_scores::HighScores, piece_set::PieceSet, puzzle::Puzzle},
+    renderers::macroquad_renderer::MacroquadRenderer,
+    utils::{
+        clock::SystemClock,
+        launch_args::{parse_launch_args, GameSetup, LaunchArgs},
+        tetris_rng::{ScriptedTetrisRng, SeededTetrisRng, TetrisRng, ThreadTetrisRng},
+    },
+};
 
 fn window_conf() -> Conf {
     Conf {
         window_title: WINDOW_TITLE.to_string(),
         window_width: WINDOW_WIDTH as i32,
         window_height: WINDOW_HEIGHT as i32,
-        window_resizable: false,
+        window_resizable: true,
         ..Default::default() // Use default values for other settings
     }
 }
 
 #[macroquad::main(window_conf)]
 async fn main() {
-    let tetris_constants = TetrisConstants {
-        map_width: MAP_WIDTH,
-        map_height: MAP_HEIGHT,
-        block_chunk_side: BLOCK_CHUNK_SIDE,
-        grain_side_size: GRAIN_SIDE_SIZE,
-        preview_block_chunk_side: PREVIEW_BLOCK_CHUNK_SIDE,
-        block_starting_pos: BLOCK_STARTING_POS,
-    };
-    let mut rng: ThreadTetrisRng = ThreadTetrisRng::new();
-
-    let mut gc: GameController<ThreadTetrisRng> = GameController::new(&mut rng, &tetris_constants);
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let launch_args: LaunchArgs =
+        parse_launch_args(&args).unwrap_or_else(|message| exit_with(&message));
+    let GameSetup {
+        constants: tetris_constants,
+        piece_set,
+        puzzle,
+        script,
+        high_scores,
+    } = launch_args
+        .load_game_setup()
+        .unwrap_or_else(|message| exit_with(&message));
+    request_new_screen_size(
+        (tetris_constants.map_width * tetris_constants.grain_side_size) as f32,
+        (tetris_constants.map_height * tetris_constants.grain_side_size) as f32,
+    );
+
+    if let Some(path) = &launch_args.edit_path {
+        let puzzle = Puzzle::load_or_blank(path, &piece_set)
+            .and_then(|puzzle| tetris_constants.check_puzzle(&puzzle).map(|_| puzzle))
+            .unwrap_or_else(|message| exit_with(&message));
+        run_editor(&tetris_constants, piece_set, puzzle, path).await;
+        return;
+    }
+
+    if launch_args.is_sandbox {
+        match launch_args.seed {
+            Some(seed) => run_sandbox(&mut SeededTetrisRng::new(seed), &tetris_constants).await,
+            None => run_sandbox(&mut ThreadTetrisRng::new(), &tetris_constants).await,
+        }
+        return;
+    }
+
+    match launch_args.seed {
+        Some(seed) => {
+            run_game(
+                &mut ScriptedTetrisRng::new(SeededTetrisRng::new(seed), script),
+                &tetris_constants,
+                piece_set,
+                puzzle,
+                high_scores,
+            )
+            .await
+        }
+        None => {
+            run_game(
+                &mut ScriptedTetrisRng::new(ThreadTetrisRng::new(), script),
+                &tetris_constants,
+                piece_set,
+                puzzle,
+                high_scores,
+            )
+            .await
+        }
+    }
+}
+
+async fn run_game<R: TetrisRng>(
+    rng: &mut R,
+    tetris_constants: &TetrisConstants,
+    piece_set: PieceSet,
+    puzzle: Option<Puzzle>,
+    mut high_scores: HighScores,
+) {
+    let mut renderer: MacroquadRenderer = MacroquadRenderer::new();
+    let clock = SystemClock::new();
+    let mut gc: GameController<R, MacroquadRenderer, SystemClock> =
+        GameController::new(rng, &mut renderer, &clock, tetris_constants);
+    gc.set_piece_set(piece_set);
+    if let Some(puzzle) = puzzle {
+        gc.set_puzzle(puzzle);
+    }
+    gc.set_high_scores(high_scores.clone());
     gc.reset_game();
+    let mut is_fullscreen = false;
 
     loop {
         gc.tick().await;
-        for &key in &[KeyCode::D, KeyCode::A, KeyCode::S] {
+        if *gc.get_high_scores() != high_scores {
+            high_scores = gc.get_high_scores().clone();
+            high_scores
+                .save(HIGH_SCORES_PATH)
+                .unwrap_or_else(|message| eprintln!("{}", message));
+        }
+        for &key in &[KeyCode::D, KeyCode::A, KeyCode::S, KeyCode::W] {
             if is_key_down(key) {
                 gc.do_move(key);
             }
         }
-        for &key in &[KeyCode::E, KeyCode::Q] {
+        for &key in &[KeyCode::E, KeyCode::Q, KeyCode::Space] {
             if is_key_pressed(key) {
                 gc.do_move(key);
             }
@@ -53,9 +127,76 @@ async fn main() {
         if is_key_pressed(KeyCode::R) {
             gc.reset_game();
         }
+        if is_key_pressed(KeyCode::C) {
+            gc.toggle_cursor();
+        }
+        if is_key_pressed(KeyCode::F11) {
+            is_fullscreen = !is_fullscreen;
+            set_fullscreen(is_fullscreen);
+        }
     }
 }
 
+async fn run_editor(
+    tetris_constants: &TetrisConstants,
+    piece_set: PieceSet,
+    puzzle: Puzzle,
+    path: &str,
+) {
+    let mut renderer: MacroquadRenderer = MacroquadRenderer::new();
+    let mut editor: EditorController<MacroquadRenderer> =
+        EditorController::new(&mut renderer, tetris_constants, piece_set, puzzle, path);
+    let mut is_fullscreen = false;
+
+    loop {
+        editor.set_cursor(mouse_position());
+        if is_mouse_button_pressed(MouseButton::Left) {
+            editor.start_stroke();
+        } else if is_mouse_button_down(MouseButton::Left) {
+            editor.continue_stroke();
+        }
+        if is_mouse_button_released(MouseButton::Left) {
+            editor.end_stroke();
+        }
+        for key in get_keys_pressed() {
+            editor
+                .do_key(key)
+                .unwrap_or_else(|message| eprintln!("{}", message));
+        }
+        if is_key_pressed(KeyCode::F11) {
+            is_fullscreen = !is_fullscreen;
+            set_fullscreen(is_fullscreen);
+        }
+        editor.tick().await;
+    }
+}
+
+async fn run_sandbox<R: TetrisRng>(rng: &mut R, tetris_constants: &TetrisConstants) {
+    let mut renderer: MacroquadRenderer = MacroquadRenderer::new();
+    let mut sandbox: SandboxController<R, MacroquadRenderer> =
+        SandboxController::new(rng, &mut renderer, tetris_constants);
+    let mut is_fullscreen = false;
+
+    loop {
+        sandbox.set_cursor(mouse_position());
+        sandbox.set_pouring(is_mouse_button_down(MouseButton::Left));
+        sandbox.set_erasing(is_mouse_button_down(MouseButton::Right));
+        for key in get_keys_pressed() {
+            sandbox.do_key(key);
+        }
+        if is_key_pressed(KeyCode::F11) {
+            is_fullscreen = !is_fullscreen;
+            set_fullscreen(is_fullscreen);
+        }
+        sandbox.tick().await;
+    }
+}
+
+fn exit_with(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1);
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -72,5 +213,6 @@ mod test {
         assert_eq!(conf.window_title, WINDOW_TITLE);
         assert_eq!(conf.window_width, WINDOW_WIDTH as i32);
         assert_eq!(conf.window_height, WINDOW_HEIGHT as i32);
+        assert!(conf.window_resizable);
     }
 }
@@ -1,9 +1,9 @@
-use crate::constants::block_schemas;
+use crate::objects::piece_set::PieceDefinition;
 
 #[derive(Clone)]
 pub struct Block {
     schema: Vec<(i8, i8)>,
-    block_type: BlockType,
+    rotations: u8,
     rotation: u8,
 }
 
@@ -18,23 +18,25 @@ pub enum BlockType {
     TBlock,
 }
 
-const NOT_ROTABLE_TYPES: [BlockType; 1] = [BlockType::SquareBlock];
+// Order of the pieces in the classic piece set
+pub const CLASSIC_BLOCK_TYPES: [BlockType; 7] = [
+    BlockType::LBlock,
+    BlockType::RevLBlock,
+    BlockType::SquareBlock,
+    BlockType::ZBlock,
+    BlockType::RevZBlock,
+    BlockType::IBlock,
+    BlockType::TBlock,
+];
 
 impl Block {
-    pub fn new(block_type: BlockType) -> Block {
-        let schema = match block_type {
-            BlockType::LBlock => block_schemas::L_BLOCK,
-            BlockType::RevLBlock => block_schemas::REV_L_BLOCK,
-            BlockType::SquareBlock => block_schemas::SQUARE_BLOCK,
-            BlockType::ZBlock => block_schemas::Z_BLOCK,
-            BlockType::RevZBlock => block_schemas::REV_Z_BLOCK,
-            BlockType::IBlock => block_schemas::I_BLOCK,
-            BlockType::TBlock => block_schemas::T_BLOCK,
-        };
+    pub fn new(piece: impl Into<PieceDefinition>) -> Block {
+        let piece: PieceDefinition = piece.into();
+
         Block {
-            schema: Vec::from(schema),
+            schema: piece.get_cells().clone(),
+            rotations: piece.get_rotations(),
             rotation: 0,
-            block_type,
         }
     }
 
@@ -67,36 +69,30 @@ impl Block {
     }
 
     fn can_rotate(&self) -> bool {
-        !NOT_ROTABLE_TYPES.contains(&self.block_type)
+        self.rotations > 1
     }
 
     pub fn rotate_clockwise(&mut self) {
         if !self.can_rotate() {
-            return ();
+            return;
         }
 
-        if self.rotation == 3 {
-            self.rotation = 0;
-        } else {
-            self.rotation += 1;
-        }
+        self.rotation = (self.rotation + 1) % self.rotations;
     }
 
     pub fn rotate_counter_clockwise(&mut self) {
         if !self.can_rotate() {
-            return ();
+            return;
         }
 
-        if self.rotation == 0 {
-            self.rotation = 3;
-        } else {
-            self.rotation -= 1;
-        }
+        self.rotation = (self.rotation + self.rotations - 1) % self.rotations;
     }
 }
 
 #[cfg(test)]
 mod test {
+    use crate::constants::block_schemas;
+
     use super::*;
 
     #[test]
@@ -191,6 +187,39 @@ mod test {
         assert_eq!(lb.rotation, 0);
     }
 
+    #[test]
+    fn rotate_two_state_piece() {
+        let mut b: Block = Block::new(PieceDefinition::new(
+            "I3",
+            Vec::from([(-1, 0), (0, 0), (1, 0)]),
+            2,
+            1,
+        ));
+
+        b.rotate_clockwise();
+        assert_eq!(b.rotation, 1);
+        assert_eq!(b.get_schema(), Vec::from([(0, -1), (0, 0), (0, 1)]));
+
+        b.rotate_clockwise();
+        assert_eq!(b.rotation, 0);
+
+        b.rotate_counter_clockwise();
+        assert_eq!(b.rotation, 1);
+    }
+
+    #[test]
+    fn create_from_definition() {
+        let b: Block = Block::new(PieceDefinition::new(
+            "P",
+            Vec::from([(0, -1), (1, -1), (0, 0), (1, 0), (0, 1)]),
+            4,
+            1,
+        ));
+
+        assert_eq!(b.get_schema().len(), 5);
+        assert!(b.can_rotate());
+    }
+
     #[test]
     fn rotate_90_lblock() {
         let mut lb: Block = Block::new(BlockType::LBlock);
@@ -0,0 +1,252 @@
+// Which group a grain belongs to as far as the rules are concerned, themes decide what it looks like
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorFamily {
+    Red,
+    Blue,
+    Green,
+    Yellow,
+    Purple,
+    Orange,
+    Cyan,
+    Pink,
+}
+
+impl ColorFamily {
+    pub const COUNT: usize = 8;
+    pub const ALL: [ColorFamily; ColorFamily::COUNT] = [
+        ColorFamily::Red,
+        ColorFamily::Blue,
+        ColorFamily::Green,
+        ColorFamily::Yellow,
+        ColorFamily::Purple,
+        ColorFamily::Orange,
+        ColorFamily::Cyan,
+        ColorFamily::Pink,
+    ];
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            ColorFamily::Red => "red",
+            ColorFamily::Blue => "blue",
+            ColorFamily::Green => "green",
+            ColorFamily::Yellow => "yellow",
+            ColorFamily::Purple => "purple",
+            ColorFamily::Orange => "orange",
+            ColorFamily::Cyan => "cyan",
+            ColorFamily::Pink => "pink",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<ColorFamily> {
+        ColorFamily::ALL
+            .into_iter()
+            .find(|family| family.get_name() == name)
+    }
+
+    // One character per grain in board files, pink takes 'k' since purple has 'p'
+    pub fn get_symbol(&self) -> char {
+        match self {
+            ColorFamily::Red => 'r',
+            ColorFamily::Blue => 'b',
+            ColorFamily::Green => 'g',
+            ColorFamily::Yellow => 'y',
+            ColorFamily::Purple => 'p',
+            ColorFamily::Orange => 'o',
+            ColorFamily::Cyan => 'c',
+            ColorFamily::Pink => 'k',
+        }
+    }
+
+    pub fn from_symbol(symbol: char) -> Option<ColorFamily> {
+        ColorFamily::ALL
+            .into_iter()
+            .find(|family| family.get_symbol() == symbol)
+    }
+}
+
+// A grain's family and which shade of the family's palette it is drawn with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GrainColor {
+    family: ColorFamily,
+    shade: usize,
+}
+
+impl GrainColor {
+    pub const fn new(family: ColorFamily, shade: usize) -> Self {
+        GrainColor { family, shade }
+    }
+
+    pub fn get_family(&self) -> ColorFamily {
+        self.family
+    }
+
+    pub fn get_shade(&self) -> usize {
+        self.shade
+    }
+}
+
+// How often blocks spawn with each family, in the order of ColorFamily::ALL. Families with no weight are left out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorWeights {
+    weights: [u32; ColorFamily::COUNT],
+}
+
+impl ColorWeights {
+    pub const MIN_FAMILIES: usize = 2;
+    pub const CLASSIC: ColorWeights = ColorWeights {
+        weights: [1, 1, 1, 2, 0, 0, 0, 0],
+    };
+
+    pub fn new(weights: [u32; ColorFamily::COUNT]) -> Result<ColorWeights, String> {
+        let color_weights = ColorWeights { weights };
+        let family_count = color_weights.get_families().len();
+        if family_count < ColorWeights::MIN_FAMILIES {
+            return Err(format!(
+                "At least {} colors have to be in play, got {}",
+                ColorWeights::MIN_FAMILIES,
+                family_count
+            ));
+        }
+
+        Ok(color_weights)
+    }
+
+    // The first `count` families, all equally likely
+    pub fn evenly(count: usize) -> Result<ColorWeights, String> {
+        if count > ColorFamily::COUNT {
+            return Err(format!(
+                "At most {} colors can be in play, got {}",
+                ColorFamily::COUNT,
+                count
+            ));
+        }
+        let mut weights = [0; ColorFamily::COUNT];
+        weights[..count].fill(1);
+
+        ColorWeights::new(weights)
+    }
+
+    // Either a number of colors or a list like "red,blue:2,cyan"
+    pub fn parse(text: &str) -> Result<ColorWeights, String> {
+        if let Ok(count) = text.parse() {
+            return ColorWeights::evenly(count);
+        }
+
+        let mut weights = [0; ColorFamily::COUNT];
+        for entry in text.split(',') {
+            let (name, weight) = match entry.split_once(':') {
+                Some((name, weight)) => (
+                    name,
+                    weight
+                        .parse()
+                        .ok()
+                        .filter(|weight| *weight > 0)
+                        .ok_or(format!("Invalid color weight: {}", entry))?,
+                ),
+                None => (entry, 1),
+            };
+            let family =
+                ColorFamily::from_name(name).ok_or(format!("Unknown color family: {}", name))?;
+            if weights[family as usize] > 0 {
+                return Err(format!("Color family listed twice: {}", name));
+            }
+            weights[family as usize] = weight;
+        }
+
+        ColorWeights::new(weights)
+    }
+
+    pub fn get_weights(&self) -> &[u32] {
+        &self.weights
+    }
+
+    pub fn get_families(&self) -> Vec<ColorFamily> {
+        ColorFamily::ALL
+            .into_iter()
+            .filter(|family| self.weights[*family as usize] > 0)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn from_name() {
+        for family in ColorFamily::ALL {
+            assert_eq!(ColorFamily::from_name(family.get_name()), Some(family));
+        }
+        assert_eq!(ColorFamily::from_name("violet"), None);
+    }
+
+    #[test]
+    fn from_symbol() {
+        for family in ColorFamily::ALL {
+            assert_eq!(ColorFamily::from_symbol(family.get_symbol()), Some(family));
+        }
+        assert_eq!(ColorFamily::from_symbol('.'), None);
+    }
+
+    #[test]
+    fn color_weights_classic() {
+        assert_eq!(
+            ColorWeights::CLASSIC.get_families(),
+            Vec::from([
+                ColorFamily::Red,
+                ColorFamily::Blue,
+                ColorFamily::Green,
+                ColorFamily::Yellow
+            ])
+        );
+        assert_eq!(ColorWeights::CLASSIC.get_weights()[3], 2);
+    }
+
+    #[test]
+    fn color_weights_evenly() {
+        let color_weights = ColorWeights::evenly(6).unwrap();
+
+        assert_eq!(color_weights.get_weights(), &[1, 1, 1, 1, 1, 1, 0, 0]);
+        assert!(ColorWeights::evenly(1).is_err());
+        assert!(ColorWeights::evenly(9).is_err());
+    }
+
+    #[test]
+    fn color_weights_parse() {
+        assert_eq!(
+            ColorWeights::parse("8").unwrap(),
+            ColorWeights::evenly(8).unwrap()
+        );
+
+        let color_weights = ColorWeights::parse("pink,blue:3").unwrap();
+
+        assert_eq!(
+            color_weights.get_families(),
+            Vec::from([ColorFamily::Blue, ColorFamily::Pink])
+        );
+        assert_eq!(color_weights.get_weights(), &[0, 3, 0, 0, 0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn color_weights_parse_invalid() {
+        for text in [
+            "red",
+            "red,red",
+            "red,brown",
+            "red,blue:0",
+            "red,blue:x",
+            "0",
+            "",
+        ] {
+            assert!(ColorWeights::parse(text).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn create_grain_color() {
+        let grain_color = GrainColor::new(ColorFamily::Green, 2);
+
+        assert_eq!(grain_color.get_family(), ColorFamily::Green);
+        assert_eq!(grain_color.get_shade(), 2);
+    }
+}
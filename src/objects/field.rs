@@ -1,31 +1,33 @@
-use macroquad::color::Color;
-
-use crate::constants::colors::BACKGROUND_COLOR;
+use crate::objects::color_family::{ColorFamily, GrainColor};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Field {
     x: i32,
     y: i32,
-    color: Color,
+    color: Option<GrainColor>, // None for empty fields
     group_id: u32,
+    momentum: i32, // Sideways cells left to travel, the sign gives the direction
+    velocity: f32, // Cells fallen per tick, 0 while resting
 }
 
 impl Field {
-    pub fn new(x: i32, y: i32, color: Color, group_id: u32) -> Self {
+    pub fn new(x: i32, y: i32, color: Option<GrainColor>, group_id: u32) -> Self {
         Field {
             x,
             y,
             color,
             group_id,
+            momentum: 0,
+            velocity: 0.0,
         }
     }
 
     pub fn do_draw(&self) -> bool {
-        self.color != BACKGROUND_COLOR
+        self.color.is_some()
     }
 
     pub fn is_empty(&self) -> bool {
-        self.color == BACKGROUND_COLOR
+        self.color.is_none()
     }
 
     pub fn get_x(&self) -> i32 {
@@ -36,11 +38,15 @@ impl Field {
         self.y
     }
 
-    pub fn get_color(&self) -> Color {
+    pub fn get_color(&self) -> Option<GrainColor> {
         self.color
     }
 
-    pub fn set_color(&mut self, new_color: Color) {
+    pub fn get_family(&self) -> Option<ColorFamily> {
+        self.color.map(|color| color.get_family())
+    }
+
+    pub fn set_color(&mut self, new_color: Option<GrainColor>) {
         self.color = new_color;
     }
 
@@ -51,14 +57,30 @@ impl Field {
     pub fn set_group_id(&mut self, new_group_id: u32) {
         self.group_id = new_group_id;
     }
+
+    pub fn get_momentum(&self) -> i32 {
+        self.momentum
+    }
+
+    pub fn set_momentum(&mut self, new_momentum: i32) {
+        self.momentum = new_momentum;
+    }
+
+    pub fn get_velocity(&self) -> f32 {
+        self.velocity
+    }
+
+    pub fn set_velocity(&mut self, new_velocity: f32) {
+        self.velocity = new_velocity;
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use macroquad::color::RED;
-
     use super::*;
 
+    const RED: Option<GrainColor> = Some(GrainColor::new(ColorFamily::Red, 1));
+
     #[test]
     fn create_field() {
         let field: Field = Field::new(1, 2, RED, 0);
@@ -66,12 +88,15 @@ mod test {
         assert_eq!(field.get_x(), 1);
         assert_eq!(field.get_y(), 2);
         assert_eq!(field.get_color(), RED);
+        assert_eq!(field.get_family(), Some(ColorFamily::Red));
         assert_eq!(field.get_group_id(), 0);
+        assert_eq!(field.get_momentum(), 0);
+        assert_eq!(field.get_velocity(), 0.0);
     }
 
     #[test]
     fn do_draw() {
-        let field_dont: Field = Field::new(1, 2, BACKGROUND_COLOR, 0);
+        let field_dont: Field = Field::new(1, 2, None, 0);
         let field_do: Field = Field::new(1, 2, RED, 0);
 
         assert_eq!(field_dont.do_draw(), false);
@@ -80,7 +105,7 @@ mod test {
 
     #[test]
     fn is_empty() {
-        let field_empty: Field = Field::new(1, 2, BACKGROUND_COLOR, 0);
+        let field_empty: Field = Field::new(1, 2, None, 0);
         let field_not_empty: Field = Field::new(1, 2, RED, 0);
 
         assert_eq!(field_empty.is_empty(), true);
@@ -89,7 +114,7 @@ mod test {
 
     #[test]
     fn set_color() {
-        let mut field: Field = Field::new(1, 2, BACKGROUND_COLOR, 0);
+        let mut field: Field = Field::new(1, 2, None, 0);
 
         field.set_color(RED);
 
@@ -98,17 +123,35 @@ mod test {
 
     #[test]
     fn set_group_id() {
-        let mut field: Field = Field::new(1, 2, BACKGROUND_COLOR, 0);
+        let mut field: Field = Field::new(1, 2, None, 0);
 
         field.set_group_id(1);
 
         assert_eq!(field.get_group_id(), 1);
     }
 
+    #[test]
+    fn set_momentum() {
+        let mut field: Field = Field::new(1, 2, RED, 0);
+
+        field.set_momentum(-3);
+
+        assert_eq!(field.get_momentum(), -3);
+    }
+
+    #[test]
+    fn set_velocity() {
+        let mut field: Field = Field::new(1, 2, RED, 0);
+
+        field.set_velocity(1.5);
+
+        assert_eq!(field.get_velocity(), 1.5);
+    }
+
     #[test]
     fn partial_eq() {
-        let field1: Field = Field::new(1, 2, BACKGROUND_COLOR, 0);
-        let field2: Field = Field::new(1, 2, BACKGROUND_COLOR, 0);
+        let field1: Field = Field::new(1, 2, None, 0);
+        let field2: Field = Field::new(1, 2, None, 0);
         let field3: Field = Field::new(1, 2, RED, 0);
 
         assert!(field1 == field2);
@@ -0,0 +1,191 @@
+use std::time::Duration;
+
+use crate::{
+    constants::{
+        lock_delay_constants::{FAST_LOCK_DELAY_TICKS, LOCK_DELAY_TICKS, ZEN_LOCK_DELAY_TICKS},
+        mode_constants::{
+            LEVELS_PER_FALL_STEP, MARATHON_GRAINS_PER_LEVEL, MARATHON_MAX_LEVEL, SPRINT_TARGET,
+            ULTRA_TIME_LIMIT,
+        },
+    },
+    objects::high_scores::Record,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameMode {
+    Endless,
+    Marathon,                       // Speeds up every level, done after the last one
+    Sprint { target: u32 },         // Clear that many grains as fast as possible
+    Ultra { time_limit: Duration }, // As many points as possible before the time runs out
+    Zen,                            // Topping out erases the upper rows instead of ending the game
+    Puzzle, // Played on a board from a puzzle file, its goal is checked by the game
+}
+
+impl GameMode {
+    // Modes picked by name, a puzzle needs a board so it is started with its file instead
+    pub const ALL: [GameMode; 5] = [
+        GameMode::Endless,
+        GameMode::Marathon,
+        GameMode::Sprint {
+            target: SPRINT_TARGET,
+        },
+        GameMode::Ultra {
+            time_limit: ULTRA_TIME_LIMIT,
+        },
+        GameMode::Zen,
+    ];
+
+    // Also the category its high scores are kept under
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            GameMode::Endless => "endless",
+            GameMode::Marathon => "marathon",
+            GameMode::Sprint { .. } => "sprint",
+            GameMode::Ultra { .. } => "ultra",
+            GameMode::Zen => "zen",
+            GameMode::Puzzle => "puzzle",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<GameMode> {
+        GameMode::ALL
+            .into_iter()
+            .find(|mode| mode.get_name() == name)
+    }
+
+    pub fn get_level(&self, cleared_grains: u32) -> u32 {
+        match self {
+            GameMode::Marathon => {
+                (cleared_grains / MARATHON_GRAINS_PER_LEVEL + 1).min(MARATHON_MAX_LEVEL)
+            }
+            _ => 1,
+        }
+    }
+
+    // How many grains the block falls every tick
+    pub fn get_fall_steps(&self, cleared_grains: u32) -> u32 {
+        (self.get_level(cleared_grains) - 1) / LEVELS_PER_FALL_STEP + 1
+    }
+
+    // How long a block rests on sand before settling, unless the launch args set it
+    pub fn get_lock_delay_ticks(&self) -> u32 {
+        match self {
+            GameMode::Endless | GameMode::Puzzle => LOCK_DELAY_TICKS,
+            GameMode::Marathon | GameMode::Sprint { .. } | GameMode::Ultra { .. } => {
+                FAST_LOCK_DELAY_TICKS
+            }
+            GameMode::Zen => ZEN_LOCK_DELAY_TICKS,
+        }
+    }
+
+    pub fn is_goal_reached(&self, cleared_grains: u32, elapsed: Duration) -> bool {
+        match self {
+            GameMode::Endless | GameMode::Zen | GameMode::Puzzle => false,
+            GameMode::Marathon => cleared_grains >= MARATHON_GRAINS_PER_LEVEL * MARATHON_MAX_LEVEL,
+            GameMode::Sprint { target } => cleared_grains >= *target,
+            GameMode::Ultra { time_limit } => elapsed >= *time_limit,
+        }
+    }
+
+    // What goes on the high score table once the game ends, a sprint only counts when finished
+    pub fn get_record(
+        &self,
+        score: u32,
+        elapsed: Duration,
+        is_goal_reached: bool,
+    ) -> Option<Record> {
+        match self {
+            GameMode::Sprint { .. } if is_goal_reached => Some(Record::Time(elapsed)),
+            GameMode::Sprint { .. } | GameMode::Zen | GameMode::Puzzle => None,
+            _ if score > 0 => Some(Record::Score(score)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SPRINT: GameMode = GameMode::Sprint { target: 100 };
+    const ULTRA: GameMode = GameMode::Ultra {
+        time_limit: Duration::from_secs(10),
+    };
+
+    #[test]
+    fn from_name() {
+        for mode in GameMode::ALL {
+            assert_eq!(GameMode::from_name(mode.get_name()), Some(mode));
+        }
+        assert_eq!(GameMode::from_name("blitz"), None);
+        assert_eq!(GameMode::from_name(GameMode::Puzzle.get_name()), None);
+    }
+
+    #[test]
+    fn get_level() {
+        assert_eq!(GameMode::Marathon.get_level(0), 1);
+        assert_eq!(
+            GameMode::Marathon.get_level(MARATHON_GRAINS_PER_LEVEL * 2),
+            3
+        );
+        assert_eq!(GameMode::Marathon.get_level(u32::MAX), MARATHON_MAX_LEVEL);
+        assert_eq!(GameMode::Endless.get_level(u32::MAX), 1);
+    }
+
+    #[test]
+    fn get_fall_steps() {
+        assert_eq!(GameMode::Marathon.get_fall_steps(0), 1);
+        assert_eq!(
+            GameMode::Marathon.get_fall_steps(MARATHON_GRAINS_PER_LEVEL * LEVELS_PER_FALL_STEP),
+            2
+        );
+        assert_eq!(GameMode::Endless.get_fall_steps(u32::MAX), 1);
+    }
+
+    #[test]
+    fn get_lock_delay_ticks() {
+        assert_eq!(GameMode::Endless.get_lock_delay_ticks(), LOCK_DELAY_TICKS);
+        assert_eq!(
+            GameMode::Marathon.get_lock_delay_ticks(),
+            FAST_LOCK_DELAY_TICKS
+        );
+        assert_eq!(SPRINT.get_lock_delay_ticks(), FAST_LOCK_DELAY_TICKS);
+        assert_eq!(ULTRA.get_lock_delay_ticks(), FAST_LOCK_DELAY_TICKS);
+        assert_eq!(GameMode::Zen.get_lock_delay_ticks(), ZEN_LOCK_DELAY_TICKS);
+        assert_eq!(GameMode::Puzzle.get_lock_delay_ticks(), LOCK_DELAY_TICKS);
+    }
+
+    #[test]
+    fn is_goal_reached() {
+        let second = Duration::from_secs(1);
+
+        assert!(!GameMode::Endless.is_goal_reached(u32::MAX, Duration::MAX));
+        assert!(!GameMode::Marathon
+            .is_goal_reached(MARATHON_GRAINS_PER_LEVEL * MARATHON_MAX_LEVEL - 1, second));
+        assert!(GameMode::Marathon
+            .is_goal_reached(MARATHON_GRAINS_PER_LEVEL * MARATHON_MAX_LEVEL, second));
+        assert!(!SPRINT.is_goal_reached(99, second));
+        assert!(SPRINT.is_goal_reached(100, second));
+        assert!(!ULTRA.is_goal_reached(0, 9 * second));
+        assert!(ULTRA.is_goal_reached(0, 10 * second));
+    }
+
+    #[test]
+    fn get_record() {
+        let elapsed = Duration::from_secs(42);
+
+        assert_eq!(
+            GameMode::Marathon.get_record(120, elapsed, false),
+            Some(Record::Score(120))
+        );
+        assert_eq!(ULTRA.get_record(80, elapsed, true), Some(Record::Score(80)));
+        assert_eq!(
+            SPRINT.get_record(100, elapsed, true),
+            Some(Record::Time(elapsed))
+        );
+        assert_eq!(SPRINT.get_record(60, elapsed, false), None);
+        assert_eq!(GameMode::Endless.get_record(0, elapsed, false), None);
+        assert_eq!(GameMode::Zen.get_record(500, elapsed, false), None);
+        assert_eq!(GameMode::Puzzle.get_record(500, elapsed, true), None);
+    }
+}
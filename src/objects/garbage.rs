@@ -0,0 +1,17 @@
+use std::time::Duration;
+
+use crate::constants::garbage_constants::{GARBAGE_INTERVAL, GARBAGE_ROWS};
+
+// Sand pushed up from the bottom every so often
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Garbage {
+    pub interval: Duration,
+    pub rows: i32, // In grains
+}
+
+impl Garbage {
+    pub const CLASSIC: Garbage = Garbage {
+        interval: GARBAGE_INTERVAL,
+        rows: GARBAGE_ROWS,
+    };
+}
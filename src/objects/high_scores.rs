@@ -0,0 +1,193 @@
+use std::{fs, io::ErrorKind, time::Duration};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Record {
+    Score(u32),     // Higher is better
+    Time(Duration), // Lower is better
+}
+
+impl Record {
+    pub fn is_better_than(&self, other: &Record) -> bool {
+        match (self, other) {
+            (Record::Score(score), Record::Score(other_score)) => score > other_score,
+            (Record::Time(time), Record::Time(other_time)) => time < other_time,
+            _ => true, // The kind of record changed, the new one replaces the old one
+        }
+    }
+
+    pub fn get_text(&self) -> String {
+        match self {
+            Record::Score(score) => score.to_string(),
+            Record::Time(time) => format_time(*time),
+        }
+    }
+}
+
+// m:ss.cc
+pub fn format_time(time: Duration) -> String {
+    let centiseconds = time.as_millis() / 10;
+
+    format!(
+        "{}:{:02}.{:02}",
+        centiseconds / 6000,
+        centiseconds / 100 % 60,
+        centiseconds % 100
+    )
+}
+
+// Best result of every game mode, by the mode's name
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HighScores {
+    records: Vec<(String, Record)>,
+}
+
+impl HighScores {
+    pub fn new() -> Self {
+        HighScores {
+            records: Vec::new(),
+        }
+    }
+
+    pub fn get_record(&self, category: &str) -> Option<Record> {
+        self.records
+            .iter()
+            .find(|(name, _)| name == category)
+            .map(|(_, record)| *record)
+    }
+
+    // True when the record beats the best one so far
+    pub fn submit(&mut self, category: &str, record: Record) -> bool {
+        match self.records.iter_mut().find(|(name, _)| name == category) {
+            Some((_, best)) if record.is_better_than(best) => *best = record,
+            Some(_) => return false,
+            None => self.records.push((category.to_string(), record)),
+        }
+
+        true
+    }
+
+    // Nothing has been played yet when the file is missing
+    pub fn load(path: &str) -> Result<Self, String> {
+        match fs::read_to_string(path) {
+            Ok(text) => {
+                HighScores::parse(&text).map_err(|message| format!("{}: {}", path, message))
+            }
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(HighScores::new()),
+            Err(error) => Err(format!("{}: {}", path, error)),
+        }
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        fs::write(path, self.to_text()).map_err(|error| format!("{}: {}", path, error))
+    }
+
+    /*
+        marathon score 1200    <- category, kind of record and its value
+        sprint time 83.52      <- times are in seconds
+    */
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut high_scores = HighScores::new();
+
+        for (line_index, line) in text.lines().enumerate() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let record = match words.as_slice() {
+                [] => continue,
+                [_, "score", value] => value.parse().ok().map(Record::Score),
+                [_, "time", value] => value
+                    .parse()
+                    .ok()
+                    .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+                    .map(Record::Time),
+                _ => None,
+            };
+            match record {
+                Some(record) => high_scores.submit(words[0], record),
+                None => return Err(format!("line {}: invalid record", line_index + 1)),
+            };
+        }
+
+        Ok(high_scores)
+    }
+
+    pub fn to_text(&self) -> String {
+        self.records
+            .iter()
+            .map(|(category, record)| match record {
+                Record::Score(score) => format!("{} score {}\n", category, score),
+                Record::Time(time) => format!("{} time {:.2}\n", category, time.as_secs_f64()),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn is_better_than() {
+        assert!(Record::Score(10).is_better_than(&Record::Score(5)));
+        assert!(!Record::Score(5).is_better_than(&Record::Score(5)));
+        assert!(Record::Time(Duration::from_secs(5))
+            .is_better_than(&Record::Time(Duration::from_secs(10))));
+        assert!(!Record::Time(Duration::from_secs(10))
+            .is_better_than(&Record::Time(Duration::from_secs(5))));
+    }
+
+    #[test]
+    fn format_time() {
+        assert_eq!(super::format_time(Duration::from_millis(83_527)), "1:23.52");
+        assert_eq!(super::format_time(Duration::ZERO), "0:00.00");
+    }
+
+    #[test]
+    fn submit() {
+        let mut high_scores = HighScores::new();
+
+        assert!(high_scores.submit("marathon", Record::Score(10)));
+        assert!(!high_scores.submit("marathon", Record::Score(8)));
+        assert!(high_scores.submit("marathon", Record::Score(12)));
+        assert!(high_scores.submit("ultra", Record::Score(3)));
+
+        assert_eq!(high_scores.get_record("marathon"), Some(Record::Score(12)));
+        assert_eq!(high_scores.get_record("ultra"), Some(Record::Score(3)));
+        assert_eq!(high_scores.get_record("sprint"), None);
+    }
+
+    #[test]
+    fn parse_and_to_text() {
+        let text = "marathon score 1200\nsprint time 83.52\n";
+
+        let high_scores = HighScores::parse(text).unwrap();
+
+        assert_eq!(
+            high_scores.get_record("marathon"),
+            Some(Record::Score(1200))
+        );
+        assert_eq!(
+            high_scores.get_record("sprint"),
+            Some(Record::Time(Duration::from_secs_f64(83.52)))
+        );
+        assert_eq!(high_scores.to_text(), text);
+    }
+
+    #[test]
+    fn parse_invalid() {
+        for text in [
+            "marathon",
+            "marathon score",
+            "sprint time -1",
+            "ultra lines 4",
+        ] {
+            assert_eq!(
+                HighScores::parse(text),
+                Err("line 1: invalid record".to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn load_missing_file() {
+        assert_eq!(HighScores::load("missing.txt"), Ok(HighScores::new()));
+    }
+}
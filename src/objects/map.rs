@@ -1,15 +1,17 @@
-use macroquad::color::Color;
 use std::collections::VecDeque;
 
 use crate::{
-    constants::colors::BACKGROUND_COLOR, controllers::graphic_controller::GraphicController,
-    objects::field::Field, utils::tetris_rng::TetrisRng,
+    constants::physics_constants::{GRAIN_GRAVITY, GRAIN_START_VELOCITY, GRAIN_TERMINAL_VELOCITY},
+    objects::{color_family::GrainColor, field::Field, matching_rules::MatchingRules},
+    utils::tetris_rng::TetrisRng,
 };
 
 pub struct Map {
     width: i32,
     height: i32,
     grid: Vec<Vec<Field>>,
+    matching_rules: MatchingRules,
+    is_still: bool, // No grain moved during the last tick
 }
 
 impl Map {
@@ -20,9 +22,15 @@ impl Map {
             width,
             height,
             grid,
+            matching_rules: MatchingRules::CLASSIC,
+            is_still: true,
         }
     }
 
+    pub fn set_matching_rules(&mut self, matching_rules: MatchingRules) {
+        self.matching_rules = matching_rules;
+    }
+
     pub fn get_width(&self) -> i32 {
         self.width
     }
@@ -36,7 +44,7 @@ impl Map {
         for y in 0..height {
             grid.push(Vec::new());
             for x in 0..width {
-                grid[y as usize].push(Field::new(x, y, BACKGROUND_COLOR, 0));
+                grid[y as usize].push(Field::new(x, y, None, 0));
             }
         }
         grid
@@ -56,12 +64,41 @@ impl Map {
         Some(self.get_field(x, y).unwrap().get_group_id())
     }
 
-    pub fn change_field(&mut self, x: i32, y: i32, new_color: Color, new_group_id: u32) {
+    pub fn change_field(
+        &mut self,
+        x: i32,
+        y: i32,
+        new_color: Option<GrainColor>,
+        new_group_id: u32,
+    ) {
         if !self.check_coords_in_bounds(x, y) {
             return ();
         }
         self.grid[y as usize][x as usize].set_color(new_color);
         self.grid[y as usize][x as usize].set_group_id(new_group_id);
+        self.grid[y as usize][x as usize].set_momentum(0);
+        self.grid[y as usize][x as usize].set_velocity(0.0);
+    }
+
+    pub fn set_field_momentum(&mut self, x: i32, y: i32, new_momentum: i32) {
+        if !self.check_coords_in_bounds(x, y) {
+            return;
+        }
+        self.grid[y as usize][x as usize].set_momentum(new_momentum);
+    }
+
+    pub fn set_field_group_id(&mut self, x: i32, y: i32, new_group_id: u32) {
+        if !self.check_coords_in_bounds(x, y) {
+            return;
+        }
+        self.grid[y as usize][x as usize].set_group_id(new_group_id);
+    }
+
+    pub fn set_field_velocity(&mut self, x: i32, y: i32, new_velocity: f32) {
+        if !self.check_coords_in_bounds(x, y) {
+            return;
+        }
+        self.grid[y as usize][x as usize].set_velocity(new_velocity);
     }
 
     fn check_coords_in_bounds(&self, x: i32, y: i32) -> bool {
@@ -85,19 +122,40 @@ impl Map {
         output
     }
 
+    pub fn is_still(&self) -> bool {
+        self.is_still
+    }
+
     pub fn tick_and_get_score_fields(&mut self, rng: &mut impl TetrisRng) -> Vec<(i32, i32)> {
+        self.is_still = true;
         for (x, y) in self.get_fields_coords_bottom_up(rng) {
             if !self.get_field(x, y).unwrap().is_empty() {
                 let (new_x, new_y) = self.get_new_pos(x, y, rng);
-                if (new_x, new_y) != (x, y) {
+                if (new_x, new_y) == (x, y) {
+                    // Grain came to rest
+                    self.set_field_momentum(x, y, 0);
+                    self.set_field_velocity(x, y, 0.0);
+                } else {
+                    self.is_still = false;
                     let old_field_pos = self.get_field(x, y).unwrap();
                     let field_color = old_field_pos.get_color();
                     let group_id = old_field_pos.get_group_id();
+                    let momentum = old_field_pos.get_momentum();
+                    let velocity = self.get_fall_velocity(x, y);
+                    let cells_fallen = new_y - y; // Every step of the path goes one row down
 
                     let new_group_id = self.get_new_group((new_x, new_y), (x, y), group_id);
 
                     self.change_field(new_x, new_y, field_color, new_group_id);
-                    self.change_field(x, y, BACKGROUND_COLOR, 0);
+                    self.set_field_momentum(
+                        new_x,
+                        new_y,
+                        Map::decay_momentum(momentum, cells_fallen),
+                    );
+                    if cells_fallen == velocity as i32 {
+                        self.set_field_velocity(new_x, new_y, velocity);
+                    } // Otherwise the grain hit something along the way and lost its speed
+                    self.change_field(x, y, None, 0);
 
                     let groups = Vec::from([new_group_id, group_id]); // Combine them because not every group from the block has yet been converted
 
@@ -154,11 +212,16 @@ impl Map {
         let field = self.get_field(pos.0, pos.1).unwrap();
         let neighbour_field = self.get_field(neighbour_pos.0, neighbour_pos.1).unwrap();
 
+        let do_families_match = match (field.get_family(), neighbour_field.get_family()) {
+            (Some(family), Some(other_family)) => {
+                self.matching_rules.do_colors_match(family, other_family)
+            }
+            _ => false,
+        };
+
         neighbour_field.get_group_id() != 0                                         // Field has a group
             && field.get_group_id() != neighbour_field.get_group_id()               // The group of neighbour is not the same
-            && GraphicController::normalize_color(field.get_color())
-                == GraphicController::normalize_color(neighbour_field.get_color())
-        // The color family is the same
+            && do_families_match // The color families are compatible
     }
 
     fn change_group_bfs(&mut self, x: i32, y: i32, new_group_id: u32) {
@@ -226,20 +289,13 @@ impl Map {
     }
 
     fn get_field_neighbors(&self, x: i32, y: i32) -> Vec<(i32, i32)> {
-        let output: Vec<(i32, i32)> = [
-            (-1, 0),
-            (1, 0),
-            (0, -1),
-            (0, 1),
-            (-1, 1),
-            (1, -1),
-            (-1, -1),
-            (1, 1),
-        ]
-        .iter()
-        .filter(|(n_x, n_y)| self.check_coords_in_bounds(x + n_x, y + n_y))
-        .map(|(n_x, n_y)| (x + n_x, y + n_y))
-        .collect();
+        let output: Vec<(i32, i32)> = self
+            .matching_rules
+            .get_neighbour_offsets()
+            .iter()
+            .filter(|(n_x, n_y)| self.check_coords_in_bounds(x + n_x, y + n_y))
+            .map(|(n_x, n_y)| (x + n_x, y + n_y))
+            .collect();
 
         output
     }
@@ -256,7 +312,45 @@ impl Map {
         output
     }
 
+    fn get_fall_velocity(&self, x: i32, y: i32) -> f32 {
+        let velocity = self.get_field(x, y).unwrap().get_velocity();
+
+        (velocity + GRAIN_GRAVITY).clamp(GRAIN_START_VELOCITY, GRAIN_TERMINAL_VELOCITY)
+    }
+
+    fn decay_momentum(momentum: i32, cells_travelled: i32) -> i32 {
+        momentum.signum() * (momentum.abs() - cells_travelled).max(0)
+    }
+
     fn get_new_pos(&self, x: i32, y: i32, rng: &mut impl TetrisRng) -> (i32, i32) {
+        let momentum = self.get_field(x, y).unwrap().get_momentum();
+        let mut pos = (x, y);
+
+        // Follow the path cell by cell, so fast grains stop at the first obstacle
+        for step in 0..self.get_fall_velocity(x, y) as i32 {
+            let next_pos =
+                self.get_next_pos(pos.0, pos.1, Map::decay_momentum(momentum, step), rng);
+            if next_pos == pos {
+                break;
+            }
+            pos = next_pos;
+        }
+
+        pos
+    }
+
+    fn get_next_pos(&self, x: i32, y: i32, momentum: i32, rng: &mut impl TetrisRng) -> (i32, i32) {
+        if momentum != 0 {
+            // Grains thrown sideways tumble diagonally while there is room
+            let side_x = x + momentum.signum();
+            if self
+                .get_field(side_x, y + 1)
+                .is_some_and(|field| field.is_empty())
+            {
+                return (side_x, y + 1);
+            }
+        }
+
         let field_down = self.get_field(x, y + 1);
         let field_down_left = self.get_field(x - 1, y + 1);
         let field_down_right = self.get_field(x + 1, y + 1);
@@ -313,18 +407,27 @@ impl Map {
 
     pub fn clear(&mut self) {
         self.grid = Map::create_grid(self.width, self.height);
+        self.is_still = true;
     }
 }
 
 #[cfg(test)]
 mod test {
     use crate::{
-        constants::colors::{BLUE, RED, YELLOW, YELLOW_DARK},
-        utils::tetris_rng::{MockTetrisRng, ThreadTetrisRng},
+        objects::{
+            color_family::ColorFamily,
+            matching_rules::{ColorMatching, Connectivity},
+        },
+        utils::tetris_rng::{MockTetrisRng, SeededTetrisRng, ThreadTetrisRng},
     };
 
     use super::*;
 
+    const RED: Option<GrainColor> = Some(GrainColor::new(ColorFamily::Red, 0));
+    const BLUE: Option<GrainColor> = Some(GrainColor::new(ColorFamily::Blue, 0));
+    const YELLOW: Option<GrainColor> = Some(GrainColor::new(ColorFamily::Yellow, 0));
+    const YELLOW_DARK: Option<GrainColor> = Some(GrainColor::new(ColorFamily::Yellow, 2));
+
     #[test]
     fn create_map() {
         let map = Map::new(200, 400);
@@ -347,7 +450,7 @@ mod test {
             let field = grid.get(y).unwrap().get(x).unwrap();
             assert_eq!(field.get_x(), x as i32);
             assert_eq!(field.get_y(), y as i32);
-            assert_eq!(field.get_color(), BACKGROUND_COLOR);
+            assert_eq!(field.get_color(), None);
             assert_eq!(field.get_group_id(), 0);
         }
     }
@@ -362,7 +465,7 @@ mod test {
 
         assert_eq!(field_middle.get_x(), 20);
         assert_eq!(field_middle.get_y(), 40);
-        assert_eq!(field_middle.get_color(), BACKGROUND_COLOR);
+        assert_eq!(field_middle.get_color(), None);
         assert_eq!(field_middle.get_group_id(), 0);
         assert_eq!(field_min.get_x(), 0);
         assert_eq!(field_min.get_y(), 0);
@@ -638,6 +741,114 @@ mod test {
         assert!(map.is_row_complete(&Vec::from([1])))
     }
 
+    fn place_and_merge(
+        map: &mut Map,
+        x: i32,
+        y: i32,
+        color: Option<GrainColor>,
+        group_id: u32,
+    ) -> u32 {
+        map.change_field(x, y, color, group_id);
+        map.get_new_group((x, y), (x, y), group_id)
+    }
+
+    #[test]
+    fn is_row_complete_four_way_connectivity() {
+        let mut map: Map = Map::new(3, 3);
+        map.set_matching_rules(MatchingRules {
+            connectivity: Connectivity::FourWay,
+            color_matching: ColorMatching::ExactFamily,
+        });
+        /*
+            0|      #
+            1|   #
+            2|#
+              0 1 2
+        */
+
+        let group_1 = place_and_merge(&mut map, 0, 2, YELLOW, 1);
+        let group_2 = place_and_merge(&mut map, 1, 1, YELLOW, 2);
+        let group_3 = place_and_merge(&mut map, 2, 0, YELLOW, 3);
+
+        assert_eq!((group_1, group_2, group_3), (1, 2, 3)); // Diagonals don't connect
+        assert!(!map.is_row_complete(&Vec::from([group_1])));
+        assert!(!map.is_row_complete(&Vec::from([group_3])));
+    }
+
+    #[test]
+    fn is_row_complete_eight_way_connectivity() {
+        let mut map: Map = Map::new(3, 3);
+        map.set_matching_rules(MatchingRules {
+            connectivity: Connectivity::EightWay,
+            color_matching: ColorMatching::ExactFamily,
+        });
+        /*
+            0|      #
+            1|   #
+            2|#
+              0 1 2
+        */
+
+        let group_1 = place_and_merge(&mut map, 0, 2, YELLOW, 1);
+        place_and_merge(&mut map, 1, 1, YELLOW, 2);
+        let group_3 = place_and_merge(&mut map, 2, 0, YELLOW, 3);
+
+        assert_eq!(group_3, group_1);
+        assert!(map.is_row_complete(&Vec::from([group_1])));
+    }
+
+    #[test]
+    fn is_row_complete_exact_family() {
+        let mut map: Map = Map::new(3, 1);
+        map.set_matching_rules(MatchingRules::CLASSIC);
+
+        let group_1 = place_and_merge(&mut map, 0, 0, YELLOW, 1);
+        place_and_merge(&mut map, 1, 0, YELLOW_DARK, 2);
+        let group_3 = place_and_merge(&mut map, 2, 0, BLUE, 3);
+
+        assert_eq!(map.get_field_group_id(1, 0).unwrap(), group_1); // Shades of one family connect
+        assert_ne!(group_3, group_1);
+        assert!(!map.is_row_complete(&Vec::from([group_1])));
+    }
+
+    #[test]
+    fn is_row_complete_any_color() {
+        let mut map: Map = Map::new(3, 1);
+        map.set_matching_rules(MatchingRules {
+            connectivity: Connectivity::EightWay,
+            color_matching: ColorMatching::AnyColor,
+        });
+
+        let group_1 = place_and_merge(&mut map, 0, 0, YELLOW, 1);
+        place_and_merge(&mut map, 1, 0, RED, 2);
+        let group_3 = place_and_merge(&mut map, 2, 0, BLUE, 3);
+
+        assert_eq!(group_3, group_1);
+        assert!(map.is_row_complete(&Vec::from([group_1])));
+    }
+
+    #[test]
+    fn is_row_complete_custom_table() {
+        const RED_BLUE: [(ColorFamily, ColorFamily); 1] = [(ColorFamily::Red, ColorFamily::Blue)];
+        let mut map: Map = Map::new(3, 1);
+        map.set_matching_rules(MatchingRules {
+            connectivity: Connectivity::EightWay,
+            color_matching: ColorMatching::Custom(&RED_BLUE),
+        });
+
+        let group_1 = place_and_merge(&mut map, 0, 0, RED, 1);
+        place_and_merge(&mut map, 1, 0, BLUE, 2);
+        let group_3 = place_and_merge(&mut map, 2, 0, RED, 3);
+
+        assert_eq!(group_3, group_1);
+        assert!(map.is_row_complete(&Vec::from([group_1])));
+
+        map.change_field(1, 0, YELLOW, 4); // Yellow is not in the table
+        map.change_field(2, 0, RED, 5);
+
+        assert!(!map.is_row_complete(&Vec::from([group_1])));
+    }
+
     #[test]
     fn get_fields_for_groups() {
         let mut map: Map = Map::new(10, 10);
@@ -683,6 +894,21 @@ mod test {
         assert!(neighbors.contains(&(2, 2)));
     }
 
+    #[test]
+    fn get_field_neighbors_four_way() {
+        let mut map: Map = Map::new(10, 10);
+        map.set_matching_rules(MatchingRules {
+            connectivity: Connectivity::FourWay,
+            color_matching: ColorMatching::ExactFamily,
+        });
+
+        let neighbors = map.get_field_neighbors(1, 1);
+        assert_eq!(neighbors.len(), 4);
+        for diagonal in [(0, 0), (2, 0), (0, 2), (2, 2)] {
+            assert!(!neighbors.contains(&diagonal));
+        }
+    }
+
     #[test]
     fn get_field_neighbors_corner() {
         let map: Map = Map::new(10, 10);
@@ -910,7 +1136,7 @@ mod test {
         map.tick_and_get_score_fields(&mut ThreadTetrisRng::new());
 
         assert_eq!(map.filter_fields(|field: &Field| field.do_draw()).len(), 1);
-        assert_eq!(map.get_field(40, 20).unwrap().get_color(), BACKGROUND_COLOR);
+        assert_eq!(map.get_field(40, 20).unwrap().get_color(), None);
         assert_eq!(map.get_field(40, 21).unwrap().get_color(), RED);
     }
 
@@ -927,15 +1153,15 @@ mod test {
 
         assert_eq!(map.filter_fields(|field: &Field| field.do_draw()).len(), 2);
         assert_eq!(map.get_field(5, 9).unwrap().get_color(), RED);
-        assert_eq!(map.get_field(5, 8).unwrap().get_color(), BACKGROUND_COLOR);
+        assert_eq!(map.get_field(5, 8).unwrap().get_color(), None);
 
-        let possible_positions: [Color; 2] = [
+        let possible_positions: [Option<GrainColor>; 2] = [
             map.get_field(4, 9).unwrap().get_color(),
             map.get_field(6, 9).unwrap().get_color(),
         ];
         assert!(
-            (possible_positions[0] == RED && possible_positions[1] == BACKGROUND_COLOR)
-                || (possible_positions[1] == RED && possible_positions[0] == BACKGROUND_COLOR)
+            (possible_positions[0] == RED && possible_positions[1].is_none())
+                || (possible_positions[1] == RED && possible_positions[0].is_none())
         );
     }
 
@@ -949,7 +1175,7 @@ mod test {
 
         assert_eq!(map.filter_fields(|field: &Field| field.do_draw()).len(), 2);
         assert_eq!(map.get_field(0, 9).unwrap().get_color(), RED);
-        assert_eq!(map.get_field(0, 8).unwrap().get_color(), BACKGROUND_COLOR);
+        assert_eq!(map.get_field(0, 8).unwrap().get_color(), None);
         assert_eq!(map.get_field(1, 9).unwrap().get_color(), RED);
     }
 
@@ -965,7 +1191,7 @@ mod test {
 
         assert_eq!(map.filter_fields(|field: &Field| field.do_draw()).len(), 4);
         assert_eq!(map.get_field(1, 9).unwrap().get_color(), RED);
-        assert_eq!(map.get_field(1, 8).unwrap().get_color(), BACKGROUND_COLOR);
+        assert_eq!(map.get_field(1, 8).unwrap().get_color(), None);
         assert_eq!(map.get_field(0, 9).unwrap().get_color(), RED);
         assert_eq!(map.get_field(0, 8).unwrap().get_color(), RED);
         assert_eq!(map.get_field(2, 9).unwrap().get_color(), RED);
@@ -981,7 +1207,7 @@ mod test {
 
         assert_eq!(map.filter_fields(|field: &Field| field.do_draw()).len(), 2);
         assert_eq!(map.get_field(9, 9).unwrap().get_color(), RED);
-        assert_eq!(map.get_field(9, 8).unwrap().get_color(), BACKGROUND_COLOR);
+        assert_eq!(map.get_field(9, 8).unwrap().get_color(), None);
         assert_eq!(map.get_field(8, 9).unwrap().get_color(), RED);
     }
 
@@ -997,12 +1223,220 @@ mod test {
 
         assert_eq!(map.filter_fields(|field: &Field| field.do_draw()).len(), 4);
         assert_eq!(map.get_field(8, 9).unwrap().get_color(), RED);
-        assert_eq!(map.get_field(8, 8).unwrap().get_color(), BACKGROUND_COLOR);
+        assert_eq!(map.get_field(8, 8).unwrap().get_color(), None);
         assert_eq!(map.get_field(9, 9).unwrap().get_color(), RED);
         assert_eq!(map.get_field(9, 8).unwrap().get_color(), RED);
         assert_eq!(map.get_field(7, 9).unwrap().get_color(), RED);
     }
 
+    #[test]
+    fn change_field_resets_momentum() {
+        let mut map: Map = Map::new(10, 10);
+        map.change_field(0, 0, RED, 1);
+        map.set_field_momentum(0, 0, 2);
+
+        map.change_field(0, 0, BLUE, 2);
+
+        assert_eq!(map.get_field(0, 0).unwrap().get_momentum(), 0);
+    }
+
+    #[test]
+    fn get_new_pos_with_momentum() {
+        let mut map: Map = Map::new(10, 10);
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        rng.set_go_right(false);
+        /*
+            7|   [>]      <- Thrown to the right, would fall straight down otherwise
+            8|
+            9|
+               0  1  2
+        */
+        map.change_field(1, 7, YELLOW, 1);
+        map.set_field_momentum(1, 7, 1);
+
+        assert_eq!(map.get_new_pos(1, 7, &mut rng), (2, 8));
+
+        map.set_field_momentum(1, 7, -1);
+
+        assert_eq!(map.get_new_pos(1, 7, &mut rng), (0, 8));
+    }
+
+    #[test]
+    fn grains_with_momentum_scatter() {
+        let mut map: Map = Map::new(10, 10);
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        /*
+            7|      [>]         ->    7|
+            8|                  ->    8|
+            9|                  ->    9|            [x]
+               0  1  2  3  4              0  1  2  3  4
+        */
+        map.change_field(2, 7, YELLOW, 1);
+        map.set_field_momentum(2, 7, 5);
+
+        map.tick_and_get_score_fields(&mut rng);
+
+        assert_eq!(map.get_field(3, 8).unwrap().get_color(), YELLOW);
+        assert_eq!(map.get_field(3, 8).unwrap().get_momentum(), 4);
+
+        map.tick_and_get_score_fields(&mut rng);
+
+        assert_eq!(map.get_field(4, 9).unwrap().get_color(), YELLOW);
+
+        map.tick_and_get_score_fields(&mut rng);
+
+        assert_eq!(map.get_field(4, 9).unwrap().get_momentum(), 0); // Lost on the floor
+    }
+
+    #[test]
+    fn grains_with_momentum_blocked() {
+        let mut map: Map = Map::new(10, 10);
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        /*
+            8|   [>]
+            9|   [x][x]
+               0  1  2
+        */
+        map.change_field(1, 9, RED, 1);
+        map.change_field(2, 9, RED, 1);
+        map.change_field(1, 8, YELLOW, 2);
+        map.set_field_momentum(1, 8, 3);
+        rng.set_go_right(true);
+
+        map.tick_and_get_score_fields(&mut rng);
+
+        assert_eq!(map.get_field(0, 9).unwrap().get_color(), YELLOW); // Regular sand rules apply
+        assert_eq!(map.get_field(0, 9).unwrap().get_momentum(), 2);
+    }
+
+    #[test]
+    fn get_fall_velocity() {
+        let mut map: Map = Map::new(10, 10);
+
+        map.change_field(1, 1, RED, 1);
+
+        assert_eq!(map.get_fall_velocity(1, 1), GRAIN_START_VELOCITY);
+
+        map.set_field_velocity(1, 1, 2.0);
+
+        assert_eq!(map.get_fall_velocity(1, 1), 2.0 + GRAIN_GRAVITY);
+
+        map.set_field_velocity(1, 1, GRAIN_TERMINAL_VELOCITY);
+
+        assert_eq!(map.get_fall_velocity(1, 1), GRAIN_TERMINAL_VELOCITY);
+    }
+
+    #[test]
+    fn falling_grain_accelerates() {
+        let mut map: Map = Map::new(3, 40);
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+
+        map.change_field(1, 0, YELLOW, 1);
+
+        let mut y = 0;
+        let mut cells_fallen_per_tick = Vec::new();
+        for _ in 0..8 {
+            map.tick_and_get_score_fields(&mut rng);
+            let new_y = (0..40)
+                .find(|&row| !map.get_field(1, row).unwrap().is_empty())
+                .unwrap();
+            cells_fallen_per_tick.push(new_y - y);
+            y = new_y;
+        }
+
+        assert_eq!(cells_fallen_per_tick, Vec::from([1, 1, 1, 1, 2, 2, 2, 2]));
+        assert_eq!(map.get_field(1, y).unwrap().get_velocity(), 2.75);
+    }
+
+    #[test]
+    fn falling_grain_terminal_velocity() {
+        let mut map: Map = Map::new(3, 40);
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+
+        map.change_field(1, 0, YELLOW, 1);
+        map.set_field_velocity(1, 0, GRAIN_TERMINAL_VELOCITY);
+
+        map.tick_and_get_score_fields(&mut rng);
+
+        assert_eq!(map.get_field(1, 4).unwrap().get_color(), YELLOW);
+        assert_eq!(
+            map.get_field(1, 4).unwrap().get_velocity(),
+            GRAIN_TERMINAL_VELOCITY
+        );
+    }
+
+    #[test]
+    fn fast_grain_stops_at_obstacle() {
+        let mut map: Map = Map::new(10, 4);
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        /*
+            0|   [v]      <- Falling at terminal velocity
+            1|
+            2|
+            3|[x][x][x]
+               0  1  2
+        */
+        map.change_field(0, 3, RED, 1);
+        map.change_field(1, 3, RED, 1);
+        map.change_field(2, 3, RED, 1);
+        map.change_field(1, 0, YELLOW, 2);
+        map.set_field_velocity(1, 0, GRAIN_TERMINAL_VELOCITY);
+
+        map.tick_and_get_score_fields(&mut rng);
+
+        assert_eq!(map.get_field(1, 2).unwrap().get_color(), YELLOW);
+        assert_eq!(map.get_field(1, 2).unwrap().get_velocity(), 0.0); // Speed lost on impact
+        assert_eq!(map.get_field(1, 3).unwrap().get_color(), RED);
+    }
+
+    #[test]
+    fn grain_at_rest_has_no_velocity() {
+        let mut map: Map = Map::new(10, 10);
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+
+        map.change_field(1, 9, YELLOW, 1);
+        map.set_field_velocity(1, 9, 2.0);
+
+        map.tick_and_get_score_fields(&mut rng);
+
+        assert_eq!(map.get_field(1, 9).unwrap().get_velocity(), 0.0);
+    }
+
+    #[test]
+    fn same_seed_same_pour() {
+        let mut map: Map = Map::new(20, 30);
+        let mut other_map: Map = Map::new(20, 30);
+        let mut rng = SeededTetrisRng::new(42);
+        let mut other_rng = SeededTetrisRng::new(42);
+
+        for x in 5..10 {
+            for y in 0..5 {
+                map.change_field(x, y, YELLOW, 1);
+                other_map.change_field(x, y, YELLOW, 1);
+            }
+        }
+        for _ in 0..40 {
+            map.tick_and_get_score_fields(&mut rng);
+            other_map.tick_and_get_score_fields(&mut other_rng);
+        }
+
+        assert!(map.grid == other_map.grid);
+    }
+
+    #[test]
+    fn is_still() {
+        let mut map: Map = Map::new(10, 10);
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+        map.change_field(0, 7, RED, 1);
+
+        map.tick_and_get_score_fields(&mut rng);
+        assert!(!map.is_still());
+
+        map.tick_and_get_score_fields(&mut rng); // Lands on the floor
+        map.tick_and_get_score_fields(&mut rng);
+        assert!(map.is_still());
+    }
+
     #[test]
     fn clear() {
         let mut map: Map = Map::new(10, 10);
@@ -1011,6 +1445,6 @@ mod test {
 
         map.clear();
 
-        assert_eq!(map.get_field(0, 0).unwrap().get_color(), BACKGROUND_COLOR);
+        assert_eq!(map.get_field(0, 0).unwrap().get_color(), None);
     }
 }
@@ -0,0 +1,111 @@
+use crate::objects::color_family::ColorFamily;
+
+const FOUR_WAY_OFFSETS: [(i32, i32); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+const EIGHT_WAY_OFFSETS: [(i32, i32); 8] = [
+    (-1, 0),
+    (1, 0),
+    (0, -1),
+    (0, 1),
+    (-1, 1),
+    (1, -1),
+    (-1, -1),
+    (1, 1),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Connectivity {
+    FourWay,
+    EightWay,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorMatching {
+    ExactFamily,
+    AnyColor,
+    Custom(&'static [(ColorFamily, ColorFamily)]), // Pairs of compatible families, order doesn't matter
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MatchingRules {
+    pub connectivity: Connectivity,
+    pub color_matching: ColorMatching,
+}
+
+impl MatchingRules {
+    pub const CLASSIC: MatchingRules = MatchingRules {
+        connectivity: Connectivity::EightWay,
+        color_matching: ColorMatching::ExactFamily,
+    };
+
+    pub fn get_neighbour_offsets(&self) -> &'static [(i32, i32)] {
+        match self.connectivity {
+            Connectivity::FourWay => &FOUR_WAY_OFFSETS,
+            Connectivity::EightWay => &EIGHT_WAY_OFFSETS,
+        }
+    }
+
+    pub fn do_colors_match(&self, family: ColorFamily, other_family: ColorFamily) -> bool {
+        match self.color_matching {
+            ColorMatching::ExactFamily => family == other_family,
+            ColorMatching::AnyColor => true,
+            ColorMatching::Custom(compatible_pairs) => {
+                family == other_family
+                    || compatible_pairs.iter().any(|&(a, b)| {
+                        (a == family && b == other_family) || (a == other_family && b == family)
+                    })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const RED_BLUE_COMPATIBLE: [(ColorFamily, ColorFamily); 1] =
+        [(ColorFamily::Red, ColorFamily::Blue)];
+
+    #[test]
+    fn get_neighbour_offsets() {
+        let mut rules = MatchingRules::CLASSIC;
+
+        assert_eq!(rules.get_neighbour_offsets().len(), 8);
+
+        rules.connectivity = Connectivity::FourWay;
+
+        assert_eq!(rules.get_neighbour_offsets().len(), 4);
+        assert!(!rules.get_neighbour_offsets().contains(&(1, 1)));
+    }
+
+    #[test]
+    fn do_colors_match_exact_family() {
+        let rules = MatchingRules::CLASSIC;
+
+        assert!(rules.do_colors_match(ColorFamily::Red, ColorFamily::Red));
+        assert!(!rules.do_colors_match(ColorFamily::Red, ColorFamily::Blue));
+    }
+
+    #[test]
+    fn do_colors_match_any_color() {
+        let rules = MatchingRules {
+            connectivity: Connectivity::EightWay,
+            color_matching: ColorMatching::AnyColor,
+        };
+
+        assert!(rules.do_colors_match(ColorFamily::Red, ColorFamily::Blue));
+        assert!(rules.do_colors_match(ColorFamily::Green, ColorFamily::Yellow));
+    }
+
+    #[test]
+    fn do_colors_match_custom() {
+        let rules = MatchingRules {
+            connectivity: Connectivity::EightWay,
+            color_matching: ColorMatching::Custom(&RED_BLUE_COMPATIBLE),
+        };
+
+        assert!(rules.do_colors_match(ColorFamily::Red, ColorFamily::Blue));
+        assert!(rules.do_colors_match(ColorFamily::Blue, ColorFamily::Red)); // Pairs work both ways
+        assert!(rules.do_colors_match(ColorFamily::Green, ColorFamily::Green));
+        assert!(!rules.do_colors_match(ColorFamily::Red, ColorFamily::Green));
+    }
+}
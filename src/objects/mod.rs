@@ -1,3 +1,14 @@
 pub mod block;
+pub mod color_family;
 pub mod field;
+pub mod game_mode;
+pub mod garbage;
+pub mod high_scores;
 pub mod map;
+pub mod matching_rules;
+pub mod particle;
+pub mod piece_set;
+pub mod puzzle;
+pub mod settle_rules;
+pub mod theme;
+pub mod timeline;
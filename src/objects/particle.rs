@@ -0,0 +1,121 @@
+use crate::objects::color_family::GrainColor;
+
+// A grain flying off a cleared row, it fades out over its lifetime
+#[derive(Debug, Clone, PartialEq)]
+pub struct Particle {
+    x: f32,
+    y: f32,
+    velocity: (f32, f32), // Cells per tick
+    color: GrainColor,
+    age: u32,
+    lifetime: u32, // Ticks until it is gone
+}
+
+impl Particle {
+    pub fn new(x: f32, y: f32, velocity: (f32, f32), color: GrainColor, lifetime: u32) -> Self {
+        Particle {
+            x,
+            y,
+            velocity,
+            color,
+            age: 0,
+            lifetime,
+        }
+    }
+
+    pub fn tick(&mut self, gravity: f32) {
+        self.x += self.velocity.0;
+        self.y += self.velocity.1;
+        self.velocity.1 += gravity;
+        self.age += 1;
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.age < self.lifetime
+    }
+
+    pub fn get_x(&self) -> f32 {
+        self.x
+    }
+
+    pub fn get_y(&self) -> f32 {
+        self.y
+    }
+
+    // Map cell the particle is drawn in
+    pub fn get_cell(&self) -> (i32, i32) {
+        (self.x.floor() as i32, self.y.floor() as i32)
+    }
+
+    pub fn get_velocity(&self) -> (f32, f32) {
+        self.velocity
+    }
+
+    pub fn get_color(&self) -> GrainColor {
+        self.color
+    }
+
+    // 1.0 when spawned, down to 0.0 at the end of its lifetime
+    pub fn get_opacity(&self) -> f32 {
+        if self.lifetime == 0 {
+            return 0.0;
+        }
+
+        1.0 - (self.age as f32 / self.lifetime as f32).min(1.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::objects::color_family::ColorFamily;
+
+    use super::*;
+
+    const RED: GrainColor = GrainColor::new(ColorFamily::Red, 1);
+
+    #[test]
+    fn create_particle() {
+        let particle = Particle::new(1.5, 2.0, (0.5, -1.0), RED, 4);
+
+        assert_eq!(particle.get_x(), 1.5);
+        assert_eq!(particle.get_y(), 2.0);
+        assert_eq!(particle.get_cell(), (1, 2));
+        assert_eq!(particle.get_velocity(), (0.5, -1.0));
+        assert_eq!(particle.get_color(), RED);
+        assert_eq!(particle.get_opacity(), 1.0);
+        assert!(particle.is_alive());
+    }
+
+    #[test]
+    fn tick_moves_and_falls() {
+        let mut particle = Particle::new(1.0, 2.0, (0.5, -1.0), RED, 4);
+
+        particle.tick(0.25);
+
+        assert_eq!(particle.get_x(), 1.5);
+        assert_eq!(particle.get_y(), 1.0);
+        assert_eq!(particle.get_velocity(), (0.5, -0.75));
+        assert_eq!(particle.get_cell(), (1, 1));
+    }
+
+    #[test]
+    fn fades_out() {
+        let mut particle = Particle::new(0.0, 0.0, (0.0, 0.0), RED, 4);
+
+        particle.tick(0.0);
+        assert_eq!(particle.get_opacity(), 0.75);
+
+        for _ in 0..3 {
+            particle.tick(0.0);
+        }
+        assert_eq!(particle.get_opacity(), 0.0);
+        assert!(!particle.is_alive());
+    }
+
+    #[test]
+    fn cell_left_of_map() {
+        let particle = Particle::new(-0.5, 0.0, (0.0, 0.0), RED, 1);
+
+        assert_eq!(particle.get_cell(), (-1, 0));
+    }
+}
@@ -0,0 +1,428 @@
+use std::{collections::VecDeque, fs};
+
+use crate::{
+    constants::block_schemas,
+    objects::block::{BlockType, CLASSIC_BLOCK_TYPES},
+};
+
+const ALLOWED_ROTATIONS: [u8; 3] = [1, 2, 4];
+const MAX_PIECE_EXTENT: usize = 32;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PieceDefinition {
+    name: String,
+    cells: Vec<(i8, i8)>, // Relative to the rotation pivot
+    rotations: u8,
+    spawn_weight: u32,
+}
+
+impl PieceDefinition {
+    pub fn new(name: &str, cells: Vec<(i8, i8)>, rotations: u8, spawn_weight: u32) -> Self {
+        PieceDefinition {
+            name: name.to_string(),
+            cells,
+            rotations,
+            spawn_weight,
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_cells(&self) -> &Vec<(i8, i8)> {
+        &self.cells
+    }
+
+    pub fn get_rotations(&self) -> u8 {
+        self.rotations
+    }
+
+    pub fn get_spawn_weight(&self) -> u32 {
+        self.spawn_weight
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.cells.is_empty() {
+            return Err(format!("Piece {} has no cells", self.name));
+        }
+        if !ALLOWED_ROTATIONS.contains(&self.rotations) {
+            return Err(format!(
+                "Piece {} has {} rotations, expected one of {:?}",
+                self.name, self.rotations, ALLOWED_ROTATIONS
+            ));
+        }
+        if !self.cells.contains(&(0, 0)) {
+            return Err(format!("Piece {} has no cell on its pivot", self.name));
+        }
+        if !self.is_connected() {
+            return Err(format!("Piece {} is not connected", self.name));
+        }
+
+        Ok(())
+    }
+
+    fn is_connected(&self) -> bool {
+        let mut visited = Vec::from([self.cells[0]]);
+        let mut queue = VecDeque::from([self.cells[0]]);
+        while let Some((x, y)) = queue.pop_front() {
+            for neighbour in [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)] {
+                if self.cells.contains(&neighbour) && !visited.contains(&neighbour) {
+                    visited.push(neighbour);
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+
+        visited.len() == self.cells.len()
+    }
+}
+
+impl From<BlockType> for PieceDefinition {
+    fn from(block_type: BlockType) -> Self {
+        let (name, schema, rotations, spawn_weight) = match block_type {
+            BlockType::LBlock => ("L", block_schemas::L_BLOCK, 4, 1),
+            BlockType::RevLBlock => ("J", block_schemas::REV_L_BLOCK, 4, 1),
+            BlockType::SquareBlock => ("O", block_schemas::SQUARE_BLOCK, 1, 1),
+            BlockType::ZBlock => ("Z", block_schemas::Z_BLOCK, 4, 1),
+            BlockType::RevZBlock => ("S", block_schemas::REV_Z_BLOCK, 4, 1),
+            BlockType::IBlock => ("I", block_schemas::I_BLOCK, 4, 2), // I block was always twice as likely
+            BlockType::TBlock => ("T", block_schemas::T_BLOCK, 4, 1),
+        };
+
+        PieceDefinition::new(name, Vec::from(schema), rotations, spawn_weight)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PieceSet {
+    pieces: Vec<PieceDefinition>,
+}
+
+impl PieceSet {
+    pub fn new(pieces: Vec<PieceDefinition>) -> Result<Self, String> {
+        let piece_set = PieceSet { pieces };
+        piece_set.validate()?;
+
+        Ok(piece_set)
+    }
+
+    pub fn classic() -> Self {
+        PieceSet {
+            pieces: CLASSIC_BLOCK_TYPES
+                .iter()
+                .map(|block_type| PieceDefinition::from(block_type.clone()))
+                .collect(),
+        }
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        match fs::read_to_string(path) {
+            Ok(text) => PieceSet::parse(&text).map_err(|message| format!("{}: {}", path, message)),
+            Err(error) => Err(format!("{}: {}", path, error)),
+        }
+    }
+
+    /*
+        // comment
+        piece L          <- starts a piece, names must be unique
+        weight 1         <- optional spawn weight, defaults to 1
+        rotations 4      <- optional, 1 (no rotation), 2 or 4 (default)
+        #.
+        @.               <- '@' marks the rotation pivot, '#' other cells
+        ##
+        end
+    */
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut pieces = Vec::new();
+        let mut current: Option<PieceBuilder> = None;
+
+        for (line_index, raw_line) in text.lines().enumerate() {
+            let line = raw_line.split("//").next().unwrap_or("").trim();
+            let is_grid_row = !line.is_empty() && line.chars().all(|c| ".#@".contains(c));
+            let line_number = line_index + 1;
+
+            if is_grid_row {
+                match current.as_mut() {
+                    Some(builder) => builder.rows.push(line.to_string()),
+                    None => {
+                        return Err(format!("line {}: grid row outside of a piece", line_number))
+                    }
+                }
+                continue;
+            }
+            if line.is_empty() {
+                continue;
+            }
+
+            let mut words = line.split_whitespace();
+            let keyword = words.next().unwrap();
+            let value = words.next();
+            match (keyword, value, current.as_mut()) {
+                ("piece", Some(name), None) => current = Some(PieceBuilder::new(name)),
+                ("weight", Some(weight), Some(builder)) => {
+                    builder.spawn_weight = weight
+                        .parse()
+                        .map_err(|_| format!("line {}: invalid weight {}", line_number, weight))?
+                }
+                ("rotations", Some(rotations), Some(builder)) => {
+                    builder.rotations = rotations.parse().map_err(|_| {
+                        format!("line {}: invalid rotations {}", line_number, rotations)
+                    })?
+                }
+                ("end", None, Some(_)) => {
+                    let piece = current.take().unwrap().build()?;
+                    pieces.push(piece);
+                }
+                _ => return Err(format!("line {}: unexpected '{}'", line_number, line)),
+            }
+        }
+
+        if let Some(builder) = current {
+            return Err(format!("Piece {} is missing 'end'", builder.name));
+        }
+
+        PieceSet::new(pieces)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.pieces.is_empty() {
+            return Err("Piece set is empty".to_string());
+        }
+        for (i, piece) in self.pieces.iter().enumerate() {
+            piece.validate()?;
+            if self.pieces[..i]
+                .iter()
+                .any(|other| other.get_name() == piece.get_name())
+            {
+                return Err(format!("Piece {} is defined twice", piece.name));
+            }
+        }
+        if self.get_spawn_weights().iter().sum::<u32>() == 0 {
+            return Err("Piece set has no piece with a positive weight".to_string());
+        }
+
+        Ok(())
+    }
+
+    pub fn get_pieces(&self) -> &Vec<PieceDefinition> {
+        &self.pieces
+    }
+
+    pub fn get_piece(&self, index: usize) -> &PieceDefinition {
+        &self.pieces[index]
+    }
+
+    pub fn get_spawn_weights(&self) -> Vec<u32> {
+        self.pieces
+            .iter()
+            .map(|piece| piece.get_spawn_weight())
+            .collect()
+    }
+}
+
+struct PieceBuilder {
+    name: String,
+    rows: Vec<String>,
+    rotations: u8,
+    spawn_weight: u32,
+}
+
+impl PieceBuilder {
+    fn new(name: &str) -> Self {
+        PieceBuilder {
+            name: name.to_string(),
+            rows: Vec::new(),
+            rotations: 4,
+            spawn_weight: 1,
+        }
+    }
+
+    fn build(self) -> Result<PieceDefinition, String> {
+        if self.rows.len() > MAX_PIECE_EXTENT
+            || self.rows.iter().any(|row| row.len() > MAX_PIECE_EXTENT)
+        {
+            return Err(format!("Piece {} is too big", self.name));
+        }
+
+        let mut pivot = None;
+        let mut grid_cells = Vec::new();
+        for (y, row) in self.rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                if c == '@' {
+                    if pivot.is_some() {
+                        return Err(format!("Piece {} has more than one pivot", self.name));
+                    }
+                    pivot = Some((x as i8, y as i8));
+                }
+                if c == '@' || c == '#' {
+                    grid_cells.push((x as i8, y as i8));
+                }
+            }
+        }
+
+        let (pivot_x, pivot_y) = match pivot {
+            Some(pivot) => pivot,
+            None => return Err(format!("Piece {} has no pivot", self.name)),
+        };
+        let cells = grid_cells
+            .into_iter()
+            .map(|(x, y)| (x - pivot_x, y - pivot_y))
+            .collect();
+
+        Ok(PieceDefinition::new(
+            &self.name,
+            cells,
+            self.rotations,
+            self.spawn_weight,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn count_pieces(piece_set: &PieceSet) -> usize {
+        piece_set.get_spawn_weights().len()
+    }
+
+    fn get_cell_counts(piece_set: &PieceSet) -> Vec<usize> {
+        (0..count_pieces(piece_set))
+            .map(|i| piece_set.get_piece(i).get_cells().len())
+            .collect()
+    }
+
+    const TRIOMINO_SET: &str = include_str!("../../assets/pieces/triomino.txt");
+    const PENTOMINO_SET: &str = include_str!("../../assets/pieces/pentomino.txt");
+    const CLASSIC_SET: &str = include_str!("../../assets/pieces/classic.txt");
+    const BIG_SET: &str = include_str!("../../assets/pieces/big.txt");
+
+    #[test]
+    fn classic() {
+        let piece_set = PieceSet::classic();
+
+        assert_eq!(count_pieces(&piece_set), 7);
+        assert_eq!(
+            piece_set.get_spawn_weights(),
+            Vec::from([1, 1, 1, 1, 1, 2, 1])
+        );
+        assert_eq!(
+            piece_set.get_piece(0).get_cells(),
+            &Vec::from(block_schemas::L_BLOCK)
+        );
+        assert_eq!(piece_set.get_piece(2).get_rotations(), 1);
+        assert!(piece_set.validate().is_ok());
+    }
+
+    #[test]
+    fn parse_piece() {
+        let piece_set = PieceSet::parse(
+            "
+            // Comments and blank lines are skipped
+            piece L
+            weight 3
+            rotations 2
+            #.
+            @.
+            ##
+            end
+            ",
+        )
+        .unwrap();
+
+        let piece = piece_set.get_piece(0);
+        assert_eq!(piece.get_name(), "L");
+        assert_eq!(piece.get_spawn_weight(), 3);
+        assert_eq!(piece.get_rotations(), 2);
+        assert_eq!(
+            piece.get_cells(),
+            &Vec::from([(0, -1), (0, 0), (0, 1), (1, 1)])
+        );
+    }
+
+    #[test]
+    fn parse_commented_grid_row() {
+        let piece_set = PieceSet::parse("piece I\n#@## // pivot second\nend").unwrap();
+
+        assert_eq!(
+            piece_set.get_piece(0).get_cells(),
+            &Vec::from([(-1, 0), (0, 0), (1, 0), (2, 0)])
+        );
+    }
+
+    #[test]
+    fn parse_defaults() {
+        let piece_set = PieceSet::parse("piece X\n@#\nend").unwrap();
+
+        assert_eq!(piece_set.get_piece(0).get_spawn_weight(), 1);
+        assert_eq!(piece_set.get_piece(0).get_rotations(), 4);
+    }
+
+    #[test]
+    fn parse_invalid() {
+        for (text, expected_error) in [
+            ("", "empty"),
+            ("piece A\n##\nend", "no pivot"),
+            ("piece A\n@@\nend", "more than one pivot"),
+            ("piece A\n@.#\nend", "not connected"),
+            ("piece A\nrotations 3\n@#\nend", "rotations"),
+            ("piece A\nweight -1\n@#\nend", "invalid weight"),
+            ("piece A\nweight 0\n@#\nend", "positive weight"),
+            ("piece A\n@#\nend\npiece A\n@\nend", "defined twice"),
+            ("piece A\n@#", "missing 'end'"),
+            ("@#", "outside of a piece"),
+            ("piece A\npiece B\nend", "unexpected"),
+        ] {
+            let error = PieceSet::parse(text).unwrap_err();
+            assert!(error.contains(expected_error), "{}: {}", text, error);
+        }
+    }
+
+    #[test]
+    fn parse_too_big() {
+        let text = format!("piece A\n@{}\nend", "#".repeat(MAX_PIECE_EXTENT));
+
+        assert!(PieceSet::parse(&text).unwrap_err().contains("too big"));
+    }
+
+    #[test]
+    fn load_missing_file() {
+        assert!(PieceSet::load("does/not/exist.txt").is_err());
+    }
+
+    #[test]
+    fn bundled_sets() {
+        let classic = PieceSet::parse(CLASSIC_SET).unwrap();
+        assert_eq!(count_pieces(&classic), count_pieces(&PieceSet::classic()));
+        for i in 0..count_pieces(&classic) {
+            let (parsed, built_in) = (
+                classic.get_piece(i),
+                PieceSet::classic().get_piece(i).clone(),
+            );
+            let mut parsed_cells = parsed.get_cells().clone();
+            let mut built_in_cells = built_in.get_cells().clone();
+            parsed_cells.sort();
+            built_in_cells.sort();
+
+            assert_eq!(parsed.get_name(), built_in.get_name());
+            assert_eq!(parsed.get_rotations(), built_in.get_rotations());
+            assert_eq!(parsed.get_spawn_weight(), built_in.get_spawn_weight());
+            assert_eq!(parsed_cells, built_in_cells);
+        }
+
+        let triominoes = PieceSet::parse(TRIOMINO_SET).unwrap();
+        assert!(get_cell_counts(&triominoes).iter().all(|&count| count == 3));
+
+        let pentominoes = PieceSet::parse(PENTOMINO_SET).unwrap();
+        assert_eq!(count_pieces(&pentominoes), 12);
+        assert!(get_cell_counts(&pentominoes)
+            .iter()
+            .all(|&count| count == 5));
+
+        let big_pieces = PieceSet::parse(BIG_SET).unwrap();
+        assert!(get_cell_counts(&big_pieces)
+            .iter()
+            .all(|&count| count == 16));
+    }
+}
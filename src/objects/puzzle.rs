@@ -0,0 +1,384 @@
+use std::{fs, io::ErrorKind};
+
+use crate::objects::{color_family::ColorFamily, piece_set::PieceSet};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PuzzleGoal {
+    ClearColor(ColorFamily), // No grain of the family is left on the board
+    ClearGrains(u32),
+    Survive(u32), // Settle that many pieces without topping out
+}
+
+// A handcrafted board played with a fixed sequence of pieces
+#[derive(Debug, Clone, PartialEq)]
+pub struct Puzzle {
+    goal: PuzzleGoal,
+    pieces: Vec<(String, ColorFamily)>, // Piece names from the piece set, in the order they come
+    board: Vec<Vec<Option<ColorFamily>>>, // Rows of grains from the top down
+}
+
+impl Puzzle {
+    pub fn new(
+        goal: PuzzleGoal,
+        pieces: Vec<(String, ColorFamily)>,
+        board: Vec<Vec<Option<ColorFamily>>>,
+    ) -> Result<Self, String> {
+        let puzzle = Puzzle {
+            goal,
+            pieces,
+            board,
+        };
+        puzzle.validate()?;
+
+        Ok(puzzle)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.pieces.is_empty() {
+            return Err("Puzzle has no pieces".to_string());
+        }
+        if let PuzzleGoal::Survive(count) = self.goal {
+            if count as usize > self.pieces.len() {
+                return Err(format!(
+                    "Puzzle asks to survive {} pieces but only has {}",
+                    count,
+                    self.pieces.len()
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    // Something to start an empty board from, the goal and the pieces are meant to be edited in the file
+    pub fn blank(piece_set: &PieceSet) -> Self {
+        Puzzle {
+            goal: PuzzleGoal::Survive(1),
+            pieces: Vec::from([(
+                piece_set.get_piece(0).get_name().to_string(),
+                ColorFamily::Red,
+            )]),
+            board: Vec::new(),
+        }
+    }
+
+    pub fn get_goal(&self) -> PuzzleGoal {
+        self.goal
+    }
+
+    pub fn get_pieces(&self) -> &Vec<(String, ColorFamily)> {
+        &self.pieces
+    }
+
+    // Width and height of the board in grains
+    pub fn get_board_size(&self) -> (i32, i32) {
+        let width = self.board.iter().map(|row| row.len()).max().unwrap_or(0);
+
+        (width as i32, self.board.len() as i32)
+    }
+
+    // Grains with their map coordinates, the last row of the board lies on the floor
+    pub fn get_layout(&self, map_height: i32) -> Vec<(i32, i32, ColorFamily)> {
+        let top = map_height - self.board.len() as i32;
+        let mut layout = Vec::new();
+        for (y, row) in self.board.iter().enumerate() {
+            for (x, grain) in row.iter().enumerate() {
+                if let Some(family) = grain {
+                    layout.push((x as i32, top + y as i32, *family));
+                }
+            }
+        }
+
+        layout
+    }
+
+    // Rows from the highest grain down to the floor, all as wide as the map
+    pub fn set_board(&mut self, layout: &[(i32, i32, ColorFamily)], map_size: (i32, i32)) {
+        let top = layout
+            .iter()
+            .map(|(_, y, _)| *y)
+            .min()
+            .unwrap_or(map_size.1);
+        self.board = Vec::from_iter(
+            (top..map_size.1).map(|_| Vec::from_iter((0..map_size.0).map(|_| None))),
+        );
+        for (x, y, family) in layout {
+            self.board[(y - top) as usize][*x as usize] = Some(*family);
+        }
+    }
+
+    // Indices of the pieces in the set they are played with, for a scripted rng
+    pub fn get_script(&self, piece_set: &PieceSet) -> Result<Vec<(usize, ColorFamily)>, String> {
+        self.pieces
+            .iter()
+            .map(|(name, family)| {
+                piece_set
+                    .get_pieces()
+                    .iter()
+                    .position(|piece| piece.get_name() == name)
+                    .map(|index| (index, *family))
+                    .ok_or(format!("Puzzle piece {} is not in the piece set", name))
+            })
+            .collect()
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        match fs::read_to_string(path) {
+            Ok(text) => Puzzle::parse(&text).map_err(|message| format!("{}: {}", path, message)),
+            Err(error) => Err(format!("{}: {}", path, error)),
+        }
+    }
+
+    // A new puzzle when the file is missing
+    pub fn load_or_blank(path: &str, piece_set: &PieceSet) -> Result<Self, String> {
+        match fs::read_to_string(path) {
+            Ok(text) => Puzzle::parse(&text).map_err(|message| format!("{}: {}", path, message)),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(Puzzle::blank(piece_set)),
+            Err(error) => Err(format!("{}: {}", path, error)),
+        }
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        fs::write(path, self.to_text()).map_err(|error| format!("{}: {}", path, error))
+    }
+
+    /*
+        // comment
+        goal clear red      <- clear every red grain,
+        goal clear 500      <- or that many grains,
+        goal survive 10     <- or settle that many pieces without topping out
+        piece L red         <- pieces by their name in the piece set, in the order they come
+        piece I blue
+        board               <- one character per grain, '.' is empty and colors go by
+        ..rr..              <- their first letter, except 'k' for pink
+        bbrrgg
+        end
+    */
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut goal = None;
+        let mut pieces = Vec::new();
+        let mut board = Vec::new();
+        let mut is_in_board = false;
+
+        for (line_index, raw_line) in text.lines().enumerate() {
+            let line = raw_line.split("//").next().unwrap_or("").trim();
+            let line_number = line_index + 1;
+            if line.is_empty() {
+                continue;
+            }
+
+            if is_in_board && line != "end" {
+                let row = line
+                    .chars()
+                    .map(|symbol| match symbol {
+                        '.' => Ok(None),
+                        _ => ColorFamily::from_symbol(symbol)
+                            .map(Some)
+                            .ok_or(format!("line {}: unknown grain '{}'", line_number, symbol)),
+                    })
+                    .collect::<Result<Vec<_>, String>>()?;
+                board.push(row);
+                continue;
+            }
+
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.as_slice() {
+                ["goal", "clear", value] => {
+                    goal = Some(match value.parse() {
+                        Ok(count) => PuzzleGoal::ClearGrains(count),
+                        Err(_) => PuzzleGoal::ClearColor(ColorFamily::from_name(value).ok_or(
+                            format!("line {}: unknown color family {}", line_number, value),
+                        )?),
+                    })
+                }
+                ["goal", "survive", value] => {
+                    goal = Some(PuzzleGoal::Survive(value.parse().map_err(|_| {
+                        format!("line {}: invalid piece count {}", line_number, value)
+                    })?))
+                }
+                ["piece", name, color] => pieces.push((
+                    name.to_string(),
+                    ColorFamily::from_name(color).ok_or(format!(
+                        "line {}: unknown color family {}",
+                        line_number, color
+                    ))?,
+                )),
+                ["board"] if board.is_empty() => is_in_board = true,
+                ["end"] if is_in_board => is_in_board = false,
+                _ => return Err(format!("line {}: unexpected '{}'", line_number, line)),
+            }
+        }
+
+        if is_in_board {
+            return Err("Board is missing 'end'".to_string());
+        }
+        let goal = goal.ok_or("Puzzle has no goal".to_string())?;
+
+        Puzzle::new(goal, pieces, board)
+    }
+
+    // Comments are not kept
+    pub fn to_text(&self) -> String {
+        let mut text = match self.goal {
+            PuzzleGoal::ClearColor(family) => format!("goal clear {}\n", family.get_name()),
+            PuzzleGoal::ClearGrains(count) => format!("goal clear {}\n", count),
+            PuzzleGoal::Survive(count) => format!("goal survive {}\n", count),
+        };
+        for (name, family) in &self.pieces {
+            text += &format!("piece {} {}\n", name, family.get_name());
+        }
+        if !self.board.is_empty() {
+            text += "board\n";
+            for row in &self.board {
+                text += &row
+                    .iter()
+                    .map(|grain| grain.map_or('.', |family| family.get_symbol()))
+                    .collect::<String>();
+                text += "\n";
+            }
+            text += "end\n";
+        }
+
+        text
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const FIRST_PUZZLE: &str = include_str!("../../assets/puzzles/first_steps.txt");
+
+    #[test]
+    fn parse() {
+        let puzzle = Puzzle::parse(
+            "
+            // Comments and blank lines are skipped
+            goal clear red
+            piece L red
+            piece O blue
+            board
+            .r
+            bk
+            end
+            ",
+        )
+        .unwrap();
+
+        assert_eq!(puzzle.get_goal(), PuzzleGoal::ClearColor(ColorFamily::Red));
+        assert_eq!(
+            puzzle.get_pieces(),
+            &Vec::from([
+                ("L".to_string(), ColorFamily::Red),
+                ("O".to_string(), ColorFamily::Blue)
+            ])
+        );
+        assert_eq!(puzzle.get_board_size(), (2, 2));
+        assert_eq!(
+            puzzle.get_layout(10),
+            Vec::from([
+                (1, 8, ColorFamily::Red),
+                (0, 9, ColorFamily::Blue),
+                (1, 9, ColorFamily::Pink)
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_goals() {
+        for (line, goal) in [
+            ("goal clear 500", PuzzleGoal::ClearGrains(500)),
+            ("goal clear cyan", PuzzleGoal::ClearColor(ColorFamily::Cyan)),
+            ("goal survive 1", PuzzleGoal::Survive(1)),
+        ] {
+            let puzzle = Puzzle::parse(&format!("{}\npiece T red", line)).unwrap();
+
+            assert_eq!(puzzle.get_goal(), goal);
+        }
+    }
+
+    #[test]
+    fn parse_invalid() {
+        for (text, message) in [
+            ("piece T red", "Puzzle has no goal"),
+            ("goal clear red", "Puzzle has no pieces"),
+            ("goal clear violet", "line 1: unknown color family violet"),
+            ("goal survive many", "line 1: invalid piece count many"),
+            ("goal clear 5\npiece T", "line 2: unexpected 'piece T'"),
+            ("goal clear 5\nboard\nrx\nend", "line 3: unknown grain 'x'"),
+            ("goal clear 5\nboard\nrr", "Board is missing 'end'"),
+            (
+                "goal survive 2\npiece T red",
+                "Puzzle asks to survive 2 pieces but only has 1",
+            ),
+        ] {
+            assert_eq!(Puzzle::parse(text), Err(message.to_string()));
+        }
+    }
+
+    #[test]
+    fn get_script() {
+        let puzzle = Puzzle::parse("goal clear 5\npiece I red\npiece L blue").unwrap();
+
+        assert_eq!(
+            puzzle.get_script(&PieceSet::classic()),
+            Ok(Vec::from([(5, ColorFamily::Red), (0, ColorFamily::Blue)]))
+        );
+
+        let puzzle = Puzzle::parse("goal clear 5\npiece P red").unwrap();
+
+        assert_eq!(
+            puzzle.get_script(&PieceSet::classic()),
+            Err("Puzzle piece P is not in the piece set".to_string())
+        );
+    }
+
+    #[test]
+    fn set_board_and_to_text() {
+        let mut puzzle = Puzzle::blank(&PieceSet::classic());
+
+        assert_eq!(puzzle.to_text(), "goal survive 1\npiece L red\n");
+
+        puzzle.set_board(
+            &[(1, 8, ColorFamily::Red), (0, 9, ColorFamily::Pink)],
+            (3, 10),
+        );
+
+        assert_eq!(puzzle.get_board_size(), (3, 2));
+        assert_eq!(
+            puzzle.to_text(),
+            "goal survive 1\npiece L red\nboard\n.r.\nk..\nend\n"
+        );
+        assert_eq!(Puzzle::parse(&puzzle.to_text()), Ok(puzzle));
+    }
+
+    #[test]
+    fn set_board_empty() {
+        let mut puzzle = Puzzle::parse(FIRST_PUZZLE).unwrap();
+
+        puzzle.set_board(&[], (3, 10));
+
+        assert_eq!(puzzle.get_board_size(), (0, 0));
+        assert!(puzzle.get_layout(10).is_empty());
+    }
+
+    #[test]
+    fn load_or_blank() {
+        assert_eq!(
+            Puzzle::load_or_blank("missing.txt", &PieceSet::classic()),
+            Ok(Puzzle::blank(&PieceSet::classic()))
+        );
+        assert_eq!(
+            Puzzle::load_or_blank("assets/puzzles/first_steps.txt", &PieceSet::classic()),
+            Puzzle::parse(FIRST_PUZZLE)
+        );
+    }
+
+    #[test]
+    fn load_assets() {
+        let puzzle = Puzzle::parse(FIRST_PUZZLE).unwrap();
+
+        assert!(puzzle.get_script(&PieceSet::classic()).is_ok());
+        assert!(Puzzle::load("missing.txt").is_err());
+    }
+}
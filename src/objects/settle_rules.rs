@@ -0,0 +1,19 @@
+// How a falling block comes to rest once it meets sand
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImpactMode {
+    Settle,
+    Shatter,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SettlePolicy {
+    AnyContact,      // Touching sand from any side settles the block
+    DownwardContact, // Only landing on sand settles, bumping a pile sideways doesn't
+    AfterLockDelay,  // Landing starts the lock delay, the block settles once it runs out
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LockDelay {
+    pub ticks: Option<u32>, // None uses the game mode's
+    pub max_resets: u32,
+}
@@ -0,0 +1,673 @@
+use std::fs;
+
+use macroquad::color::Color;
+
+use crate::{
+    constants::{
+        block_skins::{
+            ANTI_DIAGONAL_STRIPES, CHECKERS, DIAGONAL_STRIPES, DOTS, GRID, HORIZONTAL_STRIPES,
+            NATURAL, PATTERN_SIDE, SKIN_SIDE, VERTICAL_STRIPES,
+        },
+        colorblind_colors,
+        colors::{
+            BACKGROUND_COLOR, BLACK, BLUE_VAR, CYAN_VAR, GREEN_VAR, LETTERBOX_COLOR, ORANGE_VAR,
+            PINK_VAR, PURPLE_VAR, RED_VAR, WHITE, YELLOW_VAR,
+        },
+    },
+    objects::color_family::{ColorFamily, GrainColor},
+};
+
+const NATURAL_SKIN: &[[usize; SKIN_SIDE]; SKIN_SIDE] = &NATURAL;
+const DIAGONAL_STRIPES_PATTERN: &[[usize; PATTERN_SIDE]; PATTERN_SIDE] = &DIAGONAL_STRIPES;
+const DOTS_PATTERN: &[[usize; PATTERN_SIDE]; PATTERN_SIDE] = &DOTS;
+const HORIZONTAL_STRIPES_PATTERN: &[[usize; PATTERN_SIDE]; PATTERN_SIDE] = &HORIZONTAL_STRIPES;
+const VERTICAL_STRIPES_PATTERN: &[[usize; PATTERN_SIDE]; PATTERN_SIDE] = &VERTICAL_STRIPES;
+const CHECKERS_PATTERN: &[[usize; PATTERN_SIDE]; PATTERN_SIDE] = &CHECKERS;
+const ANTI_DIAGONAL_STRIPES_PATTERN: &[[usize; PATTERN_SIDE]; PATTERN_SIDE] =
+    &ANTI_DIAGONAL_STRIPES;
+const GRID_PATTERN: &[[usize; PATTERN_SIDE]; PATTERN_SIDE] = &GRID;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Palette {
+    pub family: ColorFamily,
+    pub shades: &'static [Color],
+    pub pattern: Option<Skin>, // Replaces the theme skin for this family
+}
+
+// Which shade of the palette each grain of a block gets, repeated over the whole block
+#[derive(Debug, Clone, PartialEq)]
+pub struct Skin {
+    pub width: usize,
+    pub shade_indices: &'static [usize], // Row by row
+}
+
+impl Skin {
+    pub fn get_shade_index(&self, x: i32, y: i32) -> usize {
+        let height = self.shade_indices.len() / self.width;
+        let x_normalized = x.rem_euclid(self.width as i32) as usize;
+        let y_normalized = y.rem_euclid(height as i32) as usize;
+
+        self.shade_indices[y_normalized * self.width + x_normalized]
+    }
+}
+
+// Themes are picked once at launch and live until the game closes, hence the static slices
+#[derive(Debug, PartialEq)]
+pub struct Theme {
+    pub name: &'static str,
+    pub palettes: &'static [Palette],
+    pub skin: Skin,
+    pub background_color: Color,
+    pub letterbox_color: Color,
+    pub hud_text_color: Color,
+    pub hud_outline_color: Color,
+}
+
+impl Theme {
+    pub const CLASSIC: Theme = Theme {
+        name: "classic",
+        palettes: &[
+            Palette {
+                family: ColorFamily::Red,
+                shades: &RED_VAR,
+                pattern: None,
+            },
+            Palette {
+                family: ColorFamily::Blue,
+                shades: &BLUE_VAR,
+                pattern: None,
+            },
+            Palette {
+                family: ColorFamily::Green,
+                shades: &GREEN_VAR,
+                pattern: None,
+            },
+            Palette {
+                family: ColorFamily::Yellow,
+                shades: &YELLOW_VAR,
+                pattern: None,
+            },
+            Palette {
+                family: ColorFamily::Purple,
+                shades: &PURPLE_VAR,
+                pattern: None,
+            },
+            Palette {
+                family: ColorFamily::Orange,
+                shades: &ORANGE_VAR,
+                pattern: None,
+            },
+            Palette {
+                family: ColorFamily::Cyan,
+                shades: &CYAN_VAR,
+                pattern: None,
+            },
+            Palette {
+                family: ColorFamily::Pink,
+                shades: &PINK_VAR,
+                pattern: None,
+            },
+        ],
+        skin: Skin {
+            width: SKIN_SIDE,
+            shade_indices: NATURAL_SKIN.as_flattened(),
+        },
+        background_color: BACKGROUND_COLOR,
+        letterbox_color: LETTERBOX_COLOR,
+        hud_text_color: BLACK,
+        hud_outline_color: WHITE,
+    };
+
+    // Each family also gets its own pattern, so it can be told apart without seeing hues
+    pub const COLORBLIND: Theme = Theme {
+        name: "colorblind",
+        palettes: &[
+            Palette {
+                family: ColorFamily::Red,
+                shades: &colorblind_colors::RED_VAR,
+                pattern: Some(Skin {
+                    width: PATTERN_SIDE,
+                    shade_indices: DIAGONAL_STRIPES_PATTERN.as_flattened(),
+                }),
+            },
+            Palette {
+                family: ColorFamily::Blue,
+                shades: &colorblind_colors::BLUE_VAR,
+                pattern: Some(Skin {
+                    width: PATTERN_SIDE,
+                    shade_indices: DOTS_PATTERN.as_flattened(),
+                }),
+            },
+            Palette {
+                family: ColorFamily::Green,
+                shades: &colorblind_colors::GREEN_VAR,
+                pattern: Some(Skin {
+                    width: PATTERN_SIDE,
+                    shade_indices: HORIZONTAL_STRIPES_PATTERN.as_flattened(),
+                }),
+            },
+            Palette {
+                family: ColorFamily::Yellow,
+                shades: &colorblind_colors::YELLOW_VAR,
+                pattern: None, // Plain
+            },
+            Palette {
+                family: ColorFamily::Purple,
+                shades: &colorblind_colors::PURPLE_VAR,
+                pattern: Some(Skin {
+                    width: PATTERN_SIDE,
+                    shade_indices: VERTICAL_STRIPES_PATTERN.as_flattened(),
+                }),
+            },
+            Palette {
+                family: ColorFamily::Orange,
+                shades: &colorblind_colors::ORANGE_VAR,
+                pattern: Some(Skin {
+                    width: PATTERN_SIDE,
+                    shade_indices: CHECKERS_PATTERN.as_flattened(),
+                }),
+            },
+            Palette {
+                family: ColorFamily::Cyan,
+                shades: &colorblind_colors::CYAN_VAR,
+                pattern: Some(Skin {
+                    width: PATTERN_SIDE,
+                    shade_indices: ANTI_DIAGONAL_STRIPES_PATTERN.as_flattened(),
+                }),
+            },
+            Palette {
+                family: ColorFamily::Pink,
+                shades: &colorblind_colors::PINK_VAR,
+                pattern: Some(Skin {
+                    width: PATTERN_SIDE,
+                    shade_indices: GRID_PATTERN.as_flattened(),
+                }),
+            },
+        ],
+        ..Theme::CLASSIC
+    };
+
+    pub const BUILT_IN: [&'static Theme; 2] = [&Theme::CLASSIC, &Theme::COLORBLIND];
+
+    pub fn get_palette(&self, family: ColorFamily) -> &Palette {
+        self.palettes
+            .iter()
+            .find(|palette| palette.family == family)
+            .expect("Themes have a palette for every color family")
+    }
+
+    pub fn get_skin(&self, family: ColorFamily) -> &Skin {
+        self.get_palette(family)
+            .pattern
+            .as_ref()
+            .unwrap_or(&self.skin)
+    }
+
+    // Grains keep their shade when the theme changes, so it may not exist in this palette
+    pub fn get_color(&self, grain_color: GrainColor) -> Color {
+        let shades = self.get_palette(grain_color.get_family()).shades;
+
+        shades[grain_color.get_shade().min(shades.len() - 1)]
+    }
+
+    // The shade used when a whole block is drawn in one color, e.g. the next block preview
+    pub fn get_display_color(&self, family: ColorFamily) -> Color {
+        self.get_palette(family).shades[0]
+    }
+
+    // Mixed into the background, for grains that are fading out
+    pub fn get_faded_color(&self, grain_color: GrainColor, opacity: f32) -> Color {
+        mix_colors(self.background_color, self.get_color(grain_color), opacity)
+    }
+
+    // Built in themes are picked by name, anything else is a theme file
+    pub fn load(name_or_path: &str) -> Result<&'static Theme, String> {
+        if let Some(theme) = Theme::BUILT_IN
+            .into_iter()
+            .find(|theme| theme.name == name_or_path)
+        {
+            return Ok(theme);
+        }
+
+        match fs::read_to_string(name_or_path) {
+            Ok(text) => Theme::parse(&text)
+                .map(|theme| &*Box::leak(Box::new(theme)))
+                .map_err(|message| format!("{}: {}", name_or_path, message)),
+            Err(error) => Err(format!("{}: {}", name_or_path, error)),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Theme, String> {
+        let mut builder = ThemeBuilder::new();
+        let mut current_palette: Option<(ColorFamily, Vec<Color>)> = None;
+        // The family is None for the theme skin and set for a pattern
+        let mut current_skin: Option<(Option<ColorFamily>, Vec<Vec<usize>>)> = None;
+
+        for (line_index, raw_line) in text.lines().enumerate() {
+            let line = raw_line.split("//").next().unwrap_or("").trim();
+            let line_number = line_index + 1;
+            if line.is_empty() {
+                continue;
+            }
+
+            if let (Some((_, rows)), true) = (
+                current_skin.as_mut(),
+                line.chars().all(|c| c.is_ascii_digit()),
+            ) {
+                rows.push(
+                    line.chars()
+                        .map(|c| c.to_digit(10).unwrap() as usize)
+                        .collect(),
+                );
+                continue;
+            }
+            if let (Some((_, shades)), true) = (current_palette.as_mut(), line.starts_with('#')) {
+                shades.push(
+                    parse_color(line)
+                        .map_err(|message| format!("line {}: {}", line_number, message))?,
+                );
+                continue;
+            }
+
+            let mut words = line.split_whitespace();
+            let keyword = words.next().unwrap();
+            let value = words.next();
+            let is_in_block = current_skin.is_some() || current_palette.is_some();
+            match (keyword, value, is_in_block) {
+                ("name", Some(name), false) => builder.name = name.to_string(),
+                ("background", Some(color), false) => {
+                    builder.theme.background_color = parse_line_color(color, line_number)?
+                }
+                ("letterbox", Some(color), false) => {
+                    builder.theme.letterbox_color = parse_line_color(color, line_number)?
+                }
+                ("hud-text", Some(color), false) => {
+                    builder.theme.hud_text_color = parse_line_color(color, line_number)?
+                }
+                ("hud-outline", Some(color), false) => {
+                    builder.theme.hud_outline_color = parse_line_color(color, line_number)?
+                }
+                ("palette", Some(family_name), false) => {
+                    current_palette = Some((parse_family(family_name, line_number)?, Vec::new()));
+                }
+                ("skin", None, false) => current_skin = Some((None, Vec::new())),
+                ("pattern", Some(family_name), false) => {
+                    current_skin =
+                        Some((Some(parse_family(family_name, line_number)?), Vec::new()));
+                }
+                ("end", None, true) => {
+                    if let Some(palette) = current_palette.take() {
+                        builder.palettes.push(palette);
+                    }
+                    match current_skin.take() {
+                        Some((Some(family), rows)) => builder.patterns.push((family, rows)),
+                        Some((None, rows)) => builder.skin_rows = rows,
+                        None => {}
+                    }
+                }
+                _ => return Err(format!("line {}: unexpected '{}'", line_number, line)),
+            }
+        }
+
+        if current_skin.is_some() || current_palette.is_some() {
+            return Err("Theme is missing 'end'".to_string());
+        }
+
+        builder.build()
+    }
+}
+
+// Starts from the classic theme, so a file only has to list what it changes
+struct ThemeBuilder {
+    theme: Theme,
+    name: String,
+    palettes: Vec<(ColorFamily, Vec<Color>)>,
+    skin_rows: Vec<Vec<usize>>,
+    patterns: Vec<(ColorFamily, Vec<Vec<usize>>)>,
+}
+
+impl ThemeBuilder {
+    fn new() -> Self {
+        ThemeBuilder {
+            theme: Theme::CLASSIC,
+            name: "custom".to_string(),
+            palettes: Vec::new(),
+            skin_rows: Vec::new(),
+            patterns: Vec::new(),
+        }
+    }
+
+    fn build(self) -> Result<Theme, String> {
+        let mut theme = self.theme;
+        theme.name = self.name.leak();
+
+        if !self.skin_rows.is_empty() {
+            theme.skin = build_skin(&self.skin_rows)?;
+        }
+
+        if !self.palettes.is_empty() || !self.patterns.is_empty() {
+            let mut palettes = Vec::new();
+            for family in ColorFamily::ALL {
+                let mut palette = match self
+                    .palettes
+                    .iter()
+                    .rev() // A later palette for the same family wins
+                    .find(|palette| palette.0 == family)
+                {
+                    Some((family, shades)) => Palette {
+                        family: *family,
+                        shades: shades.clone().leak(),
+                        pattern: None,
+                    },
+                    // Families the file leaves out keep their classic shades
+                    None => theme.get_palette(family).clone(),
+                };
+                if let Some((_, rows)) = self
+                    .patterns
+                    .iter()
+                    .rev()
+                    .find(|pattern| pattern.0 == family)
+                {
+                    palette.pattern = Some(build_skin(rows)?);
+                }
+                palettes.push(palette);
+            }
+            theme.palettes = palettes.leak();
+        }
+
+        ThemeBuilder::validate(&theme)?;
+
+        Ok(theme)
+    }
+
+    fn validate(theme: &Theme) -> Result<(), String> {
+        for palette in theme.palettes {
+            let skin = palette.pattern.as_ref().unwrap_or(&theme.skin);
+            let max_shade_index = skin.shade_indices.iter().max().copied().unwrap_or(0);
+            if palette.shades.len() <= max_shade_index {
+                return Err(format!(
+                    "Skin uses shade {}, but a palette only has {}",
+                    max_shade_index,
+                    palette.shades.len()
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Opaque result, for renderers that can't blend
+pub fn mix_colors(base: Color, color: Color, amount: f32) -> Color {
+    let amount = amount.clamp(0.0, 1.0);
+    let mix = |channel: f32, base_channel: f32| base_channel + (channel - base_channel) * amount;
+
+    Color::new(
+        mix(color.r, base.r),
+        mix(color.g, base.g),
+        mix(color.b, base.b),
+        1.0,
+    )
+}
+
+fn build_skin(rows: &[Vec<usize>]) -> Result<Skin, String> {
+    let width = rows.first().map_or(0, |row| row.len());
+    if width == 0 {
+        return Err("Skin is empty".to_string());
+    }
+    if rows.iter().any(|row| row.len() != width) {
+        return Err("Skin rows have different lengths".to_string());
+    }
+
+    Ok(Skin {
+        width,
+        shade_indices: rows.concat().leak(),
+    })
+}
+
+fn parse_family(name: &str, line_number: usize) -> Result<ColorFamily, String> {
+    ColorFamily::from_name(name).ok_or(format!(
+        "line {}: unknown color family {}",
+        line_number, name
+    ))
+}
+
+fn parse_color(text: &str) -> Result<Color, String> {
+    let hex = text.strip_prefix('#').unwrap_or(text);
+    let value = match hex.len() {
+        6 => u32::from_str_radix(hex, 16).ok(),
+        _ => None,
+    };
+
+    match value {
+        Some(value) => {
+            let [_, r, g, b] = value.to_be_bytes();
+            Ok(Color::from_rgba(r, g, b, 255))
+        }
+        None => Err(format!("invalid color {}, expected #rrggbb", text)),
+    }
+}
+
+fn parse_line_color(text: &str, line_number: usize) -> Result<Color, String> {
+    parse_color(text).map_err(|message| format!("line {}: {}", line_number, message))
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    const SMALL_THEME: &str = "
+        name small // Comments work like in piece sets
+        background #000010
+        hud-text #ffffff
+
+        palette red
+        #ff0000
+        #aa0000
+        end
+        palette blue
+        #0000ff
+        #0000aa
+        end
+        palette green
+        #00ff00
+        #00aa00
+        end
+        palette yellow
+        #ffff00
+        #aaaa00
+        end
+
+        skin
+        01
+        10
+        00
+        end
+    ";
+
+    #[test]
+    fn classic_theme() {
+        let theme = Theme::CLASSIC;
+
+        assert_eq!(theme.get_palette(ColorFamily::Red).shades, &RED_VAR);
+        assert_eq!(theme.skin.get_shade_index(1, 0), NATURAL[0][1]);
+        assert_eq!(theme.skin.get_shade_index(7, 8), NATURAL[2][1]); // Repeats
+        assert_eq!(ThemeBuilder::validate(&theme), Ok(()));
+    }
+
+    #[test]
+    fn colorblind_theme() {
+        let theme = Theme::COLORBLIND;
+
+        assert_eq!(ThemeBuilder::validate(&theme), Ok(()));
+        // Every family has its own shape
+        for (index, family) in ColorFamily::ALL.iter().enumerate() {
+            for other in &ColorFamily::ALL[index + 1..] {
+                assert_ne!(theme.get_skin(*family), theme.get_skin(*other));
+            }
+        }
+        assert_eq!(theme.get_skin(ColorFamily::Yellow), &theme.skin);
+        assert_eq!(theme.get_skin(ColorFamily::Red).get_shade_index(2, 2), 3);
+    }
+
+    #[test]
+    fn get_color() {
+        let theme = Theme::parse(SMALL_THEME).unwrap();
+
+        assert_eq!(
+            theme.get_color(GrainColor::new(ColorFamily::Red, 1)),
+            Color::from_rgba(170, 0, 0, 255)
+        );
+        // Classic grains have a shade this theme doesn't
+        assert_eq!(
+            theme.get_color(GrainColor::new(ColorFamily::Red, 2)),
+            Color::from_rgba(170, 0, 0, 255)
+        );
+        assert_eq!(
+            Theme::CLASSIC.get_color(GrainColor::new(ColorFamily::Blue, 2)),
+            BLUE_VAR[2]
+        );
+    }
+
+    #[test]
+    fn get_display_color() {
+        let theme = Theme::parse(SMALL_THEME).unwrap();
+
+        assert_eq!(
+            theme.get_display_color(ColorFamily::Red),
+            Color::from_rgba(255, 0, 0, 255)
+        );
+    }
+
+    #[test]
+    fn get_faded_color() {
+        let theme = Theme::CLASSIC;
+        let red = GrainColor::new(ColorFamily::Red, 0);
+
+        assert_eq!(theme.get_faded_color(red, 1.0), RED_VAR[0]);
+        assert_eq!(theme.get_faded_color(red, 0.0), BACKGROUND_COLOR);
+        let half_faded = theme.get_faded_color(red, 0.5);
+        assert!((half_faded.r - (RED_VAR[0].r + BACKGROUND_COLOR.r) / 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn mix_colors_clamps() {
+        let black = Color::new(0.0, 0.0, 0.0, 1.0);
+        let white = Color::new(1.0, 1.0, 1.0, 1.0);
+
+        assert_eq!(
+            mix_colors(black, white, 0.25),
+            Color::new(0.25, 0.25, 0.25, 1.0)
+        );
+        assert_eq!(mix_colors(black, white, 2.0), white);
+        assert_eq!(mix_colors(black, white, -1.0), black);
+    }
+
+    #[test]
+    fn parse() {
+        let theme = Theme::parse(SMALL_THEME).unwrap();
+
+        assert_eq!(theme.name, "small");
+        assert_eq!(theme.background_color, Color::from_rgba(0, 0, 16, 255));
+        assert_eq!(theme.hud_text_color, Color::from_rgba(255, 255, 255, 255));
+        assert_eq!(theme.hud_outline_color, Theme::CLASSIC.hud_outline_color);
+        assert_eq!(theme.palettes.len(), 8);
+        assert_eq!(
+            theme.get_palette(ColorFamily::Blue).shades,
+            &[
+                Color::from_rgba(0, 0, 255, 255),
+                Color::from_rgba(0, 0, 170, 255)
+            ]
+        );
+        assert_eq!(theme.skin.width, 2);
+        assert_eq!(theme.skin.get_shade_index(1, 1), 0);
+        assert_eq!(theme.skin.get_shade_index(0, 1), 1);
+        assert_eq!(theme.skin.get_shade_index(1, 3), 1); // Three rows high
+    }
+
+    #[test]
+    fn parse_patterns() {
+        let theme = Theme::parse(&format!("{}\npattern blue\n0\n1\nend", SMALL_THEME)).unwrap();
+
+        assert_eq!(theme.get_skin(ColorFamily::Red), &theme.skin);
+        assert_eq!(theme.get_skin(ColorFamily::Blue).width, 1);
+        assert_eq!(theme.get_skin(ColorFamily::Blue).get_shade_index(4, 3), 1);
+    }
+
+    #[test]
+    fn parse_patterns_over_classic_palettes() {
+        let theme = Theme::parse("pattern green\n2\nend").unwrap();
+
+        assert_eq!(theme.get_palette(ColorFamily::Green).shades, &GREEN_VAR);
+        assert_eq!(theme.get_skin(ColorFamily::Green).get_shade_index(0, 0), 2);
+        assert_eq!(theme.get_skin(ColorFamily::Red), &Theme::CLASSIC.skin);
+    }
+
+    #[test]
+    fn parse_missing_palettes() {
+        let theme = Theme::parse("palette red\n#ff0000\n#aa0000\n#550000\nend").unwrap();
+
+        assert_eq!(theme.palettes.len(), 8);
+        assert_eq!(
+            theme.get_palette(ColorFamily::Red).shades[0],
+            Color::from_rgba(255, 0, 0, 255)
+        );
+        assert_eq!(theme.get_palette(ColorFamily::Cyan).shades, &CYAN_VAR);
+    }
+
+    #[test]
+    fn parse_only_colors() {
+        let theme = Theme::parse("background #102030").unwrap();
+
+        assert_eq!(theme.palettes, Theme::CLASSIC.palettes);
+        assert_eq!(theme.skin, Theme::CLASSIC.skin);
+    }
+
+    #[test]
+    fn parse_invalid() {
+        for text in [
+            "background blue",
+            "palette violet\n#ff00ff\nend",
+            "skin\n012\n01\nend",
+            "skin\n09\nend", // Classic palettes only have three shades
+            "palette red\n#ff0000",
+            "end",
+            "unknown #ffffff",
+            "pattern violet\n0\nend",
+            "pattern red\nend",
+            "pattern red\n03\nend", // Classic palettes only have three shades
+            "pattern red\n0",
+        ] {
+            assert!(Theme::parse(text).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn parse_shared_shade() {
+        // Grains remember their family, so palettes can look alike
+        let text = SMALL_THEME
+            .replace("#0000aa", "#aa0000")
+            .replace("#0000ff", "#1a181b");
+
+        assert!(Theme::parse(&text).is_ok());
+    }
+
+    #[test]
+    fn load() {
+        assert_eq!(Theme::load("classic"), Ok(&Theme::CLASSIC));
+        assert_eq!(Theme::load("colorblind"), Ok(&Theme::COLORBLIND));
+        assert!(Theme::load("missing_theme.txt").is_err());
+    }
+
+    #[test]
+    fn load_shipped_themes() {
+        let themes_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("assets/themes");
+
+        for entry in fs::read_dir(themes_dir).unwrap() {
+            let path = entry.unwrap().path();
+            assert!(Theme::load(path.to_str().unwrap()).is_ok(), "{:?}", path);
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Effect {
+    ClearVanish(Vec<(i32, i32)>), // Cleared cells flash and fade out
+    ScorePopup {
+        text: String,
+        position: (i32, i32),
+        line: u32, // Labels stack up in lines over the points
+    },
+    ScreenShake {
+        amplitude: f32, // In grains
+    },
+    Banner(String), // Big text over the middle of the map
+    GameOverFade,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ScheduledEffect {
+    effect: Effect,
+    start: Duration,
+    duration: Duration,
+}
+
+impl ScheduledEffect {
+    fn get_end(&self) -> Duration {
+        self.start + self.duration
+    }
+}
+
+// Effects run by the clock next to the game, nothing waits for them to finish
+#[derive(Debug, Default)]
+pub struct Timeline {
+    effects: Vec<ScheduledEffect>,
+}
+
+impl Timeline {
+    pub fn new() -> Timeline {
+        Timeline {
+            effects: Vec::new(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.effects.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.effects.is_empty()
+    }
+
+    pub fn schedule(&mut self, effect: Effect, start: Duration, duration: Duration) {
+        self.effects.push(ScheduledEffect {
+            effect,
+            start,
+            duration,
+        });
+    }
+
+    // Forgets the effects that are over
+    pub fn advance(&mut self, now: Duration) {
+        self.effects.retain(|scheduled| scheduled.get_end() > now);
+    }
+
+    // Effects that have started with how far along they are, from 0.0 up to 1.0
+    pub fn get_active(&self, now: Duration) -> Vec<(&Effect, f32)> {
+        self.effects
+            .iter()
+            .filter(|scheduled| scheduled.start <= now && now < scheduled.get_end())
+            .map(|scheduled| {
+                (
+                    &scheduled.effect,
+                    (now - scheduled.start).as_secs_f32() / scheduled.duration.as_secs_f32(),
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn get_active_progress() {
+        let mut timeline = Timeline::new();
+        timeline.schedule(Effect::GameOverFade, SECOND, 2 * SECOND);
+
+        assert!(timeline.get_active(Duration::ZERO).is_empty()); // Not started yet
+        assert_eq!(
+            timeline.get_active(SECOND),
+            Vec::from([(&Effect::GameOverFade, 0.0)])
+        );
+        assert_eq!(
+            timeline.get_active(2 * SECOND),
+            Vec::from([(&Effect::GameOverFade, 0.5)])
+        );
+        assert!(timeline.get_active(3 * SECOND).is_empty());
+    }
+
+    #[test]
+    fn effects_run_side_by_side() {
+        let mut timeline = Timeline::new();
+        timeline.schedule(Effect::GameOverFade, Duration::ZERO, 4 * SECOND);
+        timeline.schedule(Effect::ScreenShake { amplitude: 2.0 }, SECOND, 2 * SECOND);
+
+        assert_eq!(
+            timeline.get_active(2 * SECOND),
+            Vec::from([
+                (&Effect::GameOverFade, 0.5),
+                (&Effect::ScreenShake { amplitude: 2.0 }, 0.5)
+            ])
+        );
+    }
+
+    #[test]
+    fn advance_drops_finished_effects() {
+        let mut timeline = Timeline::new();
+        timeline.schedule(Effect::GameOverFade, Duration::ZERO, SECOND);
+        timeline.schedule(Effect::ClearVanish(Vec::new()), SECOND, SECOND);
+
+        timeline.advance(SECOND);
+        assert_eq!(timeline.effects.len(), 1);
+
+        timeline.advance(2 * SECOND);
+        assert!(timeline.is_empty());
+    }
+
+    #[test]
+    fn zero_duration_never_active() {
+        let mut timeline = Timeline::new();
+        timeline.schedule(Effect::GameOverFade, SECOND, Duration::ZERO);
+
+        assert!(timeline.get_active(SECOND).is_empty());
+    }
+
+    #[test]
+    fn clear() {
+        let mut timeline = Timeline::new();
+        timeline.schedule(Effect::GameOverFade, Duration::ZERO, SECOND);
+
+        timeline.clear();
+
+        assert!(timeline.is_empty());
+    }
+}
@@ -0,0 +1,499 @@
+use std::{
+    fs,
+    future::{ready, Future},
+};
+
+use image::{codecs::png::PngEncoder, ColorType, ImageEncoder};
+use macroquad::{color::Color, texture::Image};
+
+use crate::{
+    constants::colors::BACKGROUND_COLOR,
+    controllers::graphic_controller::GraphicController,
+    objects::{
+        color_family::ColorFamily,
+        field::Field,
+        particle::Particle,
+        theme::{mix_colors, Theme},
+    },
+    renderers::{
+        bitmap_font::{get_glyph, is_pixel_set, GLYPH_HEIGHT, GLYPH_SPACING, GLYPH_WIDTH},
+        renderer::Renderer,
+        viewport::Viewport,
+    },
+};
+
+// Draws into an RGBA buffer in memory, so frames can be made without a window or GPU
+pub struct FramebufferRenderer {
+    image: Image,
+    frame_count: u32,
+}
+
+impl FramebufferRenderer {
+    pub fn new(width: i32, height: i32) -> FramebufferRenderer {
+        FramebufferRenderer {
+            image: Image::gen_image_color(width as u16, height as u16, BACKGROUND_COLOR),
+            frame_count: 0,
+        }
+    }
+
+    pub fn from_image(image: Image) -> FramebufferRenderer {
+        FramebufferRenderer {
+            image,
+            frame_count: 0,
+        }
+    }
+
+    pub fn get_image(&self) -> &Image {
+        &self.image
+    }
+
+    pub fn get_pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        self.image.get_image_data()[(y * self.image.width as u32 + x) as usize]
+    }
+
+    pub fn get_frame_count(&self) -> u32 {
+        self.frame_count
+    }
+
+    pub fn save_png(&self, path: &str) -> Result<(), String> {
+        let mut png = Vec::new();
+        PngEncoder::new(&mut png)
+            .write_image(
+                &self.image.bytes,
+                self.image.width as u32,
+                self.image.height as u32,
+                ColorType::Rgba8,
+            )
+            .map_err(|error| format!("{}: {}", path, error))?;
+
+        fs::write(path, png).map_err(|error| format!("{}: {}", path, error))
+    }
+
+    // Pixel rows and columns covered by the rect, cut to the image
+    fn get_pixel_bounds(&self, x: f32, y: f32, width: f32, height: f32) -> (i32, i32, i32, i32) {
+        let x_start = (x.round() as i32).clamp(0, self.image.width as i32);
+        let y_start = (y.round() as i32).max(0);
+        let x_end = ((x + width).round() as i32).min(self.image.width as i32);
+        let y_end = ((y + height).round() as i32).min(self.image.height as i32);
+
+        (x_start, y_start, x_end.max(x_start), y_end)
+    }
+
+    fn fill_rect(&mut self, x: f32, y: f32, width: f32, height: f32, color: Color) {
+        let (x_start, y_start, x_end, y_end) = self.get_pixel_bounds(x, y, width, height);
+
+        let pixel: [u8; 4] = color.into();
+        let image_width = self.image.width as i32;
+        let pixels = self.image.get_image_data_mut();
+        for pixel_y in y_start..y_end {
+            let row_start = (pixel_y * image_width) as usize;
+            pixels[row_start + x_start as usize..row_start + x_end as usize].fill(pixel);
+        }
+    }
+
+    fn blend_rect(
+        &mut self,
+        (x, y, width, height): (f32, f32, f32, f32),
+        color: Color,
+        opacity: f32,
+    ) {
+        let (x_start, y_start, x_end, y_end) = self.get_pixel_bounds(x, y, width, height);
+
+        let image_width = self.image.width as i32;
+        let pixels = self.image.get_image_data_mut();
+        for pixel_y in y_start..y_end {
+            let row_start = (pixel_y * image_width) as usize;
+            for pixel in &mut pixels[row_start + x_start as usize..row_start + x_end as usize] {
+                let current = Color::from_rgba(pixel[0], pixel[1], pixel[2], pixel[3]);
+                *pixel = mix_colors(current, color, opacity).into();
+            }
+        }
+    }
+
+    fn draw_grain(&mut self, x: i32, y: i32, viewport: &Viewport, color: Color) {
+        if !viewport.contains(x, y) {
+            return; // Would land on the bars around the map
+        }
+        let (screen_x, screen_y) = viewport.map_to_screen(x, y);
+        self.fill_rect(
+            screen_x,
+            screen_y,
+            viewport.get_grain_size(),
+            viewport.get_grain_size(),
+            color,
+        );
+    }
+
+    fn get_glyph_scale(font_size: u16) -> i32 {
+        (font_size as i32 / 12).max(1)
+    }
+}
+
+impl Renderer for FramebufferRenderer {
+    fn get_screen_size(&self) -> (f32, f32) {
+        (self.image.width as f32, self.image.height as f32)
+    }
+
+    fn draw_background(&mut self, viewport: &Viewport, theme: &Theme) {
+        let (screen_width, screen_height) = self.get_screen_size();
+        self.fill_rect(0.0, 0.0, screen_width, screen_height, theme.letterbox_color);
+        let (x, y, width, height) = viewport.get_map_rect();
+        self.fill_rect(x, y, width, height, theme.background_color);
+    }
+
+    fn draw_fields(&mut self, fields: &[&Field], viewport: &Viewport, theme: &Theme) {
+        for field in fields {
+            if let Some(color) = field.get_color() {
+                self.draw_grain(
+                    field.get_x(),
+                    field.get_y(),
+                    viewport,
+                    theme.get_color(color),
+                );
+            }
+        }
+    }
+
+    fn draw_block(
+        &mut self,
+        block_schema_color: (Vec<(i32, i32)>, ColorFamily),
+        viewport: &Viewport,
+        block_chunk_side: i32,
+        theme: &Theme,
+    ) {
+        let (block_schema, family) = block_schema_color;
+        for (x, y, color) in
+            GraphicController::get_skin_for_schema(block_schema, family, block_chunk_side, theme)
+        {
+            self.draw_grain(x, y, viewport, theme.get_color(color));
+        }
+    }
+
+    fn draw_block_miniature(
+        &mut self,
+        block_schema_color: (Vec<(i32, i32)>, Color),
+        origin_point: (f32, f32),
+        preview_block_chunk_side: i32,
+        viewport: &Viewport,
+    ) {
+        let (block_schema, color) = block_schema_color;
+        let grain_size = viewport.get_grain_size();
+        for (x, y) in block_schema {
+            self.fill_rect(
+                origin_point.0 + x as f32 * grain_size,
+                origin_point.1 + y as f32 * grain_size,
+                preview_block_chunk_side as f32 * grain_size,
+                preview_block_chunk_side as f32 * grain_size,
+                color,
+            );
+        }
+    }
+
+    fn draw_text(&mut self, text: &str, x: f32, y: f32, font_size: u16, color: Color) {
+        let scale = FramebufferRenderer::get_glyph_scale(font_size);
+        let top = y - (GLYPH_HEIGHT * scale) as f32; // Text is placed by its baseline
+
+        for (index, character) in text.chars().enumerate() {
+            let glyph = get_glyph(character);
+            let left = x + (index as i32 * (GLYPH_WIDTH + GLYPH_SPACING) * scale) as f32;
+            for glyph_y in 0..GLYPH_HEIGHT {
+                for glyph_x in 0..GLYPH_WIDTH {
+                    if is_pixel_set(&glyph, glyph_x, glyph_y) {
+                        self.fill_rect(
+                            left + (glyph_x * scale) as f32,
+                            top + (glyph_y * scale) as f32,
+                            scale as f32,
+                            scale as f32,
+                            color,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    // Faded into the background color rather than blended, so they look the same as in the terminal
+    fn draw_particles(&mut self, particles: &[Particle], viewport: &Viewport, theme: &Theme) {
+        for particle in particles {
+            let (x, y) = particle.get_cell();
+            self.draw_grain(
+                x,
+                y,
+                viewport,
+                theme.get_faded_color(particle.get_color(), particle.get_opacity()),
+            );
+        }
+    }
+
+    fn draw_overlay(&mut self, rect: (f32, f32, f32, f32), color: Color, opacity: f32) {
+        self.blend_rect(rect, color, opacity);
+    }
+
+    fn measure_text(&self, text: &str, font_size: u16) -> (f32, f32) {
+        let scale = FramebufferRenderer::get_glyph_scale(font_size);
+        let characters = text.chars().count() as i32;
+
+        (
+            ((characters * (GLYPH_WIDTH + GLYPH_SPACING) - GLYPH_SPACING) * scale).max(0) as f32,
+            (GLYPH_HEIGHT * scale) as f32,
+        )
+    }
+
+    fn present(&mut self) -> impl Future<Output = ()> {
+        self.frame_count += 1;
+
+        ready(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::env::temp_dir;
+
+    use macroquad::prelude::ImageFormat;
+
+    use crate::{
+        constants::colors::{BLACK, LETTERBOX_COLOR, RED, RED_VAR, WHITE},
+        objects::color_family::GrainColor,
+        utils::block_on::block_on,
+    };
+
+    use super::*;
+
+    const RED_GRAIN: Option<GrainColor> = Some(GrainColor::new(ColorFamily::Red, 0));
+
+    fn to_bytes(color: Color) -> [u8; 4] {
+        color.into()
+    }
+
+    fn get_viewport(renderer: &FramebufferRenderer, map_size: (i32, i32)) -> Viewport {
+        Viewport::fit(renderer.get_screen_size(), map_size, 1)
+    }
+
+    #[test]
+    fn draw_background() {
+        let mut renderer = FramebufferRenderer::new(4, 3);
+        renderer.fill_rect(0.0, 0.0, 4.0, 3.0, RED);
+
+        renderer.draw_background(&get_viewport(&renderer, (4, 3)), &Theme::CLASSIC);
+
+        assert_eq!(renderer.get_pixel(3, 2), to_bytes(BACKGROUND_COLOR));
+    }
+
+    #[test]
+    fn draw_background_theme() {
+        let mut renderer = FramebufferRenderer::new(6, 2);
+        let theme = Theme {
+            background_color: RED,
+            letterbox_color: WHITE,
+            ..Theme::CLASSIC
+        };
+
+        renderer.draw_background(&get_viewport(&renderer, (1, 1)), &theme);
+
+        assert_eq!(renderer.get_pixel(0, 0), to_bytes(WHITE));
+        assert_eq!(renderer.get_pixel(3, 0), to_bytes(RED));
+    }
+
+    #[test]
+    fn draw_background_letterbox() {
+        let mut renderer = FramebufferRenderer::new(6, 2);
+
+        renderer.draw_background(&get_viewport(&renderer, (1, 1)), &Theme::CLASSIC);
+
+        assert_eq!(renderer.get_pixel(0, 0), to_bytes(LETTERBOX_COLOR));
+        assert_eq!(renderer.get_pixel(2, 1), to_bytes(BACKGROUND_COLOR));
+        assert_eq!(renderer.get_pixel(3, 0), to_bytes(BACKGROUND_COLOR));
+        assert_eq!(renderer.get_pixel(5, 1), to_bytes(LETTERBOX_COLOR));
+    }
+
+    #[test]
+    fn draw_fields() {
+        let mut renderer = FramebufferRenderer::new(10, 10);
+        let field = Field::new(1, 2, RED_GRAIN, 1);
+
+        renderer.draw_fields(&[&field], &get_viewport(&renderer, (5, 5)), &Theme::CLASSIC);
+
+        for (x, y) in [(2, 4), (3, 4), (2, 5), (3, 5)] {
+            assert_eq!(renderer.get_pixel(x, y), to_bytes(RED));
+        }
+        assert_eq!(renderer.get_pixel(4, 4), to_bytes(BACKGROUND_COLOR));
+    }
+
+    #[test]
+    fn draw_fields_scaled_and_centered() {
+        let mut renderer = FramebufferRenderer::new(8, 4);
+        let field = Field::new(1, 0, RED_GRAIN, 1);
+
+        renderer.draw_fields(&[&field], &get_viewport(&renderer, (2, 2)), &Theme::CLASSIC);
+
+        for (x, y) in [(4, 0), (5, 0), (4, 1), (5, 1)] {
+            assert_eq!(renderer.get_pixel(x, y), to_bytes(RED));
+        }
+        assert_eq!(renderer.get_pixel(6, 0), to_bytes(BACKGROUND_COLOR));
+    }
+
+    #[test]
+    fn draw_fields_theme() {
+        let mut renderer = FramebufferRenderer::new(1, 1);
+        let field = Field::new(0, 0, RED_GRAIN, 1);
+        let viewport = get_viewport(&renderer, (1, 1));
+
+        renderer.draw_fields(&[&field], &viewport, &Theme::COLORBLIND);
+
+        assert_eq!(
+            renderer.get_pixel(0, 0),
+            to_bytes(Theme::COLORBLIND.get_display_color(ColorFamily::Red))
+        );
+    }
+
+    #[test]
+    fn draw_fields_outside_map() {
+        let mut renderer = FramebufferRenderer::new(4, 4);
+        let viewport = Viewport::fit((4.0, 4.0), (2, 1), 1); // Bars above and below the map
+
+        renderer.draw_fields(
+            &[
+                &Field::new(0, -1, RED_GRAIN, 1),
+                &Field::new(2, 0, RED_GRAIN, 1),
+            ],
+            &viewport,
+            &Theme::CLASSIC,
+        );
+
+        assert!(renderer
+            .get_image()
+            .get_image_data()
+            .iter()
+            .all(|pixel| *pixel == to_bytes(BACKGROUND_COLOR)));
+    }
+
+    #[test]
+    fn fill_rect_past_right_edge() {
+        let mut renderer = FramebufferRenderer::new(2, 2);
+        let before = renderer.get_image().clone();
+
+        renderer.fill_rect(3.0, 0.0, 1.0, 2.0, WHITE); // A shaken map can reach past the screen
+
+        assert_eq!(
+            renderer.get_image().get_image_data(),
+            before.get_image_data()
+        );
+    }
+
+    #[test]
+    fn draw_particles_fade() {
+        let mut renderer = FramebufferRenderer::new(2, 1);
+        let viewport = get_viewport(&renderer, (2, 1));
+        let mut fading = Particle::new(1.2, 0.7, (0.0, 0.0), RED_GRAIN.unwrap(), 4);
+        fading.tick(0.0);
+
+        renderer.draw_particles(
+            &[
+                Particle::new(0.5, 0.5, (0.0, 0.0), RED_GRAIN.unwrap(), 4),
+                fading,
+            ],
+            &viewport,
+            &Theme::CLASSIC,
+        );
+
+        assert_eq!(renderer.get_pixel(0, 0), to_bytes(RED));
+        assert_eq!(
+            renderer.get_pixel(1, 0),
+            to_bytes(Theme::CLASSIC.get_faded_color(RED_GRAIN.unwrap(), 0.75))
+        );
+    }
+
+    #[test]
+    fn draw_overlay_blends() {
+        let mut renderer = FramebufferRenderer::new(4, 4);
+        renderer.fill_rect(0.0, 0.0, 4.0, 4.0, BLACK);
+
+        renderer.draw_overlay((1.0, 1.0, 2.0, 2.0), WHITE, 0.5);
+
+        assert_eq!(renderer.get_pixel(0, 0), to_bytes(BLACK));
+        assert_eq!(
+            renderer.get_pixel(1, 1),
+            to_bytes(mix_colors(BLACK, WHITE, 0.5))
+        );
+        assert_eq!(
+            renderer.get_pixel(2, 2),
+            to_bytes(mix_colors(BLACK, WHITE, 0.5))
+        );
+        assert_eq!(renderer.get_pixel(3, 3), to_bytes(BLACK));
+    }
+
+    #[test]
+    fn draw_block_uses_skin() {
+        let mut renderer = FramebufferRenderer::new(10, 10);
+        let viewport = get_viewport(&renderer, (10, 10));
+
+        renderer.draw_block(
+            (Vec::from([(0, 0)]), ColorFamily::Red),
+            &viewport,
+            6,
+            &Theme::CLASSIC,
+        );
+
+        for (x, y, color) in GraphicController::get_skin_for_schema(
+            Vec::from([(0, 0)]),
+            ColorFamily::Red,
+            6,
+            &Theme::CLASSIC,
+        ) {
+            let pixel = renderer.get_pixel(x as u32, y as u32);
+            assert_eq!(pixel, to_bytes(RED_VAR[color.get_shade()]));
+        }
+    }
+
+    #[test]
+    fn draw_text() {
+        let mut renderer = FramebufferRenderer::new(20, 20);
+
+        renderer.draw_text("L", 0.0, 7.0, 12, WHITE);
+
+        assert_eq!(renderer.get_pixel(0, 0), to_bytes(WHITE));
+        assert_eq!(renderer.get_pixel(4, 6), to_bytes(WHITE));
+        assert_eq!(renderer.get_pixel(4, 0), to_bytes(BACKGROUND_COLOR));
+    }
+
+    #[test]
+    fn measure_text() {
+        let renderer = FramebufferRenderer::new(1, 1);
+
+        assert_eq!(renderer.measure_text("ab", 12), (11.0, 7.0));
+        assert_eq!(renderer.measure_text("ab", 24), (22.0, 14.0));
+        assert_eq!(renderer.measure_text("", 12), (0.0, 7.0));
+    }
+
+    #[test]
+    fn present() {
+        let mut renderer = FramebufferRenderer::new(1, 1);
+
+        block_on(renderer.present());
+
+        assert_eq!(renderer.get_frame_count(), 1);
+    }
+
+    #[test]
+    fn save_png() {
+        let mut renderer = FramebufferRenderer::new(3, 2);
+        renderer.fill_rect(0.0, 0.0, 1.0, 1.0, RED);
+        let path = temp_dir().join("sand_tetris_save_png.png");
+
+        renderer.save_png(path.to_str().unwrap()).unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        let loaded = Image::from_file_with_format(&bytes, Some(ImageFormat::Png)).unwrap();
+        assert_eq!(loaded.width, 3);
+        assert_eq!(loaded.height, 2);
+        assert_eq!(loaded.bytes, renderer.get_image().bytes); // Not upside down
+    }
+
+    #[test]
+    fn save_png_invalid_path() {
+        let renderer = FramebufferRenderer::new(1, 1);
+
+        assert!(renderer.save_png("/nonexistent/dir/frame.png").is_err());
+    }
+}
@@ -0,0 +1,185 @@
+// Plays scripted games with a seeded rng and compares chosen frames with the images in tests/goldens.
+// Run with UPDATE_GOLDENS=1 to write the current frames as the new goldens.
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use macroquad::{color::Color, input::KeyCode, prelude::ImageFormat, texture::Image};
+
+use crate::{
+    constants::{animation_constants::HEADLESS_FRAME_TIME, GameRules, TetrisConstants},
+    controllers::game_controller::GameController,
+    renderers::framebuffer_renderer::FramebufferRenderer,
+    utils::{block_on::block_on, clock::FakeClock, tetris_rng::SeededTetrisRng},
+};
+
+const MATCHING_PIXEL_DIM: f32 = 0.25;
+const MISMATCH_COLOR: Color = Color::new(1.0, 0.0, 1.0, 1.0);
+
+struct GoldenScript {
+    seed: u64,
+    moves: Vec<(u32, KeyCode)>, // Tick after which the key is pressed
+    captures: Vec<(u32, &'static str)>,
+}
+
+fn play_and_capture(script: &GoldenScript) -> Vec<(&'static str, Image)> {
+    let constants = TetrisConstants::new(GameRules::CLASSIC);
+    let mut rng = SeededTetrisRng::new(script.seed);
+    let mut renderer = FramebufferRenderer::new(
+        constants.map_width * constants.grain_side_size,
+        constants.map_height * constants.grain_side_size,
+    );
+    let clock = FakeClock::new();
+    let mut gc = GameController::new(&mut rng, &mut renderer, &clock, &constants);
+    gc.reset_game();
+
+    let last_tick = script
+        .captures
+        .iter()
+        .map(|capture| capture.0)
+        .max()
+        .unwrap();
+    let mut frames = Vec::new();
+    for tick in 1..=last_tick {
+        block_on(gc.tick()); // Draws the frame before updating the game
+        clock.advance(HEADLESS_FRAME_TIME);
+        for (_, name) in script.captures.iter().filter(|capture| capture.0 == tick) {
+            frames.push((*name, gc.get_renderer().get_image().clone()));
+        }
+        for (_, key) in script.moves.iter().filter(|key_press| key_press.0 == tick) {
+            gc.do_move(*key);
+        }
+    }
+
+    frames
+}
+
+fn get_golden_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/goldens")
+        .join(format!("{}.png", name))
+}
+
+fn get_failure_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("target/golden_failures")
+        .join(format!("{}.png", name))
+}
+
+fn save_image(image: &Image, path: &Path) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    FramebufferRenderer::from_image(image.clone())
+        .save_png(path.to_str().unwrap())
+        .unwrap();
+}
+
+// Mismatching pixels are highlighted, the rest is dimmed so they stand out
+fn get_diff_image(expected: &Image, actual: &Image) -> (Image, usize) {
+    let mut diff = actual.clone();
+    let mut mismatches = 0;
+    let expected_pixels = expected.get_image_data();
+
+    for (index, pixel) in diff.get_image_data_mut().iter_mut().enumerate() {
+        if expected_pixels.get(index) == Some(pixel) {
+            let dim = |channel: u8| (channel as f32 * MATCHING_PIXEL_DIM) as u8;
+            *pixel = [dim(pixel[0]), dim(pixel[1]), dim(pixel[2]), 255];
+        } else {
+            *pixel = MISMATCH_COLOR.into();
+            mismatches += 1;
+        }
+    }
+
+    (diff, mismatches)
+}
+
+fn assert_matches_golden(name: &str, actual: &Image) {
+    let golden_path = get_golden_path(name);
+    if env::var("UPDATE_GOLDENS").is_ok() {
+        save_image(actual, &golden_path);
+        return;
+    }
+
+    let bytes = fs::read(&golden_path).unwrap_or_else(|error| {
+        panic!(
+            "{}: {}, run with UPDATE_GOLDENS=1 to create it",
+            golden_path.display(),
+            error
+        )
+    });
+    let expected = Image::from_file_with_format(&bytes, Some(ImageFormat::Png)).unwrap();
+    if (expected.width, expected.height) == (actual.width, actual.height)
+        && expected.bytes == actual.bytes
+    {
+        return;
+    }
+
+    let (diff, mismatches) = get_diff_image(&expected, actual);
+    let actual_path = get_failure_path(name);
+    let diff_path = get_failure_path(&format!("{}_diff", name));
+    save_image(actual, &actual_path);
+    save_image(&diff, &diff_path);
+    panic!(
+        "Frame {} differs from its golden in {} pixels, see {} and {}",
+        name,
+        mismatches,
+        actual_path.display(),
+        diff_path.display()
+    );
+}
+
+fn assert_script_matches_goldens(script: &GoldenScript) {
+    for (name, frame) in play_and_capture(script) {
+        assert_matches_golden(name, &frame);
+    }
+}
+
+#[test]
+fn golden_opening() {
+    assert_script_matches_goldens(&GoldenScript {
+        seed: 1,
+        moves: Vec::new(),
+        captures: Vec::from([(1, "opening")]),
+    });
+}
+
+#[test]
+fn golden_moves_and_rotations() {
+    assert_script_matches_goldens(&GoldenScript {
+        seed: 2,
+        moves: Vec::from([
+            (40, KeyCode::E),
+            (41, KeyCode::A),
+            (42, KeyCode::A),
+            (43, KeyCode::A),
+            (120, KeyCode::Q),
+            (130, KeyCode::D),
+            (131, KeyCode::D),
+        ]),
+        captures: Vec::from([(80, "moves_first_block"), (400, "moves_pile")]),
+    });
+}
+
+#[test]
+fn golden_game_over() {
+    assert_script_matches_goldens(&GoldenScript {
+        seed: 3,
+        moves: Vec::from([(10, KeyCode::E)]), // Rotating into the ceiling tops out
+        captures: Vec::from([(20, "game_over")]),
+    });
+}
+
+#[test]
+fn get_diff_image_marks_mismatches() {
+    let expected = Image::gen_image_color(2, 1, Color::new(1.0, 1.0, 1.0, 1.0));
+    let mut actual = expected.clone();
+    actual.set_pixel(1, 0, Color::new(0.0, 0.0, 0.0, 1.0));
+
+    let (diff, mismatches) = get_diff_image(&expected, &actual);
+
+    assert_eq!(mismatches, 1);
+    assert_eq!(diff.get_image_data()[0], [63, 63, 63, 255]);
+    let mismatch_pixel: [u8; 4] = MISMATCH_COLOR.into();
+
+    assert_eq!(diff.get_image_data()[1], mismatch_pixel);
+}
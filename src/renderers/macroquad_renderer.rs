@@ -0,0 +1,148 @@
+use std::future::Future;
+
+use macroquad::prelude::*;
+
+use crate::{
+    controllers::graphic_controller::GraphicController,
+    objects::{color_family::ColorFamily, field::Field, particle::Particle, theme::Theme},
+    renderers::{renderer::Renderer, viewport::Viewport},
+};
+
+#[derive(Default)]
+pub struct MacroquadRenderer;
+
+impl MacroquadRenderer {
+    pub fn new() -> MacroquadRenderer {
+        MacroquadRenderer {}
+    }
+
+    fn draw_grain(&self, x: i32, y: i32, viewport: &Viewport, color: Color) {
+        if !viewport.contains(x, y) {
+            return; // Would land on the bars around the map
+        }
+        let (screen_x, screen_y) = viewport.map_to_screen(x, y);
+        draw_rectangle(
+            screen_x,
+            screen_y,
+            viewport.get_grain_size(),
+            viewport.get_grain_size(),
+            color,
+        );
+    }
+}
+
+impl Renderer for MacroquadRenderer {
+    fn get_screen_size(&self) -> (f32, f32) {
+        (screen_width(), screen_height())
+    }
+
+    fn draw_background(&mut self, viewport: &Viewport, theme: &Theme) {
+        clear_background(theme.letterbox_color);
+        let (x, y, width, height) = viewport.get_map_rect();
+        draw_rectangle(x, y, width, height, theme.background_color);
+    }
+
+    fn draw_fields(&mut self, fields: &[&Field], viewport: &Viewport, theme: &Theme) {
+        for field in fields {
+            if let Some(color) = field.get_color() {
+                self.draw_grain(
+                    field.get_x(),
+                    field.get_y(),
+                    viewport,
+                    theme.get_color(color),
+                );
+            }
+        }
+    }
+
+    fn draw_block(
+        &mut self,
+        block_schema_color: (Vec<(i32, i32)>, ColorFamily),
+        viewport: &Viewport,
+        block_chunk_side: i32,
+        theme: &Theme,
+    ) {
+        let (block_schema, family) = block_schema_color;
+        for (x, y, color) in
+            GraphicController::get_skin_for_schema(block_schema, family, block_chunk_side, theme)
+        {
+            self.draw_grain(x, y, viewport, theme.get_color(color));
+        }
+    }
+
+    fn draw_block_miniature(
+        &mut self,
+        block_schema_color: (Vec<(i32, i32)>, Color),
+        origin_point: (f32, f32),
+        preview_block_chunk_side: i32,
+        viewport: &Viewport,
+    ) {
+        let (block_schema, color) = block_schema_color;
+        let grain_size = viewport.get_grain_size();
+        for (x, y) in block_schema {
+            draw_rectangle(
+                origin_point.0 + x as f32 * grain_size,
+                origin_point.1 + y as f32 * grain_size,
+                preview_block_chunk_side as f32 * grain_size,
+                preview_block_chunk_side as f32 * grain_size,
+                color,
+            );
+        }
+    }
+    fn draw_text(&mut self, text: &str, x: f32, y: f32, font_size: u16, color: Color) {
+        draw_text_ex(
+            text,
+            x,
+            y,
+            TextParams {
+                font_size,
+                color,
+                ..Default::default()
+            },
+        );
+    }
+
+    fn draw_particles(&mut self, particles: &[Particle], viewport: &Viewport, theme: &Theme) {
+        for particle in particles {
+            let (x, y) = particle.get_cell();
+            let color = theme.get_color(particle.get_color());
+            self.draw_grain(
+                x,
+                y,
+                viewport,
+                Color {
+                    a: particle.get_opacity(),
+                    ..color
+                },
+            );
+        }
+    }
+
+    fn draw_overlay(
+        &mut self,
+        (x, y, width, height): (f32, f32, f32, f32),
+        color: Color,
+        opacity: f32,
+    ) {
+        draw_rectangle(
+            x,
+            y,
+            width,
+            height,
+            Color {
+                a: opacity.clamp(0.0, 1.0),
+                ..color
+            },
+        );
+    }
+
+    fn measure_text(&self, text: &str, font_size: u16) -> (f32, f32) {
+        let text_dimensions = measure_text(text, None, font_size, 1.0);
+
+        (text_dimensions.width, text_dimensions.height)
+    }
+
+    fn present(&mut self) -> impl Future<Output = ()> {
+        next_frame()
+    }
+}
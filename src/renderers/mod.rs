@@ -0,0 +1,10 @@
+pub mod bitmap_font;
+pub mod framebuffer_renderer;
+#[cfg(test)]
+mod golden_frames;
+pub mod macroquad_renderer;
+#[cfg(test)]
+pub mod recording_renderer;
+pub mod renderer;
+pub mod terminal_renderer;
+pub mod viewport;
@@ -0,0 +1,225 @@
+use std::future::{ready, Future};
+
+use macroquad::color::Color;
+
+use crate::{
+    constants::window_constants::{WINDOW_HEIGHT, WINDOW_WIDTH},
+    objects::{
+        color_family::{ColorFamily, GrainColor},
+        field::Field,
+        particle::Particle,
+        theme::Theme,
+    },
+    renderers::{renderer::Renderer, viewport::Viewport},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DrawCommand {
+    Background,
+    Fields(Vec<(i32, i32, GrainColor)>),
+    Block(Vec<(i32, i32)>, ColorFamily),
+    BlockMiniature(Vec<(i32, i32)>, Color, (f32, f32)),
+    Text(String, (f32, f32), u16, Color),
+    Particles(Vec<Particle>),
+    Overlay((f32, f32, f32, f32), Color, f32),
+    Present,
+}
+
+// Keeps everything it is asked to draw, so tests can check what a frame contains
+pub struct RecordingRenderer {
+    commands: Vec<DrawCommand>,
+    screen_size: (f32, f32),
+}
+
+impl Default for RecordingRenderer {
+    fn default() -> Self {
+        RecordingRenderer::new()
+    }
+}
+
+impl RecordingRenderer {
+    pub fn new() -> RecordingRenderer {
+        RecordingRenderer {
+            commands: Vec::new(),
+            screen_size: (WINDOW_WIDTH as f32, WINDOW_HEIGHT as f32),
+        }
+    }
+
+    pub fn set_screen_size(&mut self, screen_size: (f32, f32)) {
+        self.screen_size = screen_size;
+    }
+
+    pub fn get_commands(&self) -> &Vec<DrawCommand> {
+        &self.commands
+    }
+
+    pub fn get_frame_count(&self) -> usize {
+        self.commands
+            .iter()
+            .filter(|command| **command == DrawCommand::Present)
+            .count()
+    }
+
+    pub fn get_texts(&self) -> Vec<&str> {
+        self.commands
+            .iter()
+            .filter_map(|command| match command {
+                DrawCommand::Text(text, ..) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.commands.clear();
+    }
+
+    fn get_positions_and_colors(fields: &[&Field]) -> Vec<(i32, i32, GrainColor)> {
+        fields
+            .iter()
+            .filter_map(|field| {
+                field
+                    .get_color()
+                    .map(|color| (field.get_x(), field.get_y(), color))
+            })
+            .collect()
+    }
+}
+
+impl Renderer for RecordingRenderer {
+    fn get_screen_size(&self) -> (f32, f32) {
+        self.screen_size
+    }
+
+    fn draw_background(&mut self, _viewport: &Viewport, _theme: &Theme) {
+        self.commands.push(DrawCommand::Background);
+    }
+
+    fn draw_fields(&mut self, fields: &[&Field], _viewport: &Viewport, _theme: &Theme) {
+        self.commands.push(DrawCommand::Fields(
+            RecordingRenderer::get_positions_and_colors(fields),
+        ));
+    }
+
+    fn draw_block(
+        &mut self,
+        block_schema_color: (Vec<(i32, i32)>, ColorFamily),
+        _viewport: &Viewport,
+        _block_chunk_side: i32,
+        _theme: &Theme,
+    ) {
+        self.commands.push(DrawCommand::Block(
+            block_schema_color.0,
+            block_schema_color.1,
+        ));
+    }
+
+    fn draw_block_miniature(
+        &mut self,
+        block_schema_color: (Vec<(i32, i32)>, Color),
+        origin_point: (f32, f32),
+        _preview_block_chunk_side: i32,
+        _viewport: &Viewport,
+    ) {
+        self.commands.push(DrawCommand::BlockMiniature(
+            block_schema_color.0,
+            block_schema_color.1,
+            origin_point,
+        ));
+    }
+
+    fn draw_text(&mut self, text: &str, x: f32, y: f32, font_size: u16, color: Color) {
+        self.commands.push(DrawCommand::Text(
+            text.to_string(),
+            (x, y),
+            font_size,
+            color,
+        ));
+    }
+
+    fn draw_particles(&mut self, particles: &[Particle], _viewport: &Viewport, _theme: &Theme) {
+        self.commands
+            .push(DrawCommand::Particles(particles.to_vec()));
+    }
+
+    fn draw_overlay(&mut self, rect: (f32, f32, f32, f32), color: Color, opacity: f32) {
+        self.commands
+            .push(DrawCommand::Overlay(rect, color, opacity));
+    }
+
+    fn measure_text(&self, text: &str, font_size: u16) -> (f32, f32) {
+        // Fixed width glyphs, good enough to check the layout
+        (text.len() as f32 * font_size as f32 / 2.0, font_size as f32)
+    }
+
+    fn present(&mut self) -> impl Future<Output = ()> {
+        self.commands.push(DrawCommand::Present);
+
+        ready(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        constants::colors::{RED, WHITE},
+        utils::block_on::block_on,
+    };
+
+    use super::*;
+
+    #[test]
+    fn records_commands() {
+        let mut renderer = RecordingRenderer::new();
+        let field = Field::new(1, 2, Some(GrainColor::new(ColorFamily::Red, 1)), 1);
+
+        let viewport = Viewport::fit(renderer.get_screen_size(), (100, 160), 5);
+        renderer.draw_background(&viewport, &Theme::CLASSIC);
+        renderer.draw_fields(&Vec::from([&field]), &viewport, &Theme::CLASSIC);
+        renderer.draw_text("Score", 1.0, 2.0, 20, WHITE);
+
+        assert_eq!(
+            *renderer.get_commands(),
+            Vec::from([
+                DrawCommand::Background,
+                DrawCommand::Fields(Vec::from([(1, 2, GrainColor::new(ColorFamily::Red, 1))])),
+                DrawCommand::Text("Score".to_string(), (1.0, 2.0), 20, WHITE),
+            ])
+        );
+    }
+
+    #[test]
+    fn draw_text_with_outline() {
+        let mut renderer = RecordingRenderer::new();
+
+        renderer.draw_text_with_outline("Hi", (10.0, 10.0), 20, RED, WHITE, 2);
+
+        assert_eq!(renderer.get_texts(), Vec::from(["Hi", "Hi", "Hi"]));
+        assert_eq!(
+            renderer.get_commands()[2],
+            DrawCommand::Text("Hi".to_string(), (10.0, 10.0), 20, RED) // Inner text is drawn last
+        );
+    }
+
+    #[test]
+    fn get_text_center() {
+        let renderer = RecordingRenderer::new();
+
+        assert_eq!(renderer.get_text_center("abcd", 10), (10.0, 5.0));
+    }
+
+    #[test]
+    fn get_frame_count() {
+        let mut renderer = RecordingRenderer::new();
+
+        block_on(renderer.present());
+        renderer.draw_background(&Viewport::fit((1.0, 1.0), (1, 1), 1), &Theme::CLASSIC);
+        block_on(renderer.present());
+
+        assert_eq!(renderer.get_frame_count(), 2);
+
+        renderer.clear();
+
+        assert_eq!(renderer.get_frame_count(), 0);
+    }
+}
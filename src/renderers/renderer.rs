@@ -0,0 +1,118 @@
+use std::future::Future;
+
+use macroquad::color::Color;
+
+use crate::{
+    constants::interface_constants::{
+        CURSOR_OPACITY, HUD_LINE_SPACING, H_BORDER_OFFSET, SCORE_FONT_SIZE, SCORE_OUTLINE_WIDTH,
+        V_BORDER_OFFSET,
+    },
+    objects::{color_family::ColorFamily, field::Field, particle::Particle, theme::Theme},
+    renderers::viewport::Viewport,
+};
+
+pub trait Renderer {
+    fn get_screen_size(&self) -> (f32, f32);
+
+    fn draw_background(&mut self, viewport: &Viewport, theme: &Theme);
+
+    fn draw_fields(&mut self, fields: &[&Field], viewport: &Viewport, theme: &Theme);
+
+    fn draw_block(
+        &mut self,
+        block_schema_color: (Vec<(i32, i32)>, ColorFamily),
+        viewport: &Viewport,
+        block_chunk_side: i32,
+        theme: &Theme,
+    );
+
+    fn draw_block_miniature(
+        &mut self,
+        block_schema_color: (Vec<(i32, i32)>, Color),
+        origin_point: (f32, f32),
+        preview_block_chunk_side: i32,
+        viewport: &Viewport,
+    );
+
+    fn draw_text(&mut self, text: &str, x: f32, y: f32, font_size: u16, color: Color);
+
+    fn draw_particles(&mut self, particles: &[Particle], viewport: &Viewport, theme: &Theme);
+
+    // Blends a color over what is already drawn, rect is x, y, width, height on the screen
+    fn draw_overlay(&mut self, rect: (f32, f32, f32, f32), color: Color, opacity: f32);
+
+    fn measure_text(&self, text: &str, font_size: u16) -> (f32, f32);
+
+    fn present(&mut self) -> impl Future<Output = ()>;
+
+    fn draw_text_with_outline(
+        &mut self,
+        text: &str,
+        (x, y): (f32, f32),
+        font_size: u16,
+        inner_color: Color,
+        outer_color: Color,
+        border_width: u16,
+    ) {
+        self.draw_text(
+            text,
+            x - border_width as f32 / 2.0,
+            y - border_width as f32 / 2.0,
+            font_size,
+            outer_color,
+        );
+        self.draw_text(
+            text,
+            x + border_width as f32 / 2.0,
+            y + border_width as f32 / 2.0,
+            font_size,
+            outer_color,
+        );
+        self.draw_text(text, x, y, font_size, inner_color);
+    }
+
+    fn get_text_center(&self, text: &str, font_size: u16) -> (f32, f32) {
+        let (width, height) = self.measure_text(text, font_size);
+
+        (width / 2.0, height / 2.0)
+    }
+
+    // Right aligned lines in the top right corner of the map
+    fn draw_hud_lines(&mut self, lines: &[String], viewport: &Viewport, theme: &Theme) {
+        let font_size = viewport.scale_size(SCORE_FONT_SIZE);
+        let (map_x, map_y, map_width, _) = viewport.get_map_rect();
+
+        for (line, text) in lines.iter().enumerate() {
+            let text_center = self.get_text_center(text, font_size);
+            self.draw_text_with_outline(
+                text,
+                (
+                    map_x + map_width
+                        - 2.0 * text_center.0
+                        - viewport.scale_length(H_BORDER_OFFSET),
+                    map_y
+                        + 2.0 * text_center.1
+                        + viewport.scale_length(V_BORDER_OFFSET)
+                        + line as f32 * viewport.scale_length(HUD_LINE_SPACING),
+                ),
+                font_size,
+                theme.hud_text_color,
+                theme.hud_outline_color,
+                viewport.scale_size(SCORE_OUTLINE_WIDTH),
+            );
+        }
+    }
+
+    // Tints the grains a brush would cover, the ones off the map are skipped
+    fn draw_cursor(&mut self, grains: &[(i32, i32)], viewport: &Viewport, color: Color) {
+        let grain_size = viewport.get_grain_size();
+        for (x, y) in grains.iter().filter(|(x, y)| viewport.contains(*x, *y)) {
+            let (screen_x, screen_y) = viewport.map_to_screen(*x, *y);
+            self.draw_overlay(
+                (screen_x, screen_y, grain_size, grain_size),
+                color,
+                CURSOR_OPACITY,
+            );
+        }
+    }
+}
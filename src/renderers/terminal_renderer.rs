@@ -0,0 +1,396 @@
+use std::{
+    fmt::Write as _,
+    future::{ready, Future},
+    io::Write,
+    thread::sleep,
+    time::Instant,
+};
+
+use macroquad::color::Color;
+
+use crate::{
+    constants::{
+        colors::BACKGROUND_COLOR,
+        terminal_constants::{HALF_BLOCK, TERMINAL_FRAME_TIME},
+    },
+    controllers::graphic_controller::GraphicController,
+    objects::{
+        color_family::ColorFamily,
+        field::Field,
+        particle::Particle,
+        theme::{mix_colors, Theme},
+    },
+    renderers::{renderer::Renderer, viewport::Viewport},
+};
+
+// Draws the map with one character per two grains, the next block and texts go next to it
+pub struct TerminalRenderer<W: Write> {
+    output: W,
+    width: i32,
+    height: i32,
+    grains: Vec<Color>,
+    background: Color,
+    next_block: Vec<(i32, i32, Color)>,
+    texts: Vec<String>,
+    last_present: Instant,
+}
+
+impl<W: Write> TerminalRenderer<W> {
+    pub fn new(output: W, width: i32, height: i32) -> TerminalRenderer<W> {
+        TerminalRenderer {
+            output,
+            width,
+            height,
+            grains: vec![BACKGROUND_COLOR; (width * height) as usize],
+            background: BACKGROUND_COLOR,
+            next_block: Vec::new(),
+            texts: Vec::new(),
+            last_present: Instant::now(),
+        }
+    }
+
+    pub fn get_output(&self) -> &W {
+        &self.output
+    }
+
+    fn set_grain(&mut self, x: i32, y: i32, color: Color) {
+        if (0..self.width).contains(&x) && (0..self.height).contains(&y) {
+            self.grains[(y * self.width + x) as usize] = color;
+        }
+    }
+
+    fn get_grain(grains: &[Color], width: i32, x: i32, y: i32, background: Color) -> Color {
+        match grains.get((y * width + x) as usize) {
+            Some(color) if x < width => *color,
+            _ => background,
+        }
+    }
+
+    fn write_half_blocks(
+        frame: &mut String,
+        grains: &[Color],
+        width: i32,
+        row: i32,
+        background: Color,
+        last_colors: &mut Option<(Color, Color)>,
+    ) {
+        for x in 0..width {
+            let colors = (
+                TerminalRenderer::<W>::get_grain(grains, width, x, 2 * row, background),
+                TerminalRenderer::<W>::get_grain(grains, width, x, 2 * row + 1, background),
+            );
+            if *last_colors != Some(colors) {
+                let (top, bottom) = (to_rgb(colors.0), to_rgb(colors.1));
+                let _ = write!(
+                    frame,
+                    "\x1b[38;2;{};{};{}m\x1b[48;2;{};{};{}m",
+                    top.0, top.1, top.2, bottom.0, bottom.1, bottom.2
+                );
+                *last_colors = Some(colors);
+            }
+            frame.push(HALF_BLOCK);
+        }
+    }
+
+    fn get_next_block_grains(&self) -> (Vec<Color>, i32) {
+        let width = self
+            .next_block
+            .iter()
+            .map(|grain| grain.0 + 1)
+            .max()
+            .unwrap_or(0);
+        let height = self
+            .next_block
+            .iter()
+            .map(|grain| grain.1 + 1)
+            .max()
+            .unwrap_or(0);
+        let mut grains = vec![self.background; (width * height) as usize];
+        for (x, y, color) in &self.next_block {
+            grains[(y * width + x) as usize] = *color;
+        }
+
+        (grains, width)
+    }
+
+    pub fn get_frame(&self) -> String {
+        let (next_block_grains, next_block_width) = self.get_next_block_grains();
+        let next_block_height = next_block_grains.len() as i32 / next_block_width.max(1);
+        let next_block_rows = (next_block_height + 1) / 2;
+        let mut frame = String::from("\x1b[H");
+
+        for row in 0..(self.height + 1) / 2 {
+            let mut last_colors = None;
+            TerminalRenderer::<W>::write_half_blocks(
+                &mut frame,
+                &self.grains,
+                self.width,
+                row,
+                self.background,
+                &mut last_colors,
+            );
+            frame.push_str("\x1b[0m  ");
+
+            // Next block on top, texts below it
+            if row < next_block_rows {
+                let mut last_colors = None;
+                TerminalRenderer::<W>::write_half_blocks(
+                    &mut frame,
+                    &next_block_grains,
+                    next_block_width,
+                    row,
+                    self.background,
+                    &mut last_colors,
+                );
+                frame.push_str("\x1b[0m");
+            } else if row > next_block_rows {
+                if let Some(text) = self.texts.get((row - next_block_rows - 1) as usize) {
+                    frame.push_str(text);
+                }
+            }
+            frame.push_str("\x1b[K\r\n");
+        }
+
+        frame
+    }
+}
+
+fn to_rgb(color: Color) -> (u8, u8, u8) {
+    let [r, g, b, _]: [u8; 4] = color.into();
+
+    (r, g, b)
+}
+
+impl<W: Write> Renderer for TerminalRenderer<W> {
+    // One grain per half character, so the viewport always matches the map
+    fn get_screen_size(&self) -> (f32, f32) {
+        (self.width as f32, self.height as f32)
+    }
+
+    fn draw_background(&mut self, _viewport: &Viewport, theme: &Theme) {
+        self.background = theme.background_color;
+        self.grains.fill(self.background);
+        self.next_block.clear();
+        self.texts.clear();
+    }
+
+    fn draw_fields(&mut self, fields: &[&Field], _viewport: &Viewport, theme: &Theme) {
+        for field in fields {
+            if let Some(color) = field.get_color() {
+                self.set_grain(field.get_x(), field.get_y(), theme.get_color(color));
+            }
+        }
+    }
+
+    fn draw_block(
+        &mut self,
+        block_schema_color: (Vec<(i32, i32)>, ColorFamily),
+        _viewport: &Viewport,
+        block_chunk_side: i32,
+        theme: &Theme,
+    ) {
+        let (block_schema, family) = block_schema_color;
+        for (x, y, color) in
+            GraphicController::get_skin_for_schema(block_schema, family, block_chunk_side, theme)
+        {
+            self.set_grain(x, y, theme.get_color(color));
+        }
+    }
+
+    fn draw_block_miniature(
+        &mut self,
+        block_schema_color: (Vec<(i32, i32)>, Color),
+        _origin_point: (f32, f32),
+        preview_block_chunk_side: i32,
+        _viewport: &Viewport,
+    ) {
+        // One grain per chunk is enough next to the map
+        let (block_schema, color) = block_schema_color;
+        self.next_block = block_schema
+            .iter()
+            .map(|(x, y)| {
+                (
+                    x / preview_block_chunk_side,
+                    y / preview_block_chunk_side,
+                    color,
+                )
+            })
+            .collect();
+    }
+
+    fn draw_text(&mut self, text: &str, _x: f32, _y: f32, _font_size: u16, _color: Color) {
+        if !self.texts.iter().any(|drawn_text| drawn_text == text) {
+            self.texts.push(text.to_string());
+        }
+    }
+
+    fn draw_particles(&mut self, particles: &[Particle], _viewport: &Viewport, theme: &Theme) {
+        for particle in particles {
+            let (x, y) = particle.get_cell();
+            self.set_grain(
+                x,
+                y,
+                theme.get_faded_color(particle.get_color(), particle.get_opacity()),
+            );
+        }
+    }
+
+    // The screen is the map here, so the rect covers grains
+    fn draw_overlay(
+        &mut self,
+        (x, y, width, height): (f32, f32, f32, f32),
+        color: Color,
+        opacity: f32,
+    ) {
+        let x_range = (x.round() as i32).max(0)..((x + width).round() as i32).min(self.width);
+        let y_range = (y.round() as i32).max(0)..((y + height).round() as i32).min(self.height);
+        for grain_y in y_range {
+            for grain_x in x_range.clone() {
+                let grain = &mut self.grains[(grain_y * self.width + grain_x) as usize];
+                *grain = mix_colors(*grain, color, opacity);
+            }
+        }
+    }
+
+    fn measure_text(&self, text: &str, _font_size: u16) -> (f32, f32) {
+        (text.chars().count() as f32, 1.0)
+    }
+
+    fn present(&mut self) -> impl Future<Output = ()> {
+        let frame = self.get_frame();
+        let _ = self.output.write_all(frame.as_bytes());
+        let _ = self.output.flush();
+
+        if let Some(time_left) = TERMINAL_FRAME_TIME.checked_sub(self.last_present.elapsed()) {
+            sleep(time_left);
+        }
+        self.last_present = Instant::now();
+
+        ready(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        constants::colors::{RED, WHITE},
+        objects::color_family::GrainColor,
+        utils::block_on::block_on,
+    };
+
+    use super::*;
+
+    const RED_GRAIN: Option<GrainColor> = Some(GrainColor::new(ColorFamily::Red, 0));
+
+    fn get_ansi_colors(color: Color) -> (String, String) {
+        let (r, g, b) = to_rgb(color);
+
+        (
+            format!("\x1b[38;2;{};{};{}m", r, g, b),
+            format!("\x1b[48;2;{};{};{}m", r, g, b),
+        )
+    }
+
+    fn get_viewport(renderer: &TerminalRenderer<Vec<u8>>) -> Viewport {
+        let (width, height) = renderer.get_screen_size();
+
+        Viewport::fit((width, height), (width as i32, height as i32), 1)
+    }
+
+    #[test]
+    fn get_frame_half_blocks() {
+        let mut renderer = TerminalRenderer::new(Vec::new(), 3, 3);
+        let field = Field::new(1, 1, RED_GRAIN, 1);
+
+        let viewport = get_viewport(&renderer);
+        renderer.draw_background(&viewport, &Theme::CLASSIC);
+        renderer.draw_fields(&[&field], &viewport, &Theme::CLASSIC);
+
+        let frame = renderer.get_frame();
+        let lines: Vec<&str> = frame.split("\r\n").collect();
+        let (red_foreground, red_background) = get_ansi_colors(RED);
+
+        assert_eq!(lines.len(), 3); // Two rows of characters for three rows of grains
+        assert_eq!(frame.matches(HALF_BLOCK).count(), 6);
+        assert!(lines[0].contains(&red_background)); // Lower half of the first row
+        assert!(!lines[0].contains(&red_foreground));
+        assert!(!lines[1].contains(&red_background));
+    }
+
+    #[test]
+    fn get_frame_texts_and_next_block() {
+        let mut renderer = TerminalRenderer::new(Vec::new(), 2, 8);
+
+        let viewport = get_viewport(&renderer);
+        renderer.draw_background(&viewport, &Theme::CLASSIC);
+        renderer.draw_block_miniature((Vec::from([(0, 0), (3, 0)]), RED), (0.0, 0.0), 3, &viewport);
+        renderer.draw_text_with_outline("Score:5", (0.0, 0.0), 10, WHITE, RED, 2);
+
+        let frame = renderer.get_frame();
+        let lines: Vec<&str> = frame.split("\r\n").collect();
+
+        assert_eq!(lines[0].matches(HALF_BLOCK).count(), 4); // Map and the next block
+        assert!(lines[2].ends_with("Score:5\x1b[K"));
+        assert_eq!(frame.matches("Score:5").count(), 1); // Outline isn't repeated
+    }
+
+    #[test]
+    fn draw_particles() {
+        let mut renderer = TerminalRenderer::new(Vec::new(), 2, 2);
+        let mut particle = Particle::new(1.5, 0.5, (0.0, 0.0), RED_GRAIN.unwrap(), 2);
+
+        let viewport = get_viewport(&renderer);
+        renderer.draw_particles(&[particle.clone()], &viewport, &Theme::CLASSIC);
+        assert_eq!(renderer.grains[1], RED);
+
+        particle.tick(0.0);
+        renderer.draw_particles(&[particle.clone()], &viewport, &Theme::CLASSIC);
+        assert_eq!(
+            renderer.grains[1],
+            Theme::CLASSIC.get_faded_color(RED_GRAIN.unwrap(), 0.5)
+        );
+        assert_eq!(renderer.grains[0], BACKGROUND_COLOR);
+    }
+
+    #[test]
+    fn draw_overlay() {
+        let mut renderer = TerminalRenderer::new(Vec::new(), 3, 1);
+
+        renderer.draw_background(&get_viewport(&renderer), &Theme::CLASSIC);
+        renderer.draw_overlay((1.0, -1.0, 5.0, 5.0), WHITE, 0.5);
+
+        assert_eq!(renderer.grains[0], BACKGROUND_COLOR);
+        assert_eq!(renderer.grains[1], mix_colors(BACKGROUND_COLOR, WHITE, 0.5));
+        assert_eq!(renderer.grains[2], mix_colors(BACKGROUND_COLOR, WHITE, 0.5));
+    }
+
+    #[test]
+    fn draw_block_partly_outside_of_map() {
+        let mut renderer = TerminalRenderer::new(Vec::new(), 2, 2);
+
+        renderer.draw_block(
+            (Vec::from([(1, 1)]), ColorFamily::Red),
+            &get_viewport(&renderer),
+            2,
+            &Theme::CLASSIC,
+        );
+
+        let skin = GraphicController::get_skin_for_schema(
+            Vec::from([(1, 1)]),
+            ColorFamily::Red,
+            2,
+            &Theme::CLASSIC,
+        );
+        assert_eq!(renderer.grains[3], Theme::CLASSIC.get_color(skin[0].2));
+    }
+
+    #[test]
+    fn present_writes_frame() {
+        let mut renderer = TerminalRenderer::new(Vec::new(), 2, 2);
+
+        block_on(renderer.present());
+
+        let output = String::from_utf8(renderer.get_output().clone()).unwrap();
+        assert_eq!(output, renderer.get_frame());
+    }
+}
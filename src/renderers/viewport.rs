@@ -0,0 +1,177 @@
+// Where the map lands on the screen: scaled to fit while keeping its proportions, centered, with bars around it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    origin: (f32, f32),
+    grain_size: f32,
+    map_size: (i32, i32),
+    scale: f32, // Relative to the grain size the interface was laid out for
+}
+
+impl Viewport {
+    pub fn fit(
+        screen_size: (f32, f32),
+        map_size: (i32, i32),
+        reference_grain_size: i32,
+    ) -> Viewport {
+        let grain_size = (screen_size.0 / map_size.0 as f32)
+            .min(screen_size.1 / map_size.1 as f32)
+            .max(0.0);
+        let origin = (
+            (screen_size.0 - grain_size * map_size.0 as f32) / 2.0,
+            (screen_size.1 - grain_size * map_size.1 as f32) / 2.0,
+        );
+
+        Viewport {
+            origin,
+            grain_size,
+            map_size,
+            scale: grain_size / reference_grain_size as f32,
+        }
+    }
+
+    // Same scale, moved by a number of grains, e.g. to shake the map
+    pub fn shifted(&self, offset: (f32, f32)) -> Viewport {
+        Viewport {
+            origin: (
+                self.origin.0 + offset.0 * self.grain_size,
+                self.origin.1 + offset.1 * self.grain_size,
+            ),
+            ..*self
+        }
+    }
+
+    pub fn get_grain_size(&self) -> f32 {
+        self.grain_size
+    }
+
+    pub fn get_scale(&self) -> f32 {
+        self.scale
+    }
+
+    pub fn map_to_screen(&self, x: i32, y: i32) -> (f32, f32) {
+        (
+            self.origin.0 + x as f32 * self.grain_size,
+            self.origin.1 + y as f32 * self.grain_size,
+        )
+    }
+
+    // The grain under a point on the screen, which may be off the map
+    pub fn screen_to_map(&self, x: f32, y: f32) -> (i32, i32) {
+        (
+            ((x - self.origin.0) / self.grain_size).floor() as i32,
+            ((y - self.origin.1) / self.grain_size).floor() as i32,
+        )
+    }
+
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        (0..self.map_size.0).contains(&x) && (0..self.map_size.1).contains(&y)
+    }
+
+    // x, y, width, height
+    pub fn get_map_rect(&self) -> (f32, f32, f32, f32) {
+        (
+            self.origin.0,
+            self.origin.1,
+            self.map_size.0 as f32 * self.grain_size,
+            self.map_size.1 as f32 * self.grain_size,
+        )
+    }
+
+    pub fn scale_length(&self, length: f32) -> f32 {
+        length * self.scale
+    }
+
+    // For font sizes and outline widths
+    pub fn scale_size(&self, size: u16) -> u16 {
+        ((size as f32 * self.scale).round() as u16).max(1)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::constants::{
+        map_constants::{GRAIN_SIDE_SIZE, MAP_HEIGHT, MAP_WIDTH},
+        window_constants::{WINDOW_HEIGHT, WINDOW_WIDTH},
+    };
+
+    use super::*;
+
+    #[test]
+    fn fit_starting_window() {
+        let viewport = Viewport::fit(
+            (WINDOW_WIDTH as f32, WINDOW_HEIGHT as f32),
+            (MAP_WIDTH, MAP_HEIGHT),
+            GRAIN_SIDE_SIZE,
+        );
+
+        assert_eq!(viewport.get_grain_size(), GRAIN_SIDE_SIZE as f32);
+        assert_eq!(viewport.get_scale(), 1.0);
+        assert_eq!(viewport.map_to_screen(0, 0), (0.0, 0.0));
+        assert_eq!(
+            viewport.map_to_screen(MAP_WIDTH - 1, 0),
+            ((WINDOW_WIDTH - GRAIN_SIDE_SIZE) as f32, 0.0)
+        );
+        assert_eq!(
+            viewport.map_to_screen(MAP_WIDTH - 1, MAP_HEIGHT - 1),
+            (
+                (WINDOW_WIDTH - GRAIN_SIDE_SIZE) as f32,
+                (WINDOW_HEIGHT - GRAIN_SIDE_SIZE) as f32
+            )
+        );
+    }
+
+    #[test]
+    fn fit_wide_screen() {
+        let viewport = Viewport::fit((1600.0, 800.0), (100, 160), 5);
+
+        assert_eq!(viewport.get_grain_size(), 5.0);
+        assert_eq!(viewport.get_map_rect(), (550.0, 0.0, 500.0, 800.0));
+        assert_eq!(viewport.map_to_screen(1, 1), (555.0, 5.0));
+    }
+
+    #[test]
+    fn fit_tall_screen() {
+        let viewport = Viewport::fit((250.0, 1000.0), (100, 160), 5);
+
+        assert_eq!(viewport.get_grain_size(), 2.5);
+        assert_eq!(viewport.get_scale(), 0.5);
+        assert_eq!(viewport.get_map_rect(), (0.0, 300.0, 250.0, 400.0));
+    }
+
+    #[test]
+    fn shifted() {
+        let viewport = Viewport::fit((1600.0, 800.0), (100, 160), 5).shifted((1.0, -0.5));
+
+        assert_eq!(viewport.get_map_rect(), (555.0, -2.5, 500.0, 800.0));
+        assert_eq!(viewport.get_scale(), 1.0);
+        assert!(viewport.contains(0, 0));
+    }
+
+    #[test]
+    fn screen_to_map() {
+        let viewport = Viewport::fit((1600.0, 800.0), (100, 160), 5);
+
+        assert_eq!(viewport.screen_to_map(555.0, 5.0), (1, 1));
+        assert_eq!(viewport.screen_to_map(559.9, 9.9), (1, 1));
+        assert_eq!(viewport.screen_to_map(500.0, 0.0), (-10, 0)); // On the bars
+    }
+
+    #[test]
+    fn contains() {
+        let viewport = Viewport::fit((500.0, 800.0), (100, 160), 5);
+
+        assert!(viewport.contains(0, 0));
+        assert!(viewport.contains(99, 159));
+        assert!(!viewport.contains(5, -1)); // Blocks spawn above the map
+        assert!(!viewport.contains(100, 5));
+    }
+
+    #[test]
+    fn scale_interface() {
+        let viewport = Viewport::fit((1000.0, 1600.0), (100, 160), 5);
+
+        assert_eq!(viewport.scale_length(20.0), 40.0);
+        assert_eq!(viewport.scale_size(45), 90);
+        assert_eq!(Viewport::fit((1.0, 1.0), (100, 160), 5).scale_size(45), 1);
+    }
+}
@@ -0,0 +1,53 @@
+use macroquad::input::KeyCode;
+
+use crate::objects::color_family::ColorFamily;
+
+// 1 to 8 pick the colors in their usual order
+const COLOR_KEYS: [KeyCode; ColorFamily::COUNT] = [
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+];
+
+pub fn get_key_color(key: KeyCode) -> Option<ColorFamily> {
+    COLOR_KEYS
+        .iter()
+        .position(|color_key| *color_key == key)
+        .map(|index| ColorFamily::ALL[index])
+}
+
+// Grains within the radius of the center, a radius of 0 is the center alone
+pub fn get_disc((x, y): (i32, i32), radius: i32) -> Vec<(i32, i32)> {
+    (-radius..=radius)
+        .flat_map(|dx| (-radius..=radius).map(move |dy| (dx, dy)))
+        .filter(|(dx, dy)| dx * dx + dy * dy <= radius * radius)
+        .map(|(dx, dy)| (x + dx, y + dy))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn get_key_color() {
+        assert_eq!(super::get_key_color(KeyCode::Key1), Some(ColorFamily::Red));
+        assert_eq!(super::get_key_color(KeyCode::Key8), Some(ColorFamily::Pink));
+        assert_eq!(super::get_key_color(KeyCode::Key0), None);
+    }
+
+    #[test]
+    fn get_disc() {
+        assert_eq!(super::get_disc((3, 3), 0), Vec::from([(3, 3)]));
+        assert_eq!(
+            super::get_disc((3, 3), 1),
+            Vec::from([(2, 3), (3, 2), (3, 3), (3, 4), (4, 3)])
+        );
+        assert_eq!(super::get_disc((0, 0), 2).len(), 13);
+    }
+}
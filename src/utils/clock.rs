@@ -0,0 +1,84 @@
+use std::{
+    cell::Cell,
+    time::{Duration, Instant},
+};
+
+// Time since the clock was started, effects are timed with it instead of with ticks
+pub trait Clock {
+    fn now(&self) -> Duration;
+}
+
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        SystemClock::new()
+    }
+}
+
+impl SystemClock {
+    pub fn new() -> SystemClock {
+        SystemClock {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+// Only moves when told to, for tests and for frames rendered without a window
+#[derive(Default)]
+pub struct FakeClock {
+    now: Cell<Duration>,
+}
+
+impl FakeClock {
+    pub fn new() -> FakeClock {
+        FakeClock {
+            now: Cell::new(Duration::ZERO),
+        }
+    }
+
+    // Takes &self, so the clock can be moved on while a game controller holds on to it
+    pub fn advance(&self, duration: Duration) {
+        self.now.set(self.now.get() + duration);
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> Duration {
+        self.now.get()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn system_clock_moves_forward() {
+        let clock = SystemClock::new();
+        let before = clock.now();
+
+        std::thread::sleep(Duration::from_millis(1));
+
+        assert!(clock.now() > before);
+    }
+
+    #[test]
+    fn fake_clock_advance() {
+        let clock = FakeClock::new();
+        assert_eq!(clock.now(), Duration::ZERO);
+
+        clock.advance(Duration::from_millis(16));
+        clock.advance(Duration::from_millis(16));
+
+        assert_eq!(clock.now(), Duration::from_millis(32));
+    }
+}
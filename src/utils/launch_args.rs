@@ -0,0 +1,600 @@
+use std::time::Duration;
+
+use crate::{
+    constants::{
+        matching_tables::WARM_COOL, mode_constants::HIGH_SCORES_PATH, BoardSize, GameRules,
+        TetrisConstants,
+    },
+    objects::{
+        color_family::{ColorFamily, ColorWeights},
+        game_mode::GameMode,
+        garbage::Garbage,
+        high_scores::HighScores,
+        matching_rules::{ColorMatching, Connectivity},
+        piece_set::PieceSet,
+        puzzle::Puzzle,
+        settle_rules::{ImpactMode, SettlePolicy},
+        theme::Theme,
+    },
+};
+
+#[derive(Debug, PartialEq)]
+pub struct LaunchArgs {
+    pub rules: GameRules,
+    pub board: BoardSize,
+    pub piece_set_path: Option<String>,
+    pub puzzle_path: Option<String>,
+    pub edit_path: Option<String>, // Puzzle file whose board is opened in the editor instead of playing
+    pub theme: Option<String>,     // Name of a built in theme or path to a theme file
+    pub is_sandbox: bool,          // Sand is poured with the mouse instead of playing
+    pub seed: Option<u64>,
+}
+
+// Everything a game starts with, loaded and checked against each other
+pub struct GameSetup {
+    pub constants: TetrisConstants,
+    pub piece_set: PieceSet,
+    pub puzzle: Option<Puzzle>,
+    pub script: Vec<(usize, ColorFamily)>, // Blocks dealt before any are rolled
+    pub high_scores: HighScores,
+}
+
+impl LaunchArgs {
+    pub fn load_game_setup(&self) -> Result<GameSetup, String> {
+        let piece_set = self.load_piece_set()?;
+        let constants = self.get_tetris_constants()?;
+        constants.check_piece_set(&piece_set)?;
+        let puzzle = self.load_puzzle(&constants)?;
+        let script = match &puzzle {
+            Some(puzzle) => puzzle.get_script(&piece_set)?,
+            None => Vec::new(), // Every block is rolled
+        };
+        // A broken high score file is reported but doesn't stop the game
+        let high_scores = HighScores::load(HIGH_SCORES_PATH).unwrap_or_else(|message| {
+            eprintln!("{}", message);
+            HighScores::new()
+        });
+
+        Ok(GameSetup {
+            constants,
+            piece_set,
+            puzzle,
+            script,
+            high_scores,
+        })
+    }
+
+    pub fn get_tetris_constants(&self) -> Result<TetrisConstants, String> {
+        let theme = Theme::load(self.theme.as_deref().unwrap_or(Theme::CLASSIC.name))?;
+
+        Ok(TetrisConstants {
+            theme,
+            ..TetrisConstants::with_board(self.board, self.rules)?
+        })
+    }
+
+    pub fn load_piece_set(&self) -> Result<PieceSet, String> {
+        match &self.piece_set_path {
+            Some(path) => PieceSet::load(path),
+            None => Ok(PieceSet::classic()),
+        }
+    }
+
+    pub fn load_puzzle(&self, constants: &TetrisConstants) -> Result<Option<Puzzle>, String> {
+        match &self.puzzle_path {
+            Some(path) => {
+                let puzzle = Puzzle::load(path)?;
+                constants.check_puzzle(&puzzle)?;
+                Ok(Some(puzzle))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+pub fn parse_launch_args(args: &[String]) -> Result<LaunchArgs, String> {
+    let mut rules = GameRules::CLASSIC;
+    let mut board = BoardSize::CLASSIC;
+    let mut piece_set_path = None;
+    let mut puzzle_path = None;
+    let mut edit_path = None;
+    let mut is_sandbox = false;
+    let mut theme = None;
+    let mut seed = None;
+    let mut settle_policy = None;
+    let mut args_iter = args.iter();
+
+    while let Some(arg) = args_iter.next() {
+        match arg.as_str() {
+            "--pieces" => piece_set_path = Some(next_value(&mut args_iter, arg)?.to_string()),
+            "--puzzle" => {
+                puzzle_path = Some(next_value(&mut args_iter, arg)?.to_string());
+                rules.mode = GameMode::Puzzle;
+            }
+            "--edit" => edit_path = Some(next_value(&mut args_iter, arg)?.to_string()),
+            "--sandbox" => is_sandbox = true,
+            "--theme" => theme = Some(next_value(&mut args_iter, arg)?.to_string()),
+            "--seed" => {
+                let value = next_value(&mut args_iter, arg)?;
+                seed = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("Invalid seed: {}", value))?,
+                )
+            }
+            "--width" => {
+                let value = next_value(&mut args_iter, arg)?;
+                board.width = value
+                    .parse()
+                    .map_err(|_| format!("Invalid board width: {}", value))?
+            }
+            "--height" => {
+                let value = next_value(&mut args_iter, arg)?;
+                board.height = value
+                    .parse()
+                    .map_err(|_| format!("Invalid board height: {}", value))?
+            }
+            "--grain-size" => {
+                let value = next_value(&mut args_iter, arg)?;
+                board.grain_side_size = value
+                    .parse()
+                    .map_err(|_| format!("Invalid grain size: {}", value))?
+            }
+            "--chunk-side" => {
+                let value = next_value(&mut args_iter, arg)?;
+                board.block_chunk_side = value
+                    .parse()
+                    .map_err(|_| format!("Invalid block chunk side: {}", value))?
+            }
+            "--lock-delay" => {
+                let value = next_value(&mut args_iter, arg)?;
+                rules.lock_delay.ticks = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("Invalid lock delay: {}", value))?,
+                )
+            }
+            "--settle" => {
+                settle_policy = Some(match next_value(&mut args_iter, arg)? {
+                    "any" => SettlePolicy::AnyContact,
+                    "down" => SettlePolicy::DownwardContact,
+                    "lock-delay" => SettlePolicy::AfterLockDelay,
+                    other => return Err(format!("Unknown settle policy: {}", other)),
+                })
+            }
+            "--connectivity" => {
+                rules.matching_rules.connectivity = match next_value(&mut args_iter, arg)? {
+                    "4" => Connectivity::FourWay,
+                    "8" => Connectivity::EightWay,
+                    other => return Err(format!("Unknown connectivity: {}", other)),
+                }
+            }
+            "--color-matching" => {
+                rules.matching_rules.color_matching = match next_value(&mut args_iter, arg)? {
+                    "exact" => ColorMatching::ExactFamily,
+                    "any" => ColorMatching::AnyColor,
+                    "warm-cool" => ColorMatching::Custom(&WARM_COOL),
+                    other => return Err(format!("Unknown color matching: {}", other)),
+                }
+            }
+            "--colors" => {
+                rules.color_weights = ColorWeights::parse(next_value(&mut args_iter, arg)?)?
+            }
+            "--mode" => {
+                let value = next_value(&mut args_iter, arg)?;
+                rules.mode = GameMode::from_name(value)
+                    .ok_or_else(|| format!("Unknown game mode: {}", value))?
+            }
+            "--impact" => {
+                rules.impact_mode = match next_value(&mut args_iter, arg)? {
+                    "settle" => ImpactMode::Settle,
+                    "shatter" => ImpactMode::Shatter,
+                    other => return Err(format!("Unknown impact mode: {}", other)),
+                }
+            }
+            "--garbage" => {
+                let value = next_value(&mut args_iter, arg)?;
+                rules.garbage.get_or_insert(Garbage::CLASSIC).interval = value
+                    .parse()
+                    .ok()
+                    .filter(|seconds: &f64| *seconds > 0.0)
+                    .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+                    .ok_or_else(|| format!("Invalid garbage interval: {}", value))?
+            }
+            "--garbage-rows" => {
+                let value = next_value(&mut args_iter, arg)?;
+                rules.garbage.get_or_insert(Garbage::CLASSIC).rows = value
+                    .parse()
+                    .ok()
+                    .filter(|rows| *rows > 0)
+                    .ok_or_else(|| format!("Invalid garbage rows: {}", value))?
+            }
+            _ => return Err(format!("Unknown argument: {}", arg)),
+        }
+    }
+
+    // Setting the lock delay turns it on, unless another settle policy was asked for
+    rules.settle_policy = match (settle_policy, rules.lock_delay.ticks) {
+        (Some(SettlePolicy::AfterLockDelay), _) | (None, Some(_)) => SettlePolicy::AfterLockDelay,
+        (Some(_), Some(_)) => {
+            return Err("The lock delay needs --settle lock-delay".to_string());
+        }
+        (Some(policy), None) => policy,
+        (None, None) => rules.settle_policy,
+    };
+
+    if rules.garbage.is_some() && puzzle_path.is_some() {
+        return Err("Garbage can't be used in a puzzle".to_string());
+    }
+
+    Ok(LaunchArgs {
+        rules,
+        board,
+        piece_set_path,
+        puzzle_path,
+        edit_path,
+        theme,
+        is_sandbox,
+        seed,
+    })
+}
+
+fn next_value<'a>(
+    args_iter: &mut impl Iterator<Item = &'a String>,
+    flag: &str,
+) -> Result<&'a str, String> {
+    match args_iter.next() {
+        Some(value) => Ok(value.as_str()),
+        None => Err(format!("Missing value for {}", flag)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn to_args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_launch_args_default() {
+        let launch_args = parse_launch_args(&to_args(&[])).unwrap();
+
+        assert_eq!(launch_args.rules, GameRules::CLASSIC);
+        assert_eq!(launch_args.board, BoardSize::CLASSIC);
+        assert_eq!(launch_args.piece_set_path, None);
+        assert_eq!(launch_args.puzzle_path, None);
+        assert_eq!(launch_args.edit_path, None);
+        assert!(!launch_args.is_sandbox);
+        assert_eq!(launch_args.rules.garbage, None);
+        assert_eq!(launch_args.seed, None);
+    }
+
+    #[test]
+    fn parse_launch_args_seed() {
+        let launch_args = parse_launch_args(&to_args(&["--seed", "1234"])).unwrap();
+
+        assert_eq!(launch_args.seed, Some(1234));
+    }
+
+    #[test]
+    fn parse_launch_args_pieces() {
+        let launch_args =
+            parse_launch_args(&to_args(&["--pieces", "assets/pieces/pentomino.txt"])).unwrap();
+
+        assert_eq!(
+            launch_args.piece_set_path,
+            Some("assets/pieces/pentomino.txt".to_string())
+        );
+    }
+
+    #[test]
+    fn load_piece_set() {
+        let launch_args = parse_launch_args(&to_args(&[])).unwrap();
+
+        assert_eq!(launch_args.load_piece_set(), Ok(PieceSet::classic()));
+
+        let launch_args = parse_launch_args(&to_args(&["--pieces", "missing.txt"])).unwrap();
+
+        assert!(launch_args.load_piece_set().is_err());
+    }
+
+    #[test]
+    fn parse_launch_args_puzzle() {
+        let launch_args =
+            parse_launch_args(&to_args(&["--puzzle", "assets/puzzles/first_steps.txt"])).unwrap();
+
+        assert_eq!(launch_args.rules.mode, GameMode::Puzzle);
+        assert_eq!(
+            launch_args.puzzle_path,
+            Some("assets/puzzles/first_steps.txt".to_string())
+        );
+    }
+
+    #[test]
+    fn load_game_setup() {
+        let setup = parse_launch_args(&to_args(&[]))
+            .unwrap()
+            .load_game_setup()
+            .unwrap();
+
+        assert_eq!(setup.piece_set, PieceSet::classic());
+        assert_eq!(setup.puzzle, None);
+        assert!(setup.script.is_empty());
+
+        let setup = parse_launch_args(&to_args(&["--puzzle", "assets/puzzles/first_steps.txt"]))
+            .unwrap()
+            .load_game_setup()
+            .unwrap();
+        let puzzle = setup.puzzle.unwrap();
+
+        assert_eq!(setup.script, puzzle.get_script(&setup.piece_set).unwrap());
+        assert!(!setup.script.is_empty());
+
+        let launch_args = parse_launch_args(&to_args(&["--chunk-side", "40"])).unwrap();
+
+        assert!(launch_args.load_game_setup().is_err());
+    }
+
+    #[test]
+    fn parse_launch_args_edit() {
+        let launch_args = parse_launch_args(&to_args(&["--edit", "level.txt"])).unwrap();
+
+        assert_eq!(launch_args.edit_path, Some("level.txt".to_string()));
+        assert_eq!(launch_args.rules.mode, GameMode::Endless);
+    }
+
+    #[test]
+    fn parse_launch_args_sandbox() {
+        let launch_args = parse_launch_args(&to_args(&["--sandbox", "--seed", "3"])).unwrap();
+
+        assert!(launch_args.is_sandbox);
+        assert_eq!(launch_args.seed, Some(3));
+    }
+
+    #[test]
+    fn load_puzzle() {
+        let constants = TetrisConstants::new(GameRules::CLASSIC);
+        let launch_args = parse_launch_args(&to_args(&[])).unwrap();
+
+        assert_eq!(launch_args.load_puzzle(&constants), Ok(None));
+
+        let launch_args =
+            parse_launch_args(&to_args(&["--puzzle", "assets/puzzles/first_steps.txt"])).unwrap();
+
+        assert!(launch_args.load_puzzle(&constants).unwrap().is_some());
+
+        let launch_args = parse_launch_args(&to_args(&[
+            "--puzzle",
+            "assets/puzzles/first_steps.txt",
+            "--width",
+            "50",
+        ]))
+        .unwrap();
+
+        assert!(launch_args
+            .load_puzzle(&launch_args.get_tetris_constants().unwrap())
+            .is_err());
+    }
+
+    #[test]
+    fn parse_launch_args_matching() {
+        let rules = parse_launch_args(&to_args(&[
+            "--connectivity",
+            "4",
+            "--color-matching",
+            "warm-cool",
+        ]))
+        .unwrap()
+        .rules;
+
+        assert_eq!(rules.matching_rules.connectivity, Connectivity::FourWay);
+        assert_eq!(
+            rules.matching_rules.color_matching,
+            ColorMatching::Custom(&WARM_COOL)
+        );
+    }
+
+    #[test]
+    fn parse_launch_args_impact() {
+        let rules = parse_launch_args(&to_args(&["--impact", "shatter"]))
+            .unwrap()
+            .rules;
+
+        assert_eq!(rules.impact_mode, ImpactMode::Shatter);
+    }
+
+    #[test]
+    fn parse_launch_args_colors() {
+        let rules = parse_launch_args(&to_args(&["--colors", "6"]))
+            .unwrap()
+            .rules;
+        assert_eq!(rules.color_weights, ColorWeights::evenly(6).unwrap());
+
+        let rules = parse_launch_args(&to_args(&["--colors", "red,blue:2,cyan"]))
+            .unwrap()
+            .rules;
+        assert_eq!(rules.color_weights.get_weights(), &[1, 2, 0, 0, 0, 0, 1, 0]);
+    }
+
+    #[test]
+    fn parse_launch_args_garbage() {
+        let rules = parse_launch_args(&to_args(&["--garbage", "12.5"]))
+            .unwrap()
+            .rules;
+        assert_eq!(
+            rules.garbage,
+            Some(Garbage {
+                interval: Duration::from_millis(12_500),
+                ..Garbage::CLASSIC
+            })
+        );
+
+        let rules = parse_launch_args(&to_args(&["--garbage-rows", "4"]))
+            .unwrap()
+            .rules;
+        assert_eq!(
+            rules.garbage,
+            Some(Garbage {
+                rows: 4,
+                ..Garbage::CLASSIC
+            })
+        );
+
+        for args in [
+            ["--garbage", "0"],
+            ["--garbage", "soon"],
+            ["--garbage-rows", "-2"],
+        ] {
+            assert!(parse_launch_args(&to_args(&args)).is_err());
+        }
+        assert_eq!(
+            parse_launch_args(&to_args(&["--puzzle", "level.txt", "--garbage", "10"])),
+            Err("Garbage can't be used in a puzzle".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_launch_args_mode() {
+        let rules = parse_launch_args(&to_args(&["--mode", "marathon"]))
+            .unwrap()
+            .rules;
+        assert_eq!(rules.mode, GameMode::Marathon);
+
+        assert_eq!(
+            parse_launch_args(&to_args(&["--mode", "blitz"])),
+            Err("Unknown game mode: blitz".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_launch_args_settle() {
+        let rules = parse_launch_args(&to_args(&["--settle", "lock-delay", "--lock-delay", "45"]))
+            .unwrap()
+            .rules;
+
+        assert_eq!(rules.settle_policy, SettlePolicy::AfterLockDelay);
+        assert_eq!(rules.get_lock_delay_ticks(), 45);
+        assert_eq!(
+            rules.lock_delay.max_resets,
+            GameRules::CLASSIC.lock_delay.max_resets
+        );
+    }
+
+    #[test]
+    fn parse_launch_args_lock_delay_turns_it_on() {
+        let rules = parse_launch_args(&to_args(&["--lock-delay", "45"]))
+            .unwrap()
+            .rules;
+
+        assert_eq!(rules.settle_policy, SettlePolicy::AfterLockDelay);
+        assert_eq!(rules.get_lock_delay_ticks(), 45);
+
+        assert_eq!(
+            parse_launch_args(&to_args(&["--settle", "down", "--lock-delay", "45"])),
+            Err("The lock delay needs --settle lock-delay".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_launch_args_lock_delay_per_mode() {
+        let rules = parse_launch_args(&to_args(&["--mode", "zen"]))
+            .unwrap()
+            .rules;
+
+        assert_eq!(
+            rules.get_lock_delay_ticks(),
+            GameMode::Zen.get_lock_delay_ticks()
+        );
+
+        let rules = parse_launch_args(&to_args(&["--mode", "zen", "--lock-delay", "45"]))
+            .unwrap()
+            .rules;
+
+        assert_eq!(rules.get_lock_delay_ticks(), 45);
+    }
+
+    #[test]
+    fn parse_launch_args_board() {
+        let launch_args = parse_launch_args(&to_args(&[
+            "--width",
+            "60",
+            "--height",
+            "90",
+            "--grain-size",
+            "8",
+            "--chunk-side",
+            "5",
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            launch_args.board,
+            BoardSize {
+                width: 60,
+                height: 90,
+                grain_side_size: 8,
+                block_chunk_side: 5,
+            }
+        );
+        assert_eq!(launch_args.get_tetris_constants().unwrap().map_width, 60);
+    }
+
+    #[test]
+    fn get_tetris_constants_theme() {
+        let launch_args = parse_launch_args(&to_args(&[])).unwrap();
+
+        assert_eq!(
+            launch_args.get_tetris_constants().unwrap().theme,
+            &Theme::CLASSIC
+        );
+
+        let launch_args =
+            parse_launch_args(&to_args(&["--theme", "assets/themes/night.txt"])).unwrap();
+
+        assert_eq!(
+            launch_args.get_tetris_constants().unwrap().theme.name,
+            "night"
+        );
+
+        let launch_args = parse_launch_args(&to_args(&["--theme", "missing.txt"])).unwrap();
+
+        assert!(launch_args.get_tetris_constants().is_err());
+    }
+
+    #[test]
+    fn get_tetris_constants_invalid_board() {
+        let launch_args = parse_launch_args(&to_args(&["--chunk-side", "0"])).unwrap();
+
+        assert!(launch_args.get_tetris_constants().is_err());
+    }
+
+    #[test]
+    fn parse_launch_args_invalid() {
+        assert_eq!(
+            parse_launch_args(&to_args(&["--conectivity", "4"])),
+            Err("Unknown argument: --conectivity".to_string())
+        );
+        assert_eq!(
+            parse_launch_args(&to_args(&["--seed", "3", "6"])),
+            Err("Unknown argument: 6".to_string())
+        );
+        assert!(parse_launch_args(&to_args(&["--connectivity", "6"])).is_err());
+        assert!(parse_launch_args(&to_args(&["--color-matching", "rainbow"])).is_err());
+        assert!(parse_launch_args(&to_args(&["--connectivity"])).is_err());
+        assert!(parse_launch_args(&to_args(&["--pieces"])).is_err());
+        assert!(parse_launch_args(&to_args(&["--puzzle"])).is_err());
+        assert!(parse_launch_args(&to_args(&["--edit"])).is_err());
+        assert!(parse_launch_args(&to_args(&["--impact", "explode"])).is_err());
+        assert!(parse_launch_args(&to_args(&["--seed", "-1"])).is_err());
+        assert!(parse_launch_args(&to_args(&["--lock-delay", "soon"])).is_err());
+        assert!(parse_launch_args(&to_args(&["--settle", "never"])).is_err());
+        assert!(parse_launch_args(&to_args(&["--width", "wide"])).is_err());
+        assert!(parse_launch_args(&to_args(&["--height"])).is_err());
+        assert!(parse_launch_args(&to_args(&["--theme"])).is_err());
+        assert!(parse_launch_args(&to_args(&["--colors", "1"])).is_err());
+        assert!(parse_launch_args(&to_args(&["--colors", "9"])).is_err());
+        assert!(parse_launch_args(&to_args(&["--colors", "red,violet"])).is_err());
+    }
+}
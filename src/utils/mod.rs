@@ -1 +1,6 @@
+pub mod block_on;
+pub mod brush;
+pub mod clock;
+pub mod launch_args;
+pub mod terminal_input;
 pub mod tetris_rng;
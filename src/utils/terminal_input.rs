@@ -0,0 +1,98 @@
+use macroquad::input::KeyCode;
+
+#[derive(Debug, PartialEq)]
+pub enum TerminalCommand {
+    Move(KeyCode),
+    ToggleCursor,
+    Restart,
+    Quit,
+}
+
+const CTRL_C: u8 = 0x03;
+const ESCAPE: u8 = 0x1b;
+
+// Same keys as in the window, arrow keys work as well with up rotating the block
+pub fn parse_terminal_input(bytes: &[u8]) -> Vec<TerminalCommand> {
+    let mut commands = Vec::new();
+    let mut index = 0;
+
+    while index < bytes.len() {
+        let command = match bytes[index..] {
+            [ESCAPE, b'[', arrow, ..] => {
+                index += 2;
+                match arrow {
+                    b'A' => Some(TerminalCommand::Move(KeyCode::E)),
+                    b'B' => Some(TerminalCommand::Move(KeyCode::S)),
+                    b'C' => Some(TerminalCommand::Move(KeyCode::D)),
+                    b'D' => Some(TerminalCommand::Move(KeyCode::A)),
+                    _ => None,
+                }
+            }
+            [key, ..] => match key.to_ascii_lowercase() {
+                b'd' => Some(TerminalCommand::Move(KeyCode::D)),
+                b'a' => Some(TerminalCommand::Move(KeyCode::A)),
+                b's' => Some(TerminalCommand::Move(KeyCode::S)),
+                b'e' => Some(TerminalCommand::Move(KeyCode::E)),
+                b'q' => Some(TerminalCommand::Move(KeyCode::Q)),
+                b'w' => Some(TerminalCommand::Move(KeyCode::W)),
+                b' ' => Some(TerminalCommand::Move(KeyCode::Space)),
+                b'c' => Some(TerminalCommand::ToggleCursor),
+                b'r' => Some(TerminalCommand::Restart),
+                b'x' | CTRL_C => Some(TerminalCommand::Quit),
+                _ => None,
+            },
+            [] => None,
+        };
+        if let Some(command) = command {
+            commands.push(command);
+        }
+        index += 1;
+    }
+
+    commands
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_terminal_input_keys() {
+        assert_eq!(
+            parse_terminal_input(b"aDseqw crx"),
+            Vec::from([
+                TerminalCommand::Move(KeyCode::A),
+                TerminalCommand::Move(KeyCode::D),
+                TerminalCommand::Move(KeyCode::S),
+                TerminalCommand::Move(KeyCode::E),
+                TerminalCommand::Move(KeyCode::Q),
+                TerminalCommand::Move(KeyCode::W),
+                TerminalCommand::Move(KeyCode::Space),
+                TerminalCommand::ToggleCursor,
+                TerminalCommand::Restart,
+                TerminalCommand::Quit,
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_terminal_input_arrows() {
+        assert_eq!(
+            parse_terminal_input(b"\x1b[A\x1b[B\x1b[C\x1b[D"),
+            Vec::from([
+                TerminalCommand::Move(KeyCode::E),
+                TerminalCommand::Move(KeyCode::S),
+                TerminalCommand::Move(KeyCode::D),
+                TerminalCommand::Move(KeyCode::A),
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_terminal_input_ignores_unknown() {
+        assert_eq!(
+            parse_terminal_input(b"z1\x1b[H\x03"),
+            Vec::from([TerminalCommand::Quit])
+        );
+    }
+}
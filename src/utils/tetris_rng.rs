@@ -1,9 +1,9 @@
-use macroquad::color::Color;
-use rand::{seq::SliceRandom, thread_rng, Rng};
+use rand::{rngs::StdRng, seq::SliceRandom, thread_rng, Rng, SeedableRng};
+use std::cell::{Cell, RefCell};
 
-use crate::{
-    constants::colors::{BLUE, GREEN, RED, YELLOW},
-    objects::{block::BlockType, field::Field},
+use crate::objects::{
+    block::{BlockType, CLASSIC_BLOCK_TYPES},
+    color_family::ColorFamily,
 };
 
 pub trait TetrisRng {
@@ -11,13 +11,19 @@ pub trait TetrisRng {
 
     fn get_random_row_order(&self, width: i32) -> Vec<i32>;
 
-    fn shuffle_fields(&self, fields: &mut Vec<&Field>);
+    fn generate_piece_index(&self, spawn_weights: &[u32]) -> usize;
 
-    fn generate_block_type(&self) -> BlockType;
+    fn generate_block_color(&self, spawn_weights: &[u32]) -> ColorFamily;
 
-    fn generate_block_color(&self) -> Color;
+    fn gen_scatter_momentum(&self, max_momentum: i32) -> i32;
+
+    fn gen_particle_velocity(&self, max_speed: f32) -> (f32, f32);
+
+    // Starts a scripted sequence over when the game restarts
+    fn rewind(&self) {}
 }
 
+#[derive(Default)]
 pub struct ThreadTetrisRng;
 
 impl ThreadTetrisRng {
@@ -38,40 +44,155 @@ impl TetrisRng for ThreadTetrisRng {
         row_order
     }
 
-    fn shuffle_fields(&self, fields: &mut Vec<&Field>) {
-        fields.shuffle(&mut thread_rng());
+    fn generate_piece_index(&self, spawn_weights: &[u32]) -> usize {
+        roll_weighted_index(&mut thread_rng(), spawn_weights)
     }
 
-    fn generate_block_type(&self) -> BlockType {
-        let block_type = match thread_rng().gen_range(0..=7) {
-            1 => BlockType::LBlock,
-            2 => BlockType::RevLBlock,
-            3 => BlockType::SquareBlock,
-            4 => BlockType::ZBlock,
-            5 => BlockType::RevZBlock,
-            6 => BlockType::TBlock,
-            _ => BlockType::IBlock,
-        };
+    fn generate_block_color(&self, spawn_weights: &[u32]) -> ColorFamily {
+        ColorFamily::ALL[roll_weighted_index(&mut thread_rng(), spawn_weights)]
+    }
 
-        block_type
+    fn gen_scatter_momentum(&self, max_momentum: i32) -> i32 {
+        thread_rng().gen_range(0..=max_momentum)
     }
 
-    fn generate_block_color(&self) -> Color {
-        match thread_rng().gen_range(0..=4) {
-            0 => RED,
-            1 => BLUE,
-            2 => GREEN,
-            _ => YELLOW,
+    fn gen_particle_velocity(&self, max_speed: f32) -> (f32, f32) {
+        roll_particle_velocity(&mut thread_rng(), max_speed)
+    }
+}
+
+// Same rolls as ThreadTetrisRng, but replayable from the seed
+pub struct SeededTetrisRng {
+    rng: RefCell<StdRng>,
+}
+
+impl SeededTetrisRng {
+    pub fn new(seed: u64) -> SeededTetrisRng {
+        SeededTetrisRng {
+            rng: RefCell::new(StdRng::seed_from_u64(seed)),
         }
     }
 }
 
+impl TetrisRng for SeededTetrisRng {
+    fn gen_do_go_right(&self) -> bool {
+        self.rng.borrow_mut().gen_bool(0.5)
+    }
+
+    fn get_random_row_order(&self, width: i32) -> Vec<i32> {
+        let mut row_order: Vec<i32> = (0..width).collect();
+        row_order.shuffle(&mut *self.rng.borrow_mut());
+
+        row_order
+    }
+
+    fn generate_piece_index(&self, spawn_weights: &[u32]) -> usize {
+        roll_weighted_index(&mut *self.rng.borrow_mut(), spawn_weights)
+    }
+
+    fn generate_block_color(&self, spawn_weights: &[u32]) -> ColorFamily {
+        ColorFamily::ALL[roll_weighted_index(&mut *self.rng.borrow_mut(), spawn_weights)]
+    }
+
+    fn gen_scatter_momentum(&self, max_momentum: i32) -> i32 {
+        self.rng.borrow_mut().gen_range(0..=max_momentum)
+    }
+
+    fn gen_particle_velocity(&self, max_speed: f32) -> (f32, f32) {
+        roll_particle_velocity(&mut *self.rng.borrow_mut(), max_speed)
+    }
+}
+
+// Hands out pieces and colors in a fixed order, then rolls them like the rng it wraps.
+// Everything else is always rolled by the wrapped rng.
+pub struct ScriptedTetrisRng<R: TetrisRng> {
+    rng: R,
+    script: Vec<(usize, ColorFamily)>, // Piece index and color of every block
+    next_piece: Cell<usize>,
+    next_color: Cell<usize>,
+}
+
+impl<R: TetrisRng> ScriptedTetrisRng<R> {
+    pub fn new(rng: R, script: Vec<(usize, ColorFamily)>) -> ScriptedTetrisRng<R> {
+        ScriptedTetrisRng {
+            rng,
+            script,
+            next_piece: Cell::new(0),
+            next_color: Cell::new(0),
+        }
+    }
+}
+
+impl<R: TetrisRng> TetrisRng for ScriptedTetrisRng<R> {
+    fn gen_do_go_right(&self) -> bool {
+        self.rng.gen_do_go_right()
+    }
+
+    fn get_random_row_order(&self, width: i32) -> Vec<i32> {
+        self.rng.get_random_row_order(width)
+    }
+
+    fn generate_piece_index(&self, spawn_weights: &[u32]) -> usize {
+        let index = self.next_piece.get();
+        self.next_piece.set(index + 1);
+        match self.script.get(index) {
+            Some((piece_index, _)) => *piece_index,
+            None => self.rng.generate_piece_index(spawn_weights),
+        }
+    }
+
+    fn generate_block_color(&self, spawn_weights: &[u32]) -> ColorFamily {
+        let index = self.next_color.get();
+        self.next_color.set(index + 1);
+        match self.script.get(index) {
+            Some((_, family)) => *family,
+            None => self.rng.generate_block_color(spawn_weights),
+        }
+    }
+
+    fn gen_scatter_momentum(&self, max_momentum: i32) -> i32 {
+        self.rng.gen_scatter_momentum(max_momentum)
+    }
+
+    fn gen_particle_velocity(&self, max_speed: f32) -> (f32, f32) {
+        self.rng.gen_particle_velocity(max_speed)
+    }
+
+    fn rewind(&self) {
+        self.next_piece.set(0);
+        self.next_color.set(0);
+        self.rng.rewind();
+    }
+}
+
+fn roll_weighted_index(rng: &mut impl Rng, spawn_weights: &[u32]) -> usize {
+    let mut roll = rng.gen_range(0..spawn_weights.iter().sum::<u32>());
+    for (index, weight) in spawn_weights.iter().enumerate() {
+        if roll < *weight {
+            return index;
+        }
+        roll -= weight;
+    }
+
+    spawn_weights.len() - 1
+}
+
+// Sideways either way, but always upwards, so cleared grains burst out of the row
+fn roll_particle_velocity(rng: &mut impl Rng, max_speed: f32) -> (f32, f32) {
+    (
+        rng.gen_range(-max_speed..=max_speed),
+        -rng.gen_range(0.0..=max_speed),
+    )
+}
+
 #[derive(PartialEq, Debug)]
 pub struct MockTetrisRng {
     go_right: bool,
     row_order: Vec<i32>,
-    block_type: BlockType,
-    block_color: Color,
+    piece_index: usize,
+    block_color: ColorFamily,
+    scatter_momentum: i32,
+    particle_velocity: (f32, f32),
 }
 
 #[warn(dead_code)]
@@ -80,8 +201,10 @@ impl MockTetrisRng {
         MockTetrisRng {
             go_right: false,
             row_order: Vec::new(),
-            block_type: BlockType::LBlock,
-            block_color: RED,
+            piece_index: 0,
+            block_color: ColorFamily::Red,
+            scatter_momentum: 0,
+            particle_velocity: (0.0, 0.0),
         }
     }
 
@@ -93,13 +216,35 @@ impl MockTetrisRng {
         self.row_order = row_order
     }
 
+    pub fn set_piece_index(&mut self, piece_index: usize) {
+        self.piece_index = piece_index
+    }
+
     pub fn set_block_type(&mut self, block_type: BlockType) {
-        self.block_type = block_type
+        // Index of the block type in the classic piece set
+        self.piece_index = CLASSIC_BLOCK_TYPES
+            .iter()
+            .position(|classic_type| *classic_type == block_type)
+            .unwrap()
     }
 
-    pub fn set_block_color(&mut self, block_color: Color) {
+    pub fn set_block_color(&mut self, block_color: ColorFamily) {
         self.block_color = block_color
     }
+
+    pub fn set_scatter_momentum(&mut self, scatter_momentum: i32) {
+        self.scatter_momentum = scatter_momentum
+    }
+
+    pub fn set_particle_velocity(&mut self, particle_velocity: (f32, f32)) {
+        self.particle_velocity = particle_velocity
+    }
+}
+
+impl Default for MockTetrisRng {
+    fn default() -> Self {
+        MockTetrisRng::new()
+    }
 }
 
 impl TetrisRng for MockTetrisRng {
@@ -120,14 +265,20 @@ impl TetrisRng for MockTetrisRng {
         }
     }
 
-    fn shuffle_fields(&self, _fields: &mut Vec<&Field>) {}
+    fn generate_piece_index(&self, _spawn_weights: &[u32]) -> usize {
+        self.piece_index
+    }
+
+    fn generate_block_color(&self, _spawn_weights: &[u32]) -> ColorFamily {
+        self.block_color
+    }
 
-    fn generate_block_type(&self) -> BlockType {
-        self.block_type.clone()
+    fn gen_scatter_momentum(&self, max_momentum: i32) -> i32 {
+        self.scatter_momentum.min(max_momentum)
     }
 
-    fn generate_block_color(&self) -> Color {
-        self.block_color.clone()
+    fn gen_particle_velocity(&self, _max_speed: f32) -> (f32, f32) {
+        self.particle_velocity
     }
 }
 
@@ -167,43 +318,157 @@ mod test_thread_tetris_rng {
     }
 
     #[test]
-    fn test_shuffle_fields() {
+    fn test_generate_piece_index() {
         let rng = ThreadTetrisRng::new();
-        let field1 = Field::new(0, 0, RED, 1);
-        let field2 = Field::new(0, 1, RED, 1);
-        let field3 = Field::new(0, 2, RED, 1);
-        let field4 = Field::new(0, 3, RED, 1);
-
-        let mut fields = vec![&field1, &field2, &field3, &field4];
-        let original_fields = fields.clone();
-        rng.shuffle_fields(&mut fields);
 
-        assert_ne!(fields, original_fields);
+        for _ in 0..100 {
+            assert!(rng.generate_piece_index(&[1, 1, 1, 1, 1, 2, 1]) < 7);
+        }
     }
 
     #[test]
-    fn test_generate_block_type() {
+    fn test_generate_piece_index_skips_zero_weights() {
         let rng = ThreadTetrisRng::new();
-        let block_type = rng.generate_block_type();
 
-        assert!(matches!(
-            block_type,
-            BlockType::LBlock
-                | BlockType::RevLBlock
-                | BlockType::SquareBlock
-                | BlockType::ZBlock
-                | BlockType::RevZBlock
-                | BlockType::TBlock
-                | BlockType::IBlock
-        ));
+        for _ in 0..100 {
+            assert_eq!(rng.generate_piece_index(&[0, 3, 0]), 1);
+        }
     }
 
     #[test]
     fn test_generate_block_color() {
         let rng = ThreadTetrisRng::new();
-        let color = rng.generate_block_color();
+        let color = rng.generate_block_color(&[1, 1, 1, 2, 0, 0, 0, 0]);
+
+        assert!(ColorFamily::ALL[..4].contains(&color));
+    }
+
+    #[test]
+    fn test_generate_block_color_skips_zero_weights() {
+        let rng = ThreadTetrisRng::new();
 
-        assert!(matches!(color, RED | BLUE | GREEN | YELLOW));
+        for _ in 0..100 {
+            let color = rng.generate_block_color(&[0, 0, 0, 0, 0, 1, 0, 1]);
+            assert!(color == ColorFamily::Orange || color == ColorFamily::Pink);
+        }
+    }
+
+    #[test]
+    fn test_gen_scatter_momentum() {
+        let rng = ThreadTetrisRng::new();
+
+        for _ in 0..100 {
+            assert!((0..=3).contains(&rng.gen_scatter_momentum(3)));
+        }
+        assert_eq!(rng.gen_scatter_momentum(0), 0);
+    }
+
+    #[test]
+    fn test_gen_particle_velocity() {
+        let rng = ThreadTetrisRng::new();
+
+        for _ in 0..100 {
+            let (x, y) = rng.gen_particle_velocity(2.0);
+            assert!((-2.0..=2.0).contains(&x));
+            assert!((-2.0..=0.0).contains(&y));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_seeded_tetris_rng {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_rolls() {
+        let rng = SeededTetrisRng::new(7);
+        let other_rng = SeededTetrisRng::new(7);
+
+        for _ in 0..20 {
+            assert_eq!(rng.gen_do_go_right(), other_rng.gen_do_go_right());
+            assert_eq!(
+                rng.get_random_row_order(10),
+                other_rng.get_random_row_order(10)
+            );
+            assert_eq!(
+                rng.generate_piece_index(&[1, 1, 1, 1, 1, 2, 1]),
+                other_rng.generate_piece_index(&[1, 1, 1, 1, 1, 2, 1])
+            );
+            assert_eq!(
+                rng.generate_block_color(&[1, 1, 1, 2, 0, 0, 0, 0]),
+                other_rng.generate_block_color(&[1, 1, 1, 2, 0, 0, 0, 0])
+            );
+            assert_eq!(
+                rng.gen_scatter_momentum(5),
+                other_rng.gen_scatter_momentum(5)
+            );
+            assert_eq!(
+                rng.gen_particle_velocity(1.5),
+                other_rng.gen_particle_velocity(1.5)
+            );
+        }
+    }
+
+    #[test]
+    fn different_seeds_different_rolls() {
+        let rng = SeededTetrisRng::new(1);
+        let other_rng = SeededTetrisRng::new(2);
+
+        assert_ne!(
+            rng.get_random_row_order(100),
+            other_rng.get_random_row_order(100)
+        );
+    }
+
+    #[test]
+    fn generate_piece_index_skips_zero_weights() {
+        let rng = SeededTetrisRng::new(3);
+
+        for _ in 0..100 {
+            assert_eq!(rng.generate_piece_index(&[0, 3, 0]), 1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_scripted_tetris_rng {
+    use super::*;
+
+    fn get_scripted_rng() -> ScriptedTetrisRng<MockTetrisRng> {
+        let mut rng = MockTetrisRng::new();
+        rng.set_piece_index(6);
+        rng.set_block_color(ColorFamily::Cyan);
+        rng.set_go_right(true);
+
+        ScriptedTetrisRng::new(
+            rng,
+            Vec::from([(2, ColorFamily::Blue), (4, ColorFamily::Pink)]),
+        )
+    }
+
+    #[test]
+    fn follows_script_then_wrapped_rng() {
+        let rng = get_scripted_rng();
+
+        assert_eq!(rng.generate_piece_index(&[1; 7]), 2);
+        assert_eq!(rng.generate_block_color(&[1; 8]), ColorFamily::Blue);
+        assert_eq!(rng.generate_piece_index(&[1; 7]), 4);
+        assert_eq!(rng.generate_block_color(&[1; 8]), ColorFamily::Pink);
+        assert_eq!(rng.generate_piece_index(&[1; 7]), 6);
+        assert_eq!(rng.generate_block_color(&[1; 8]), ColorFamily::Cyan);
+        assert!(rng.gen_do_go_right());
+    }
+
+    #[test]
+    fn rewind() {
+        let rng = get_scripted_rng();
+        rng.generate_piece_index(&[1; 7]);
+        rng.generate_block_color(&[1; 8]);
+
+        rng.rewind();
+
+        assert_eq!(rng.generate_piece_index(&[1; 7]), 2);
+        assert_eq!(rng.generate_block_color(&[1; 8]), ColorFamily::Blue);
     }
 }
 
@@ -217,8 +482,8 @@ mod test_mock_tetris_rng {
 
         assert_eq!(rng.go_right, false);
         assert_eq!(rng.row_order.len(), 0);
-        assert_eq!(rng.block_type, BlockType::LBlock);
-        assert_eq!(rng.block_color, RED);
+        assert_eq!(rng.piece_index, 0);
+        assert_eq!(rng.block_color, ColorFamily::Red);
     }
 
     #[test]
@@ -228,12 +493,12 @@ mod test_mock_tetris_rng {
         rng.set_go_right(true);
         rng.set_random_row_order(Vec::from([1, 2, 3]));
         rng.set_block_type(BlockType::SquareBlock);
-        rng.set_block_color(BLUE);
+        rng.set_block_color(ColorFamily::Blue);
 
         assert_eq!(rng.go_right, true);
         assert_eq!(rng.row_order, Vec::from([1, 2, 3]));
-        assert_eq!(rng.block_type, BlockType::SquareBlock);
-        assert_eq!(rng.block_color, BLUE);
+        assert_eq!(rng.piece_index, 2);
+        assert_eq!(rng.block_color, ColorFamily::Blue);
     }
 
     #[test]
@@ -261,38 +526,51 @@ mod test_mock_tetris_rng {
     }
 
     #[test]
-    fn shuffle_fields() {
-        let rng: MockTetrisRng = MockTetrisRng::new();
-        let field1 = Field::new(0, 0, RED, 0);
-        let field2 = Field::new(0, 1, YELLOW, 1);
-        let field3 = Field::new(1, 1, BLUE, 2);
-        let fields_orig: Vec<&Field> = Vec::from([&field1, &field2, &field3]);
-        let mut fields_to_shuffle = fields_orig.clone();
+    fn generate_piece_index() {
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+
+        assert_eq!(rng.generate_piece_index(&[1, 1]), 0);
 
-        rng.shuffle_fields(&mut fields_to_shuffle);
+        rng.set_piece_index(1);
 
-        assert_eq!(fields_to_shuffle, fields_orig);
+        assert_eq!(rng.generate_piece_index(&[1, 1]), 1);
+
+        rng.set_block_type(BlockType::IBlock);
+
+        assert_eq!(rng.generate_piece_index(&[1, 1]), 5);
     }
 
     #[test]
-    fn generate_block_type() {
+    fn generate_block_color() {
         let mut rng: MockTetrisRng = MockTetrisRng::new();
 
-        assert_eq!(rng.generate_block_type(), BlockType::LBlock);
+        assert_eq!(rng.generate_block_color(&[1, 1]), ColorFamily::Red);
 
-        rng.set_block_type(BlockType::SquareBlock);
+        rng.set_block_color(ColorFamily::Blue);
 
-        assert_eq!(rng.generate_block_type(), BlockType::SquareBlock);
+        assert_eq!(rng.generate_block_color(&[1, 1]), ColorFamily::Blue);
     }
 
     #[test]
-    fn generate_block_color() {
+    fn gen_scatter_momentum() {
+        let mut rng: MockTetrisRng = MockTetrisRng::new();
+
+        assert_eq!(rng.gen_scatter_momentum(4), 0);
+
+        rng.set_scatter_momentum(3);
+
+        assert_eq!(rng.gen_scatter_momentum(4), 3);
+        assert_eq!(rng.gen_scatter_momentum(2), 2);
+    }
+
+    #[test]
+    fn gen_particle_velocity() {
         let mut rng: MockTetrisRng = MockTetrisRng::new();
 
-        assert_eq!(rng.generate_block_color(), RED);
+        assert_eq!(rng.gen_particle_velocity(1.0), (0.0, 0.0));
 
-        rng.set_block_color(BLUE);
+        rng.set_particle_velocity((0.5, -1.0));
 
-        assert_eq!(rng.generate_block_color(), BLUE);
+        assert_eq!(rng.gen_particle_velocity(1.0), (0.5, -1.0));
     }
 }